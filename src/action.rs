@@ -1,22 +1,47 @@
-use crate::database::{HistoryData, KanjiStatistic};
-use crate::detect::comictextdetector::{DETECT_STATE, combine_overlapping_rects, filter_rects};
-use crate::event::event::{update_backend_status, update_image_display, update_screenshot_result};
+use crate::database::{
+    ActivityRange, DailyActivity, HistoryData, KanjiStatistic, OccurrenceKind, OcrCorrection,
+    Substitution, WordStatistic,
+};
+use crate::detect::comictextdetector::{
+    Boxes, DETECT_STATE, combine_overlapping_rects, filter_rects,
+};
+use crate::error::OcrError;
+use crate::event::event::{
+    show_notification, update_backend_status, update_image_display, update_screenshot_result,
+};
+use crate::jpn::difficulty::get_difficulty;
 use crate::jpn::{JpnData, dict, get_jpn_data};
 use crate::ocr::OcrBackend::MangaOcr;
-use crate::ocr::manga_ocr::get_kanji_top_text;
-use crate::ocr::{BackendResult, OcrBackend};
+use crate::ocr::correction::{self, Correction, Substitutions};
+use crate::ocr::ensemble;
+use crate::ocr::image_model::{ImageModelConfig, run_image_model};
+use crate::ocr::layout::{CharRect, estimate_char_rects};
+use crate::ocr::manga_ocr::{Decoding, MANGA_OCR};
+use crate::ocr::preprocess::{PreprocessConfig, get_scaled_size};
+use crate::ocr::{
+    BackendResult, ConfidenceFilter, LowConfidenceAction, OcrBackend, get_mean_confidence,
+};
 use crate::translation::google::translate;
+use crate::ui::backend_status::{Backend, BackendStatus};
 use crate::ui::id_item::{IdItem, IdItemVec};
-use crate::ui::settings::{Backend, BackendStatus, PreprocessConfig};
+use crate::ui::notification::NotificationLevel;
+use crate::ui::shutdown::TASK_TRACKER;
 use crate::ui::update_queue::enqueue_update;
 use crate::{OcrApp, database};
 use ::serde::{Deserialize, Serialize};
 use egui::Context;
 use futures::future::join_all;
+use image::imageops::FilterType;
 use image::{DynamicImage, GenericImage};
 use imageproc::rect::Rect;
 use itertools::Itertools;
 use log::info;
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+const MAX_EXAMPLE_SENTENCES: usize = 10;
+/// Longest edge of the debug images, upscaled steps are shown smaller than their resolution.
+const MAX_DEBUG_IMAGE_SIZE: u32 = 4096;
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Default, Clone)]
 pub struct ScreenshotParameter {
@@ -39,22 +64,32 @@ impl Default for OcrPipeline {
                 max_box_count: 10,
                 use_capture_image_as_output: true,
             },
-            OcrPipelineStep::OcrStep { backend: MangaOcr },
+            OcrPipelineStep::OcrStep {
+                backend: MangaOcr,
+                decoding: Decoding::default(),
+                confidence_filter: ConfidenceFilter::default(),
+                auto_correct: true,
+            },
         ];
         OcrPipeline(IdItem::from_vec(steps))
     }
 }
 
-pub async fn run_ocr(captured_image: DynamicImage, OcrPipeline(pipeline_steps): OcrPipeline) {
+pub async fn run_ocr(
+    captured_image: DynamicImage,
+    OcrPipeline(pipeline_steps): OcrPipeline,
+) -> Result<(), OcrError> {
     let width = captured_image.width();
     let height = captured_image.height();
     let mut images = vec![SubImage {
         x: 0,
         y: 0,
+        scale: 1.0,
         image: captured_image.clone(),
     }];
     let pipeline_steps = get_pipeline_steps_for_ocr(pipeline_steps);
     let max_index = pipeline_steps.len();
+    load_models(&pipeline_steps).await?;
 
     show_debug_image(
         0,
@@ -69,7 +104,12 @@ pub async fn run_ocr(captured_image: DynamicImage, OcrPipeline(pipeline_steps):
         images = step
             .item
             .run_ocr_pipeline_step(&captured_image, &images)
-            .await;
+            .await
+            .map_err(|error| OcrError::Step {
+                index: index + 1,
+                name: step.item.name(),
+                error: Box::new(error),
+            })?;
         let image_index = index + 1;
         if image_index != max_index {
             show_debug_image(
@@ -82,13 +122,14 @@ pub async fn run_ocr(captured_image: DynamicImage, OcrPipeline(pipeline_steps):
             );
         }
     }
+    Ok(())
 }
 
 fn get_pipeline_steps_for_ocr(
     pipeline_steps: Vec<IdItem<OcrPipelineStep>>,
 ) -> Vec<IdItem<OcrPipelineStep>> {
     let pipeline_steps = if let Some(IdItem {
-        item: OcrPipelineStep::OcrStep { .. },
+        item: OcrPipelineStep::OcrStep { .. } | OcrPipelineStep::EnsembleStep { .. },
         ..
     }) = pipeline_steps.last()
     {
@@ -96,7 +137,12 @@ fn get_pipeline_steps_for_ocr(
         pipeline_steps
     } else {
         let mut steps = pipeline_steps.clone();
-        steps.push_item(OcrPipelineStep::OcrStep { backend: MangaOcr });
+        steps.push_item(OcrPipelineStep::OcrStep {
+            backend: MangaOcr,
+            decoding: Decoding::default(),
+            confidence_filter: ConfidenceFilter::default(),
+            auto_correct: true,
+        });
         steps
     };
 
@@ -106,6 +152,41 @@ fn get_pipeline_steps_for_ocr(
         .collect()
 }
 
+/// Runs model work on the blocking threads, building a session takes seconds and would block
+/// the async runtime.
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, OcrError> {
+    TASK_TRACKER
+        .spawn_blocking(f)
+        .await
+        .map_err(|err| OcrError::Task(err.to_string()))
+}
+
+/// Loads the unloaded models the pipeline uses, the status shows the loading.
+async fn load_models(pipeline_steps: &[IdItem<OcrPipelineStep>]) -> Result<(), OcrError> {
+    let uses_detector = pipeline_steps
+        .iter()
+        .any(|x| matches!(x.item, OcrPipelineStep::BoxDetection { .. }));
+    let uses_manga_ocr = pipeline_steps.iter().any(|x| match &x.item {
+        OcrPipelineStep::OcrStep { backend, .. } => *backend == MangaOcr,
+        OcrPipelineStep::EnsembleStep { backends, .. } => backends.contains(&MangaOcr),
+        _ => false,
+    });
+
+    if uses_detector && !DETECT_STATE.model.is_loaded() {
+        update_backend_status(Backend::Detector, BackendStatus::Loading);
+        let result = run_blocking(|| DETECT_STATE.model.load()).await?;
+        update_backend_status(Backend::Detector, BackendStatus::from_result(&result));
+    }
+    if uses_manga_ocr && !MANGA_OCR.is_loaded() {
+        update_backend_status(Backend::MangaOcr, BackendStatus::Loading);
+        let result = run_blocking(|| MANGA_OCR.load()).await?;
+        update_backend_status(Backend::MangaOcr, BackendStatus::from_result(&result));
+    }
+    Ok(())
+}
+
 fn show_debug_image(
     index: usize,
     max_index: usize,
@@ -118,12 +199,38 @@ fn show_debug_image(
         return;
     }
 
+    // Shown in the resolution of the most upscaled image, the others are enlarged to match,
+    // limited to a canvas of MAX_DEBUG_IMAGE_SIZE
+    let max_scale = MAX_DEBUG_IMAGE_SIZE as f32 / width.max(height).max(1) as f32;
+    let scale = sub_images
+        .iter()
+        .map(|x| x.scale)
+        .fold(1.0, f32::max)
+        .min(max_scale.max(1.0));
+    let (width, height) = get_scaled_size(width, height, scale);
     let mut image = DynamicImage::new(width, height, sub_images[0].image.color());
 
     for sub_image in sub_images {
-        let dynamic_image = &sub_image.image;
+        let dynamic_image = if sub_image.scale == scale {
+            Cow::Borrowed(&sub_image.image)
+        } else {
+            let (width, height) = get_scaled_size(
+                sub_image.image.width(),
+                sub_image.image.height(),
+                scale / sub_image.scale,
+            );
+            Cow::Owned(
+                sub_image
+                    .image
+                    .resize_exact(width, height, FilterType::Triangle),
+            )
+        };
 
-        let _ = image.copy_from(dynamic_image, sub_image.x as u32, sub_image.y as u32);
+        let _ = image.copy_from(
+            dynamic_image.as_ref(),
+            (sub_image.x as f32 * scale).round() as u32,
+            (sub_image.y as f32 * scale).round() as u32,
+        );
     }
 
     enqueue_update(move |ctx, app| {
@@ -134,6 +241,7 @@ fn show_debug_image(
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub enum OcrPipelineStep {
     ImageProcessing(PreprocessConfig),
+    ImageModel(ImageModelConfig),
     BoxDetection {
         threshold: f32,
         max_box_count: usize,
@@ -141,20 +249,46 @@ pub enum OcrPipelineStep {
     },
     OcrStep {
         backend: OcrBackend,
+        #[serde(default)]
+        decoding: Decoding,
+        #[serde(default)]
+        confidence_filter: ConfidenceFilter,
+        /// Enabled for the steps saved before the correction existed
+        #[serde(default = "default_true")]
+        auto_correct: bool,
+    },
+    EnsembleStep {
+        backends: Vec<OcrBackend>,
+        #[serde(default)]
+        decoding: Decoding,
+        #[serde(default)]
+        confidence_filter: ConfidenceFilter,
+        /// Enabled for the steps saved before the correction existed
+        #[serde(default = "default_true")]
+        auto_correct: bool,
     },
 }
 
+fn default_true() -> bool {
+    true
+}
+
 impl OcrPipelineStep {
     pub async fn run_ocr_pipeline_step(
         &self,
         capture_image: &DynamicImage,
         images: &Vec<SubImage>,
-    ) -> Vec<SubImage> {
-        match self {
+    ) -> Result<Vec<SubImage>, OcrError> {
+        let images = match self {
             OcrPipelineStep::ImageProcessing(config) => images
                 .iter()
                 .map(|image| run_image_processing(image, config))
                 .collect(),
+            OcrPipelineStep::ImageModel(config) => {
+                // The model is loaded on its first use
+                let (images, config) = (images.clone(), config.clone());
+                run_blocking(move || run_image_model_step(&images, &config)).await?
+            }
             OcrPipelineStep::BoxDetection {
                 threshold,
                 max_box_count,
@@ -165,47 +299,123 @@ impl OcrPipelineStep {
                 .flatten()
                 .map(|sub_image: SubImage| {
                     if *use_capture_image_as_output {
-                        let SubImage { x, y, image } = sub_image;
-                        let crop = capture_image.crop_imm(
-                            x as u32,
-                            y as u32,
-                            image.width(),
-                            image.height(),
-                        );
-                        SubImage { x, y, image: crop }
+                        let rect = sub_image.get_rect();
+                        SubImage {
+                            x: sub_image.x,
+                            y: sub_image.y,
+                            scale: 1.0,
+                            image: get_cutout_image(capture_image, &rect),
+                        }
                     } else {
                         sub_image
                     }
                 })
                 .collect(),
-            OcrPipelineStep::OcrStep { backend } => run_ocr_step(images, backend).await,
-        }
+            OcrPipelineStep::OcrStep {
+                backend,
+                decoding,
+                confidence_filter,
+                auto_correct,
+            } => {
+                let backends = std::slice::from_ref(backend);
+                run_ocr_step(images, backends, decoding, confidence_filter, *auto_correct).await?
+            }
+            OcrPipelineStep::EnsembleStep {
+                backends,
+                decoding,
+                confidence_filter,
+                auto_correct,
+            } => run_ocr_step(images, backends, decoding, confidence_filter, *auto_correct).await?,
+        };
+        Ok(images)
     }
 
     pub fn name(&self) -> &'static str {
         match self {
-            OcrPipelineStep::ImageProcessing(config) => match config {
-                PreprocessConfig::SharpenGaussian { .. } => "Sharpen Gaussian",
-            },
+            OcrPipelineStep::ImageProcessing(config) => config.name(),
+            OcrPipelineStep::ImageModel(_) => "ONNX Image Model",
             OcrPipelineStep::BoxDetection { .. } => "Box Detection",
-            OcrPipelineStep::OcrStep { .. } => "OCR Step",
+            OcrPipelineStep::OcrStep { backend, .. } => match backend {
+                OcrBackend::MangaOcr => "OCR Step",
+                OcrBackend::Tesseract(_) => "Tesseract OCR Step",
+                OcrBackend::Ctc(_) => "CTC OCR Step",
+            },
+            OcrPipelineStep::EnsembleStep { .. } => "Ensemble OCR Step",
         }
     }
 }
 
 fn run_image_processing(sub_image: &SubImage, config: &PreprocessConfig) -> SubImage {
-    let image = preprocess_image(&sub_image.image, config);
     SubImage {
         x: sub_image.x,
         y: sub_image.y,
-        image,
+        scale: sub_image.scale * config.get_scale(),
+        image: config.apply(&sub_image.image),
+    }
+}
+
+fn run_image_model_step(images: &[SubImage], config: &ImageModelConfig) -> Vec<SubImage> {
+    let result: anyhow::Result<Vec<SubImage>> = images
+        .iter()
+        .map(|x| run_image_model_on_image(x, config))
+        .collect();
+
+    match result {
+        Ok(images) => {
+            update_backend_status(Backend::ImageModel, BackendStatus::Ready);
+            images
+        }
+        Err(err) => {
+            // Degraded mode, the next steps run on the images without the model
+            let message = format!("Image model failed, skipping the step: {err:#}");
+            log::warn!("{message}");
+            show_notification(NotificationLevel::Warning, message);
+            update_backend_status(
+                Backend::ImageModel,
+                BackendStatus::Error(format!("{err:#}")),
+            );
+            images.to_vec()
+        }
     }
 }
 
+fn run_image_model_on_image(
+    sub_image: &SubImage,
+    config: &ImageModelConfig,
+) -> anyhow::Result<SubImage> {
+    let image = run_image_model(&sub_image.image, config)?;
+    Ok(SubImage {
+        x: sub_image.x,
+        y: sub_image.y,
+        scale: sub_image.scale * image.width() as f32 / sub_image.image.width().max(1) as f32,
+        image,
+    })
+}
+
 fn run_box_detection(sub_image: &SubImage, max_box_count: usize, threshold: f32) -> Vec<SubImage> {
+    let boxes = DETECT_STATE.run_model(&sub_image.image, threshold);
+    get_box_images(sub_image, boxes, max_box_count)
+}
+
+/// Cutouts of the detected boxes, the whole region when the detection failed.
+fn get_box_images(
+    sub_image: &SubImage,
+    boxes: anyhow::Result<Vec<Boxes>>,
+    max_box_count: usize,
+) -> Vec<SubImage> {
     let image = &sub_image.image;
 
-    let boxes = DETECT_STATE.run_model(image, threshold);
+    let boxes = match boxes {
+        Ok(boxes) => boxes,
+        Err(err) => {
+            // Degraded mode, the OCR runs on the whole region
+            let message = format!("Box detection failed, using the whole region: {err:#}");
+            log::warn!("{message}");
+            show_notification(NotificationLevel::Warning, message);
+            update_backend_status(Backend::Detector, BackendStatus::Error(format!("{err:#}")));
+            return vec![sub_image.clone()];
+        }
+    };
     let boxes = combine_overlapping_rects(boxes);
     let boxes = filter_rects(boxes, max_box_count);
     boxes
@@ -213,85 +423,246 @@ fn run_box_detection(sub_image: &SubImage, max_box_count: usize, threshold: f32)
         .map(|x| x.get_rect(image))
         .map(|x| (x, get_cutout_image(image, &x)))
         .map(|(rect, img)| SubImage {
-            x: sub_image.x + rect.left(),
-            y: sub_image.y + rect.top(),
+            x: sub_image.x + (rect.left() as f32 / sub_image.scale).round() as i32,
+            y: sub_image.y + (rect.top() as f32 / sub_image.scale).round() as i32,
+            scale: sub_image.scale,
             image: img,
         })
         .collect()
 }
 
-async fn run_ocr_step(images: &Vec<SubImage>, backend: &OcrBackend) -> Vec<SubImage> {
-    let images_ref: Vec<&DynamicImage> = images.iter().map(|x| &x.image).collect();
+async fn run_ocr_step(
+    images: &Vec<SubImage>,
+    backends: &[OcrBackend],
+    decoding: &Decoding,
+    confidence_filter: &ConfidenceFilter,
+    auto_correct: bool,
+) -> Result<Vec<SubImage>, OcrError> {
+    // CTC models are loaded on their first use
+    let result = {
+        let (images, backends, decoding) = (images.clone(), backends.to_vec(), decoding.clone());
+        run_blocking(move || run_backends(&images, &backends, &decoding)).await??
+    };
+
+    let result: Vec<(&SubImage, BackendResult)> = images.iter().zip(result).collect();
 
-    update_backend_status(Backend::MangaOcr, BackendStatus::Running);
+    let mut ocr_results = get_ocr_results(result, auto_correct).await;
+    for ocr_result in &mut ocr_results {
+        ocr_result.confidence_threshold = confidence_filter.threshold;
+    }
+    if confidence_filter.action == LowConfidenceAction::Drop {
+        ocr_results.retain(|x| !x.is_low_confidence());
+    }
 
-    let result: Vec<BackendResult> = backend.run_backend(images_ref).unwrap();
+    let mut screenshot_result = ScreenshotResult {
+        ocr_results,
+        ..Default::default()
+    };
+    screenshot_result.update_difficulty(&load_known_words());
 
-    update_backend_status(Backend::MangaOcr, BackendStatus::Ready);
+    // The results are shown even if they can not be stored
+    let store_result = store_ocr_results(&screenshot_result.ocr_results);
 
-    let result: Vec<(Rect, BackendResult)> = images
-        .iter()
-        .map(|sub| Rect::at(sub.x, sub.y).of_size(sub.image.width(), sub.image.height()))
-        .zip(result)
-        .collect();
+    enqueue_update(|ctx: &Context, state: &mut OcrApp| {
+        update_screenshot_result(ctx, state, screenshot_result)
+    });
 
-    let ocr_results = get_ocr_results(result).await;
+    store_result?;
+    Ok(images.iter().map(|x| x.clone()).collect())
+}
 
-    for ocr_result in &ocr_results {
+/// Stores the history, activity and statistics of the recognised texts.
+fn store_ocr_results(ocr_results: &[ResultData]) -> Result<(), OcrError> {
+    for ocr_result in ocr_results {
         //Store OCR
-        database::store_ocr(&ocr_result.ocr).expect("Failed to store ocr");
+        let history_id = database::store_ocr(&ocr_result.ocr).map_err(OcrError::storage("ocr"))?;
+
+        //Store capture for the activity
+        let char_count = ocr_result
+            .ocr
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .count();
+        database::store_capture(history_id, char_count).map_err(OcrError::storage("capture"))?;
+
+        if let Some(difficulty) = ocr_result.difficulty {
+            database::store_ocr_difficulty(&ocr_result.ocr, difficulty)
+                .map_err(OcrError::storage("difficulty"))?;
+        }
 
         for jpn_data in ocr_result.jpn.iter().flatten() {
             if jpn_data.has_kanji_data() {
                 //Store Kanji statistic
                 database::init_kanji_statistic(&jpn_data.get_kanji())
-                    .expect("Failed to store kanji");
+                    .map_err(OcrError::storage("kanji statistic"))?;
+
+                //Store word occurrence
+                database::store_word_occurrence(
+                    &jpn_data.get_dictionary_form(),
+                    OccurrenceKind::Seen,
+                    Some(history_id),
+                )
+                .map_err(OcrError::storage("word occurrence"))?;
             }
         }
     }
+    Ok(())
+}
 
-    enqueue_update(|ctx: &Context, state: &mut OcrApp| {
-        update_screenshot_result(ctx, state, ScreenshotResult { ocr_results })
-    });
+/// Runs all backends on the images, the results of several backends are combined by voting.
+/// Fails only if no backend returned a result.
+fn run_backends(
+    images: &[SubImage],
+    backends: &[OcrBackend],
+    decoding: &Decoding,
+) -> Result<Vec<BackendResult>, OcrError> {
+    let images_ref: Vec<&DynamicImage> = images.iter().map(|x| &x.image).collect();
 
-    images.iter().map(|x| x.clone()).collect()
+    let mut results: Vec<(&str, Vec<BackendResult>)> = vec![];
+    let mut errors: Vec<String> = vec![];
+    for backend in backends {
+        match run_backend(backend, images_ref.clone(), decoding) {
+            Ok(result) => results.push((backend.get_label(), result)),
+            Err(err) => errors.push(format!("{}: {err:#}", backend.get_label())),
+        }
+    }
+
+    if results.is_empty() && !errors.is_empty() {
+        return Err(OcrError::Backends(errors));
+    }
+    for error in errors {
+        show_notification(
+            NotificationLevel::Warning,
+            format!("OCR backend failed, {error}"),
+        );
+    }
+
+    let result = if results.len() > 1 {
+        ensemble::vote(&results, images.len())
+    } else {
+        results.pop().map(|(_, x)| x).unwrap_or_default()
+    };
+    Ok(result)
+}
+
+fn run_backend(
+    backend: &OcrBackend,
+    images: Vec<&DynamicImage>,
+    decoding: &Decoding,
+) -> anyhow::Result<Vec<BackendResult>> {
+    let status = Backend::from(backend);
+    update_backend_status(status.clone(), BackendStatus::Running);
+
+    let result = backend.run_backend(images, decoding);
+    match &result {
+        Ok(_) => update_backend_status(status, BackendStatus::Ready),
+        Err(err) => {
+            log::error!("{} OCR failed: {err:#}", backend.get_label());
+            update_backend_status(status, BackendStatus::Error(format!("{err:#}")));
+        }
+    }
+    result
 }
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct SubImage {
     pub x: i32,
     pub y: i32,
+    /// Size of the image relative to the capture, above 1 after upscaling
+    pub scale: f32,
     pub image: DynamicImage,
 }
 
-async fn get_ocr_results(cutout_results: Vec<(Rect, BackendResult)>) -> Vec<ResultData> {
-    let mut futures = vec![];
+impl SubImage {
+    /// Area of the image in the capture.
+    fn get_rect(&self) -> Rect {
+        let (width, height) =
+            get_scaled_size(self.image.width(), self.image.height(), 1.0 / self.scale);
+        Rect::at(self.x, self.y).of_size(width, height)
+    }
 
-    for (rect, result) in cutout_results {
-        let ocr = match &result {
-            BackendResult::MangaOcr(top_results) => get_kanji_top_text(&top_results, 0),
-            _ => None,
-        };
-        if let Some(x) = ocr {
-            futures.push(get_result_data(x, rect, result))
+    /// Converts a rect relative to the image to the capture size.
+    fn unscale_char_rect(&self, rect: CharRect) -> CharRect {
+        let unscale = |x: i32| (x as f32 / self.scale).round() as i32;
+        CharRect {
+            x: unscale(rect.x),
+            y: unscale(rect.y),
+            w: unscale(rect.w),
+            h: unscale(rect.h),
         }
     }
-
-    join_all(futures).await.into_iter().collect()
 }
 
-fn preprocess_image(image: &DynamicImage, config: &PreprocessConfig) -> DynamicImage {
-    let gray_image = image.grayscale().to_luma8();
-    let filtered = match config {
-        PreprocessConfig::SharpenGaussian { sigma, amount } => {
-            imageproc::filter::sharpen_gaussian(&gray_image, *sigma, *amount)
+async fn get_ocr_results(
+    cutout_results: Vec<(&SubImage, BackendResult)>,
+    auto_correct: bool,
+) -> Vec<ResultData> {
+    let mut futures = vec![];
+    let substitutions = load_learned_substitutions();
+
+    for (sub, result) in cutout_results {
+        if let Some(mut x) = result.get_text() {
+            let crop_hash = correction::get_crop_hash(&sub.image);
+            let mut char_confidences = result.get_char_confidences();
+
+            let correction = match load_crop_correction(&crop_hash) {
+                Some(corrected) if corrected != x => Some((
+                    corrected,
+                    Correction {
+                        original: x.clone(),
+                        replacements: vec![],
+                    },
+                )),
+                Some(_) => None,
+                None => result
+                    .get_top_results()
+                    .and_then(|top| correction::correct(top, &substitutions, auto_correct)),
+            };
+            let correction = correction.map(|(corrected, correction)| {
+                char_confidences = match result.get_top_results() {
+                    Some(top) if !correction.replacements.is_empty() => {
+                        correction.get_char_confidences(top)
+                    }
+                    // The remembered text was confirmed by the user
+                    _ => vec![1.0; corrected.chars().count()],
+                };
+                x = corrected;
+                correction
+            });
+
+            let rect = sub.get_rect();
+            let char_count = x.chars().filter(|c| !c.is_whitespace()).count();
+            let char_rects = result
+                .get_char_rects()
+                .filter(|x| x.len() == char_count)
+                .unwrap_or_else(|| estimate_char_rects(&sub.image, char_count))
+                .into_iter()
+                .map(|x| sub.unscale_char_rect(x))
+                .collect();
+            futures.push(get_result_data(
+                x,
+                rect,
+                result,
+                char_rects,
+                char_confidences,
+                correction,
+                crop_hash,
+            ))
         }
-    };
+    }
 
-    filtered.into()
+    join_all(futures).await.into_iter().collect()
 }
 
-async fn get_result_data(ocr: String, rect: Rect, result: BackendResult) -> ResultData {
+async fn get_result_data(
+    ocr: String,
+    rect: Rect,
+    result: BackendResult,
+    char_rects: Vec<CharRect>,
+    char_confidences: Vec<f32>,
+    correction: Option<Correction>,
+    crop_hash: String,
+) -> ResultData {
     let jpn: Vec<Vec<JpnData>> = get_jpn_data(&ocr).await;
 
     let translation = match database::load_history_data(&ocr) {
@@ -307,7 +678,15 @@ async fn get_result_data(ocr: String, rect: Rect, result: BackendResult) -> Resu
         ocr,
         translation,
         jpn,
+        // Tesseract has no character confidences but a confidence of the box
+        confidence: get_mean_confidence(&char_confidences).or_else(|| result.get_confidence()),
+        char_confidences,
+        confidence_threshold: 0.0,
         backend_result: result,
+        difficulty: None,
+        char_rects,
+        correction,
+        crop_hash: Some(crop_hash),
     }
 }
 
@@ -324,6 +703,28 @@ fn get_cutout_image(capture_image: &DynamicImage, rect: &Rect) -> DynamicImage {
 #[serde(default)]
 pub struct ScreenshotResult {
     pub ocr_results: Vec<ResultData>,
+    pub difficulty: Option<f32>,
+}
+
+impl ScreenshotResult {
+    pub fn update_difficulty(&mut self, known_words: &HashSet<String>) {
+        for ocr_result in &mut self.ocr_results {
+            ocr_result.difficulty = get_difficulty(ocr_result.jpn.iter().flatten(), known_words);
+        }
+
+        self.difficulty = get_difficulty(
+            self.ocr_results.iter().flat_map(|x| x.jpn.iter().flatten()),
+            known_words,
+        );
+    }
+
+    /// The OCR text of all boxes is the same, e.g. the capture was repeated on the same page.
+    pub fn has_same_text(&self, other: &ScreenshotResult) -> bool {
+        self.ocr_results
+            .iter()
+            .map(|x| &x.ocr)
+            .eq(other.ocr_results.iter().map(|x| &x.ocr))
+    }
 }
 
 #[derive(Deserialize, Serialize, Default, Clone, PartialEq)]
@@ -337,6 +738,17 @@ pub struct ResultData {
     pub translation: String,
     pub jpn: Vec<Vec<JpnData>>,
     pub backend_result: BackendResult,
+    pub difficulty: Option<f32>,
+    /// Estimated positions of the non whitespace characters relative to the box
+    pub char_rects: Vec<CharRect>,
+    /// Geometric mean of the character probabilities
+    pub confidence: Option<f32>,
+    pub char_confidences: Vec<f32>,
+    pub confidence_threshold: f32,
+    /// Automatic correction of the OCR text, `None` if the text was kept or the correction undone
+    pub correction: Option<Correction>,
+    /// Hash of the text box image to remember corrections of the same crop
+    pub crop_hash: Option<String>,
 }
 
 impl std::fmt::Debug for ResultData {
@@ -365,6 +777,35 @@ impl ResultData {
             .nth(index.rem_euclid(count) as usize)
     }
 
+    /// Index of the non whitespace character at the position relative to the box.
+    pub fn get_char_index_at(&self, x: f32, y: f32) -> Option<usize> {
+        self.char_rects.iter().position(|rect| rect.contains(x, y))
+    }
+
+    /// The segmented word containing the character together with its first character index.
+    pub fn get_jpn_data_at_char(&self, char_index: usize) -> Option<(usize, &JpnData)> {
+        let mut start = 0;
+        for jpn_data in self.jpn.iter().flatten() {
+            let end = start + jpn_data.words.len();
+            if (start..end).contains(&char_index) {
+                return Some((start, jpn_data));
+            }
+            start = end;
+        }
+        None
+    }
+
+    pub fn is_low_confidence(&self) -> bool {
+        self.confidence
+            .is_some_and(|x| x < self.confidence_threshold)
+    }
+
+    pub fn is_low_confidence_char(&self, char_index: usize) -> bool {
+        self.char_confidences
+            .get(char_index)
+            .is_some_and(|x| *x < self.confidence_threshold)
+    }
+
     fn get_jpn_data_with_info(&self) -> impl Iterator<Item = &JpnData> {
         self.jpn.iter().flatten().filter(|y| y.has_kanji_data())
     }
@@ -381,14 +822,19 @@ pub async fn get_translation(input: &str) -> String {
     let elapsed = now.elapsed();
     info!("End get_translation elapsed: {elapsed:.2?}");
 
-    let translation = translate(&input)
-        .await
+    let result = translate(&input).await;
+    update_backend_status(Backend::Translator, BackendStatus::from_result(&result));
+    let translation = result
         .map_err(|err| err.to_string())
         .unwrap_or_else(|err_string| err_string)
         .trim()
         .to_string();
 
-    database::store_ocr_translation(&input, &translation).expect("Failed to store history data");
+    if let Err(err) = database::store_ocr_translation(&input, &translation) {
+        let error = OcrError::storage("translation")(err);
+        log::error!("{error}");
+        show_notification(NotificationLevel::Error, error.to_string());
+    }
 
     translation
 }
@@ -400,8 +846,102 @@ pub fn load_history() -> Vec<HistoryData> {
     })
 }
 
-pub fn increment_kanji_statistic(kanji: &str) -> KanjiStatistic {
-    database::increment_kanji_statistic(kanji).expect("Failed to increment kanji statistic")
+pub fn increment_kanji_statistic(kanji: &str) -> Option<KanjiStatistic> {
+    database::increment_kanji_statistic(kanji)
+        .map_err(OcrError::storage("kanji statistic"))
+        .inspect_err(|error| {
+            log::error!("{error}");
+            show_notification(NotificationLevel::Error, error.to_string());
+        })
+        .ok()
+}
+
+/// Records a lookup of the word in the sentence `ocr`.
+pub fn record_word_lookup(word: &str, ocr: &str) {
+    let history_id = database::load_history_data(ocr).ok().map(|x| x.id);
+
+    if let Err(err) = database::store_word_occurrence(word, OccurrenceKind::Lookup, history_id) {
+        log::error!("Failed to store lookup of '{word}': {err}");
+    }
+}
+
+pub fn load_word_statistic(word: &str) -> WordStatistic {
+    database::load_word_statistic(word).unwrap_or_else(|err| {
+        log::error!("Failed to load word statistic: {err}");
+        WordStatistic {
+            word: word.to_string(),
+            ..Default::default()
+        }
+    })
+}
+
+pub fn load_example_sentences(word: &str) -> Vec<String> {
+    database::load_example_sentences(word, MAX_EXAMPLE_SENTENCES).unwrap_or_else(|err| {
+        log::error!("Failed to load example sentences: {err}");
+        vec![]
+    })
+}
+
+/// Remembers a correction of the user, single character changes are learned as substitution.
+pub fn store_ocr_correction(original: &str, corrected: &str, crop_hash: Option<&str>) {
+    let substitution = correction::get_substitution(original, corrected);
+    let substitution = substitution
+        .as_ref()
+        .map(|(from, to)| (from.as_str(), to.as_str()));
+
+    if let Err(err) = database::store_ocr_correction(original, corrected, crop_hash, substitution) {
+        log::error!("Failed to store correction '{original}' -> '{corrected}': {err}");
+    }
+}
+
+fn load_crop_correction(crop_hash: &str) -> Option<String> {
+    database::load_crop_correction(crop_hash).unwrap_or_else(|err| {
+        log::error!("Failed to load crop correction: {err}");
+        None
+    })
+}
+
+/// The most frequent replacement of each character.
+fn load_learned_substitutions() -> Substitutions {
+    let mut substitutions = Substitutions::new();
+    for substitution in load_substitutions() {
+        substitutions
+            .entry(substitution.from)
+            .or_insert(substitution.to);
+    }
+    substitutions
+}
+
+pub fn load_substitutions() -> Vec<Substitution> {
+    database::load_substitutions().unwrap_or_else(|err| {
+        log::error!("Failed to load substitutions: {err}");
+        vec![]
+    })
+}
+
+pub fn load_ocr_corrections() -> Vec<OcrCorrection> {
+    database::load_ocr_corrections().unwrap_or_else(|err| {
+        log::error!("Failed to load corrections: {err}");
+        vec![]
+    })
+}
+
+pub fn delete_ocr_correction(id: i32) {
+    if let Err(err) = database::delete_ocr_correction(id) {
+        log::error!("Failed to delete correction {id}: {err}");
+    }
+}
+
+pub fn delete_substitution(from: &str, to: &str) {
+    if let Err(err) = database::delete_substitution(from, to) {
+        log::error!("Failed to delete substitution '{from}' -> '{to}': {err}");
+    }
+}
+
+pub fn load_daily_activity(range: &ActivityRange) -> anyhow::Result<Vec<DailyActivity>> {
+    database::load_daily_activity(range).inspect_err(|err| {
+        log::error!("Failed to load activity: {err}");
+    })
 }
 
 pub(crate) fn load_statistic() -> Vec<KanjiStatistic> {
@@ -411,6 +951,28 @@ pub(crate) fn load_statistic() -> Vec<KanjiStatistic> {
     })
 }
 
+pub fn load_known_words() -> HashSet<String> {
+    database::load_known_words()
+        .unwrap_or_else(|err| {
+            log::error!("Failed to load known words: {err}");
+            vec![]
+        })
+        .into_iter()
+        .collect()
+}
+
+pub fn set_word_known(word: &str, known: bool) {
+    let result = if known {
+        database::store_known_word(word)
+    } else {
+        database::remove_known_word(word)
+    };
+
+    if let Err(err) = result {
+        log::error!("Failed to update known word '{word}': {err}");
+    }
+}
+
 pub async fn get_kanji_jpn_data(kanji: &str) -> Option<JpnData> {
     let vec = get_jpn_data(kanji).await;
     vec.into_iter().flatten().next()
@@ -419,15 +981,173 @@ pub async fn get_kanji_jpn_data(kanji: &str) -> Option<JpnData> {
 #[cfg(test)]
 mod tests {
     use crate::OcrApp;
-    use crate::action::{OcrPipeline, run_ocr};
+    use crate::action::{
+        OcrPipeline, OcrPipelineStep, ResultData, ScreenshotResult, SubImage, get_box_images,
+        run_image_model_step, run_ocr,
+    };
     use crate::ocr::BackendResult;
+    use crate::ocr::image_model::ImageModelConfig;
     use crate::ocr::manga_ocr::KanjiConf;
+    use crate::ocr::manga_ocr::{Decoding, MANGA_OCR};
+    use crate::ocr::{ConfidenceFilter, OcrBackend};
+    use crate::ui::backend_status::{Backend, BackendStatus};
+    use crate::ui::id_item::IdItem;
     use crate::ui::update_queue::update_state;
+    use anyhow::anyhow;
     use egui::Context;
     use image::DynamicImage;
+    use serial_test::serial;
     use std::path::Path;
+    use std::time::Duration;
+
+    fn manga_ocr_step() -> OcrPipelineStep {
+        OcrPipelineStep::OcrStep {
+            backend: OcrBackend::MangaOcr,
+            decoding: Decoding::default(),
+            confidence_filter: ConfidenceFilter::default(),
+            auto_correct: false,
+        }
+    }
+
+    #[test]
+    fn test_old_config() {
+        let step: OcrPipelineStep =
+            serde_json::from_str(r#"{"OcrStep":{"backend":"MangaOcr"}}"#).unwrap();
+        assert!(matches!(
+            step,
+            OcrPipelineStep::OcrStep {
+                auto_correct: true,
+                ..
+            }
+        ));
+
+        let step: OcrPipelineStep =
+            serde_json::from_str(r#"{"EnsembleStep":{"backends":["MangaOcr"]}}"#).unwrap();
+        assert!(matches!(
+            step,
+            OcrPipelineStep::EnsembleStep {
+                auto_correct: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_same_text() {
+        let create = |texts: &[&str], difficulty| ScreenshotResult {
+            ocr_results: texts
+                .iter()
+                .map(|x| ResultData {
+                    ocr: x.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            difficulty: Some(difficulty),
+        };
+
+        let page = create(&["吾輩は", "猫である"], 0.5);
+        assert!(page.has_same_text(&create(&["吾輩は", "猫である"], 0.7)));
+        assert!(!page.has_same_text(&create(&["吾輩は"], 0.5)));
+        assert!(!page.has_same_text(&create(&["吾輩は", "犬である"], 0.5)));
+        assert!(!page.has_same_text(&ScreenshotResult::default()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn test_detector_failure() {
+        let image = image::open(Path::new("./input/blurry.png")).expect("Failed to open image");
+        let sub_image = SubImage {
+            x: 0,
+            y: 0,
+            scale: 1.0,
+            image: image.clone(),
+        };
+
+        // The whole region is used without the detector
+        let images = get_box_images(&sub_image, Err(anyhow!("Detector failed")), 10);
+        assert_eq!(images, vec![sub_image]);
+
+        // Start OCR still runs the OCR of the region
+        let pipeline = OcrPipeline(IdItem::from_vec(vec![manga_ocr_step()]));
+        run_ocr(image, pipeline).await.unwrap();
+
+        let ctx = Context::default();
+        let mut app = OcrApp::default();
+        update_state(&ctx, &mut app);
+        assert!(matches!(
+            Backend::Detector.get_status(&ctx),
+            Some(BackendStatus::Error(_))
+        ));
+        assert_eq!(
+            Backend::MangaOcr.get_status(&ctx),
+            Some(BackendStatus::Ready)
+        );
+        assert!(!app.background_rect.screenshot_result.ocr_results.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn test_image_model_failure() {
+        let image = image::open(Path::new("./input/blurry.png")).expect("Failed to open image");
+        let config = ImageModelConfig {
+            model_path: "./input/missing.onnx".to_string(),
+            ..Default::default()
+        };
+
+        // The step is skipped and the images are unchanged
+        let images = vec![SubImage {
+            x: 0,
+            y: 0,
+            scale: 1.0,
+            image: image.clone(),
+        }];
+        assert_eq!(run_image_model_step(&images, &config), images);
+
+        // Start OCR still runs the pipeline with the failing step
+        let pipeline = OcrPipeline(IdItem::from_vec(vec![
+            OcrPipelineStep::ImageModel(config),
+            manga_ocr_step(),
+        ]));
+        run_ocr(image, pipeline).await.unwrap();
+
+        let ctx = Context::default();
+        let mut app = OcrApp::default();
+        update_state(&ctx, &mut app);
+        assert!(matches!(
+            Backend::ImageModel.get_status(&ctx),
+            Some(BackendStatus::Error(_))
+        ));
+        assert_eq!(
+            Backend::MangaOcr.get_status(&ctx),
+            Some(BackendStatus::Ready)
+        );
+        assert!(!app.background_rect.screenshot_result.ocr_results.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn test_reload_after_idle() {
+        let image = image::open(Path::new("./input/blurry.png")).expect("Failed to open image");
+        let pipeline = OcrPipeline(IdItem::from_vec(vec![manga_ocr_step()]));
+
+        run_ocr(image.clone(), pipeline.clone()).await.unwrap();
+        assert!(MANGA_OCR.is_loaded());
+
+        assert!(MANGA_OCR.unload_if_idle(Duration::ZERO));
+        assert!(!MANGA_OCR.is_loaded());
+
+        // The next OCR loads the model again
+        run_ocr(image, pipeline).await.unwrap();
+        assert!(MANGA_OCR.is_loaded());
+
+        let ctx = Context::default();
+        let mut app = OcrApp::default();
+        update_state(&ctx, &mut app);
+        assert!(!app.background_rect.screenshot_result.ocr_results.is_empty());
+    }
 
     #[tokio::test(flavor = "multi_thread")]
+    #[serial]
     async fn test_name() {
         //load DynamicImage
         let path = Path::new("./input/blurry.png");
@@ -442,7 +1162,9 @@ mod tests {
             .clone()
             .save(Path::new("./input/blurry_filtered.png"));
 
-        run_ocr(DynamicImage::ImageLuma8(filtered), OcrPipeline::default()).await;
+        run_ocr(DynamicImage::ImageLuma8(filtered), OcrPipeline::default())
+            .await
+            .unwrap();
 
         let ctx = Context::default();
         let mut app = OcrApp::default();
@@ -0,0 +1,225 @@
+use anyhow::{Context, Ok, Result, bail};
+use rusqlite::{Connection, Row, params};
+use serde::Serialize;
+
+use super::table::create_table;
+
+/// Repeated captures of the same sentence within this time are only stored once.
+const CAPTURE_DEDUPLICATION: &str = "-10 minutes";
+const MAX_RANGE_DAYS: i64 = 3660;
+
+#[derive(Debug, Clone, Serialize, serde::Deserialize, PartialEq, Default)]
+pub struct DailyActivity {
+    /// Local date as YYYY-MM-DD
+    pub day: String,
+    /// 0 is Sunday
+    pub weekday: u8,
+    pub characters: i64,
+    pub lines: i64,
+    pub lookups: i64,
+    pub new_words: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActivityRange {
+    /// The last days including today
+    LastDays(u32),
+    /// Inclusive local dates as YYYY-MM-DD
+    Between(String, String),
+    /// From the first capture until today
+    All,
+}
+
+fn open_connection() -> Result<Connection> {
+    super::word_statistic::open_connection()?;
+    super::history_data::open_connection()?;
+
+    create_table(
+        "CREATE TABLE IF NOT EXISTS capture (
+            id INTEGER PRIMARY KEY,
+            created_at TEXT NOT NULL DEFAULT current_timestamp,
+            history_id INTEGER NOT NULL REFERENCES history(id),
+            char_count INTEGER NOT NULL
+        )",
+    )
+}
+
+pub fn store_capture(history_id: i32, char_count: usize) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute(
+        "INSERT INTO capture (history_id, char_count) \
+            SELECT ?1, ?2 WHERE NOT EXISTS ( \
+                SELECT 1 FROM capture \
+                WHERE history_id = ?1 AND created_at > datetime('now', ?3))",
+        params![history_id, char_count as i64, CAPTURE_DEDUPLICATION],
+    )?;
+
+    Ok(())
+}
+
+fn resolve_range(conn: &Connection, range: &ActivityRange) -> Result<(String, String)> {
+    let (from, to): (Option<String>, Option<String>) = match range {
+        ActivityRange::LastDays(days) => conn.query_row(
+            "SELECT date('now', 'localtime', ?1), date('now', 'localtime')",
+            [format!("-{} days", days.saturating_sub(1))],
+            |row| rusqlite::Result::Ok((row.get(0)?, row.get(1)?)),
+        )?,
+        ActivityRange::All => conn.query_row(
+            "SELECT COALESCE(date(MIN(created_at), 'localtime'), date('now', 'localtime')), \
+                date('now', 'localtime') FROM capture",
+            [],
+            |row| rusqlite::Result::Ok((row.get(0)?, row.get(1)?)),
+        )?,
+        ActivityRange::Between(from, to) => conn.query_row(
+            "SELECT date(?1), date(?2)",
+            [from.trim(), to.trim()],
+            |row| rusqlite::Result::Ok((row.get(0)?, row.get(1)?)),
+        )?,
+    };
+
+    let from = from.context("Invalid start date, expected YYYY-MM-DD")?;
+    let to = to.context("Invalid end date, expected YYYY-MM-DD")?;
+
+    let days: i64 = conn.query_row(
+        "SELECT CAST(julianday(?2) - julianday(?1) AS INTEGER)",
+        [&from, &to],
+        |row| row.get(0),
+    )?;
+    if days < 0 {
+        bail!("Start date {from} is after end date {to}");
+    }
+    if days > MAX_RANGE_DAYS {
+        bail!("Date range is longer than {MAX_RANGE_DAYS} days");
+    }
+
+    Ok((from, to))
+}
+
+impl DailyActivity {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        let day: String = row.get(0)?;
+        let weekday: u8 = row.get(1)?;
+        let characters: i64 = row.get(2)?;
+        let lines: i64 = row.get(3)?;
+        let lookups: i64 = row.get(4)?;
+        let new_words: i64 = row.get(5)?;
+
+        rusqlite::Result::Ok(DailyActivity {
+            day,
+            weekday,
+            characters,
+            lines,
+            lookups,
+            new_words,
+        })
+    }
+}
+
+/// Activity per local day of the range, days without activity are included with zeros.
+pub fn load_daily_activity(range: &ActivityRange) -> Result<Vec<DailyActivity>> {
+    let conn = open_connection()?;
+
+    let (from, to) = resolve_range(&conn, range)?;
+
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE days(day) AS ( \
+            SELECT ?1 UNION ALL SELECT date(day, '+1 day') FROM days WHERE day < ?2 \
+         ), \
+         capture_days AS ( \
+            SELECT date(created_at, 'localtime') AS day, SUM(char_count) AS characters, COUNT(*) AS lines \
+            FROM capture GROUP BY 1 \
+         ), \
+         lookup_days AS ( \
+            SELECT date(created_at, 'localtime') AS day, COUNT(*) AS lookups \
+            FROM word_occurrence WHERE kind = 'lookup' GROUP BY 1 \
+         ), \
+         new_word_days AS ( \
+            SELECT date(first_seen, 'localtime') AS day, COUNT(*) AS new_words FROM ( \
+                SELECT MIN(created_at) AS first_seen FROM word_occurrence \
+                WHERE kind = 'seen' GROUP BY word \
+            ) GROUP BY 1 \
+         ) \
+         SELECT days.day, CAST(strftime('%w', days.day) AS INTEGER), \
+            COALESCE(characters, 0), COALESCE(lines, 0), COALESCE(lookups, 0), COALESCE(new_words, 0) \
+         FROM days \
+            LEFT JOIN capture_days USING (day) \
+            LEFT JOIN lookup_days USING (day) \
+            LEFT JOIN new_word_days USING (day) \
+         ORDER BY days.day",
+    )?;
+
+    let activity: Vec<DailyActivity> = stmt
+        .query_map(params![from, to], DailyActivity::from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(activity)
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use crate::database::table::drop_table;
+    use crate::database::{OccurrenceKind, store_ocr, store_word_occurrence};
+
+    use super::*;
+
+    #[test]
+    #[serial]
+    fn store_and_load_daily_activity() {
+        drop_table("capture").unwrap();
+        drop_table("word_occurrence").unwrap();
+        drop_table("history").unwrap();
+
+        let history_id1 = store_ocr("sentence1").unwrap();
+        let history_id2 = store_ocr("sentence2").unwrap();
+
+        store_capture(history_id1, 9).unwrap();
+        store_capture(history_id1, 9).unwrap();
+        store_capture(history_id2, 5).unwrap();
+
+        store_word_occurrence("word1", OccurrenceKind::Seen, Some(history_id1)).unwrap();
+        store_word_occurrence("word2", OccurrenceKind::Seen, Some(history_id2)).unwrap();
+        store_word_occurrence("word1", OccurrenceKind::Lookup, Some(history_id1)).unwrap();
+
+        let activity = load_daily_activity(&ActivityRange::LastDays(7)).unwrap();
+        assert_eq!(activity.len(), 7);
+
+        let today = activity.last().unwrap();
+        assert_eq!(today.characters, 14);
+        assert_eq!(today.lines, 2);
+        assert_eq!(today.lookups, 1);
+        assert_eq!(today.new_words, 2);
+        assert!(activity[..6].iter().all(|x| x.lines == 0));
+
+        let activity = load_daily_activity(&ActivityRange::All).unwrap();
+        assert_eq!(activity, vec![today.clone()]);
+    }
+
+    #[test]
+    #[serial]
+    fn load_daily_activity_between() {
+        let range = ActivityRange::Between("2024-02-27".to_string(), "2024-03-02".to_string());
+        let activity = load_daily_activity(&range).unwrap();
+
+        let days: Vec<_> = activity.iter().map(|x| x.day.as_str()).collect();
+        assert_eq!(
+            days,
+            vec![
+                "2024-02-27",
+                "2024-02-28",
+                "2024-02-29",
+                "2024-03-01",
+                "2024-03-02"
+            ]
+        );
+        assert_eq!(activity[0].weekday, 2);
+
+        let range = ActivityRange::Between("2024-03-02".to_string(), "2024-02-27".to_string());
+        assert!(load_daily_activity(&range).is_err());
+
+        let range = ActivityRange::Between("yesterday".to_string(), "2024-02-27".to_string());
+        assert!(load_daily_activity(&range).is_err());
+    }
+}
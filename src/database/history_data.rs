@@ -2,7 +2,7 @@ use anyhow::{Ok, Result};
 use rusqlite::{Connection, Row, params};
 use serde::Serialize;
 
-use super::table::create_table;
+use super::table::{add_column_if_missing, create_table};
 
 #[derive(Debug, Default, PartialEq, Serialize, serde::Deserialize, Clone)]
 pub struct HistoryData {
@@ -11,10 +11,11 @@ pub struct HistoryData {
     pub updated_at: String,
     pub ocr: String,
     pub translation: Option<String>,
+    pub difficulty: Option<f32>,
 }
 
-fn open_connection() -> Result<Connection> {
-    create_table(
+pub(super) fn open_connection() -> Result<Connection> {
+    let conn = create_table(
         "CREATE TABLE IF NOT EXISTS history (
             id INTEGER PRIMARY KEY,
             created_at TEXT NOT NULL DEFAULT current_timestamp,
@@ -22,10 +23,14 @@ fn open_connection() -> Result<Connection> {
             ocr TEXT UNIQUE NOT NULL,
             translation TEXT
         )",
-    )
+    )?;
+    add_column_if_missing(&conn, "history", "difficulty", "REAL")?;
+
+    Ok(conn)
 }
 
-pub fn store_ocr(ocr: &str) -> Result<()> {
+/// Stores the ocr text and returns the id of its history row.
+pub fn store_ocr(ocr: &str) -> Result<i32> {
     let conn = open_connection()?;
 
     conn.execute(
@@ -34,6 +39,21 @@ pub fn store_ocr(ocr: &str) -> Result<()> {
         params![ocr],
     )?;
 
+    let id: i32 = conn.query_row("SELECT id FROM history WHERE ocr = ?1", [ocr], |row| {
+        row.get(0)
+    })?;
+
+    Ok(id)
+}
+
+pub fn store_ocr_difficulty(ocr: &str, difficulty: f32) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute(
+        "UPDATE history SET difficulty = ?2 WHERE ocr = ?1",
+        params![ocr, difficulty],
+    )?;
+
     Ok(())
 }
 
@@ -56,6 +76,7 @@ impl HistoryData {
         let updated_at: String = row.get(2)?;
         let ocr: String = row.get(3)?;
         let translation: Option<String> = row.get(4)?;
+        let difficulty: Option<f32> = row.get(5)?;
 
         rusqlite::Result::Ok(HistoryData {
             id,
@@ -63,6 +84,7 @@ impl HistoryData {
             updated_at,
             ocr,
             translation,
+            difficulty,
         })
     }
 }
@@ -123,4 +145,16 @@ mod tests {
         assert_eq!(&vec[1].ocr, "ocr2");
         assert!(&vec[1].translation.is_none());
     }
+
+    #[test]
+    #[serial]
+    fn store_and_load_difficulty() {
+        drop_table("history").unwrap();
+
+        store_ocr("ocr1").unwrap();
+        assert_eq!(load_history_data("ocr1").unwrap().difficulty, None);
+
+        store_ocr_difficulty("ocr1", 42.5).unwrap();
+        assert_eq!(load_history_data("ocr1").unwrap().difficulty, Some(42.5));
+    }
 }
@@ -0,0 +1,71 @@
+use anyhow::{Ok, Result};
+use rusqlite::{Connection, params};
+
+use super::table::create_table;
+
+fn open_connection() -> Result<Connection> {
+    create_table(
+        "CREATE TABLE IF NOT EXISTS known_word (
+            id INTEGER PRIMARY KEY,
+            created_at TEXT NOT NULL DEFAULT current_timestamp,
+            word TEXT UNIQUE NOT NULL
+        )",
+    )
+}
+
+pub fn store_known_word(word: &str) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute(
+        "INSERT INTO known_word (word) VALUES (?1) \
+            ON CONFLICT(word) DO NOTHING",
+        params![word],
+    )?;
+
+    Ok(())
+}
+
+pub fn remove_known_word(word: &str) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("DELETE FROM known_word WHERE word = ?1", params![word])?;
+
+    Ok(())
+}
+
+pub fn load_known_words() -> Result<Vec<String>> {
+    let conn = open_connection()?;
+
+    let mut stmt = conn.prepare("SELECT word FROM known_word ORDER BY word")?;
+
+    let words: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use crate::database::table::drop_table;
+
+    use super::*;
+
+    #[test]
+    #[serial]
+    fn store_and_remove_known_word() {
+        drop_table("known_word").unwrap();
+
+        store_known_word("word2").unwrap();
+        store_known_word("word1").unwrap();
+        store_known_word("word1").unwrap();
+
+        assert_eq!(load_known_words().unwrap(), vec!["word1", "word2"]);
+
+        remove_known_word("word1").unwrap();
+
+        assert_eq!(load_known_words().unwrap(), vec!["word2"]);
+    }
+}
@@ -1,14 +1,43 @@
+mod activity;
 mod history_data;
 mod kanji_statistic;
+mod known_word;
+mod ocr_correction;
 mod table;
+mod word_statistic;
+
+pub use activity::ActivityRange;
+pub use activity::DailyActivity;
+pub use activity::load_daily_activity;
+pub use activity::store_capture;
 
 pub use history_data::HistoryData;
 pub use history_data::load_full_history;
 pub use history_data::load_history_data;
 pub use history_data::store_ocr;
+pub use history_data::store_ocr_difficulty;
 pub use history_data::store_ocr_translation;
 
 pub use kanji_statistic::KanjiStatistic;
 pub use kanji_statistic::increment_kanji_statistic;
 pub use kanji_statistic::init_kanji_statistic;
 pub use kanji_statistic::load_statistic;
+
+pub use known_word::load_known_words;
+pub use known_word::remove_known_word;
+pub use known_word::store_known_word;
+
+pub use ocr_correction::OcrCorrection;
+pub use ocr_correction::Substitution;
+pub use ocr_correction::delete_ocr_correction;
+pub use ocr_correction::delete_substitution;
+pub use ocr_correction::load_crop_correction;
+pub use ocr_correction::load_ocr_corrections;
+pub use ocr_correction::load_substitutions;
+pub use ocr_correction::store_ocr_correction;
+
+pub use word_statistic::OccurrenceKind;
+pub use word_statistic::WordStatistic;
+pub use word_statistic::load_example_sentences;
+pub use word_statistic::load_word_statistic;
+pub use word_statistic::store_word_occurrence;
@@ -0,0 +1,190 @@
+use anyhow::{Ok, Result};
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use serde::Serialize;
+
+use super::table::create_table;
+
+#[derive(Debug, Clone, Serialize, serde::Deserialize, PartialEq, Default)]
+pub struct OcrCorrection {
+    pub id: i32,
+    pub created_at: String,
+    pub original: String,
+    pub corrected: String,
+    pub crop_hash: Option<String>,
+    pub substitution_from: Option<String>,
+    pub substitution_to: Option<String>,
+}
+
+/// Character substitution learned from the corrections, `count` is the number of corrections.
+#[derive(Debug, Clone, Serialize, serde::Deserialize, PartialEq, Default)]
+pub struct Substitution {
+    pub from: String,
+    pub to: String,
+    pub count: i32,
+}
+
+fn open_connection() -> Result<Connection> {
+    create_table(
+        "CREATE TABLE IF NOT EXISTS ocr_correction (
+            id INTEGER PRIMARY KEY,
+            created_at TEXT NOT NULL DEFAULT current_timestamp,
+            original TEXT NOT NULL,
+            corrected TEXT NOT NULL,
+            crop_hash TEXT,
+            substitution_from TEXT,
+            substitution_to TEXT
+        )",
+    )
+}
+
+pub fn store_ocr_correction(
+    original: &str,
+    corrected: &str,
+    crop_hash: Option<&str>,
+    substitution: Option<(&str, &str)>,
+) -> Result<i32> {
+    let conn = open_connection()?;
+
+    conn.execute(
+        "INSERT INTO ocr_correction \
+            (original, corrected, crop_hash, substitution_from, substitution_to) \
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            original,
+            corrected,
+            crop_hash,
+            substitution.map(|x| x.0),
+            substitution.map(|x| x.1)
+        ],
+    )?;
+
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+impl OcrCorrection {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        rusqlite::Result::Ok(OcrCorrection {
+            id: row.get(0)?,
+            created_at: row.get(1)?,
+            original: row.get(2)?,
+            corrected: row.get(3)?,
+            crop_hash: row.get(4)?,
+            substitution_from: row.get(5)?,
+            substitution_to: row.get(6)?,
+        })
+    }
+}
+
+/// All corrections, most recent first.
+pub fn load_ocr_corrections() -> Result<Vec<OcrCorrection>> {
+    let conn = open_connection()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, created_at, original, corrected, crop_hash, substitution_from, substitution_to \
+         FROM ocr_correction ORDER BY created_at DESC, id DESC",
+    )?;
+
+    let corrections: Vec<OcrCorrection> = stmt
+        .query_map([], OcrCorrection::from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(corrections)
+}
+
+/// The latest corrected text of the same crop.
+pub fn load_crop_correction(crop_hash: &str) -> Result<Option<String>> {
+    let conn = open_connection()?;
+
+    let corrected = conn
+        .query_row(
+            "SELECT corrected FROM ocr_correction WHERE crop_hash = ?1 \
+             ORDER BY created_at DESC, id DESC LIMIT 1",
+            [crop_hash],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    Ok(corrected)
+}
+
+/// Learned substitutions, the most frequent first.
+pub fn load_substitutions() -> Result<Vec<Substitution>> {
+    let conn = open_connection()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT substitution_from, substitution_to, COUNT(*) FROM ocr_correction \
+         WHERE substitution_from IS NOT NULL AND substitution_to IS NOT NULL \
+         GROUP BY substitution_from, substitution_to \
+         ORDER BY COUNT(*) DESC, substitution_from",
+    )?;
+
+    let substitutions: Vec<Substitution> = stmt
+        .query_map([], |row| {
+            rusqlite::Result::Ok(Substitution {
+                from: row.get(0)?,
+                to: row.get(1)?,
+                count: row.get(2)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(substitutions)
+}
+
+pub fn delete_ocr_correction(id: i32) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute("DELETE FROM ocr_correction WHERE id = ?1", params![id])?;
+
+    Ok(())
+}
+
+/// Forgets a substitution by removing all corrections which taught it.
+pub fn delete_substitution(from: &str, to: &str) -> Result<()> {
+    let conn = open_connection()?;
+
+    conn.execute(
+        "DELETE FROM ocr_correction WHERE substitution_from = ?1 AND substitution_to = ?2",
+        params![from, to],
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use crate::database::table::drop_table;
+
+    use super::*;
+
+    #[test]
+    #[serial]
+    fn store_and_load_ocr_correction() {
+        drop_table("ocr_correction").unwrap();
+
+        store_ocr_correction("口ボット", "ロボット", Some("hash1"), Some(("口", "ロ"))).unwrap();
+        store_ocr_correction("口ケット", "ロケット", Some("hash2"), Some(("口", "ロ"))).unwrap();
+        let id = store_ocr_correction("力学", "カ学", None, Some(("力", "カ"))).unwrap();
+        store_ocr_correction("ロケット", "ロケット!", Some("hash2"), None).unwrap();
+
+        assert_eq!(load_ocr_corrections().unwrap().len(), 4);
+        assert_eq!(
+            load_crop_correction("hash2").unwrap(),
+            Some("ロケット!".to_string())
+        );
+        assert_eq!(load_crop_correction("unknown").unwrap(), None);
+
+        let substitutions = load_substitutions().unwrap();
+        assert_eq!(substitutions.len(), 2);
+        assert_eq!(substitutions[0].from, "口");
+        assert_eq!(substitutions[0].count, 2);
+
+        delete_ocr_correction(id).unwrap();
+        delete_substitution("口", "ロ").unwrap();
+
+        assert!(load_substitutions().unwrap().is_empty());
+        assert_eq!(load_ocr_corrections().unwrap().len(), 1);
+    }
+}
@@ -24,6 +24,31 @@ pub fn create_table(create_table_query: &str) -> Result<Connection> {
     Ok(conn)
 }
 
+/// Adds a column to an existing table, used for columns which were added after the table was created.
+pub fn add_column_if_missing(
+    conn: &Connection,
+    table_name: &str,
+    column_name: &str,
+    column_definition: &str,
+) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({table_name})"))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .iter()
+        .any(|x| x == column_name);
+
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {table_name} ADD COLUMN {column_name} {column_definition}"),
+            [],
+        )
+        .with_context(|| format!("could not add column {column_name} to {table_name}"))?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 pub fn drop_table(table_name: &str) -> Result<()> {
     let conn = create_database()?;
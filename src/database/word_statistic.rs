@@ -0,0 +1,165 @@
+use anyhow::{Ok, Result};
+use rusqlite::{Connection, Row, params};
+use serde::Serialize;
+
+use super::table::create_table;
+
+/// Repeated sightings of the same word in the same sentence within this time are only stored once,
+/// otherwise auto restart would count a word every few seconds.
+const SEEN_DEDUPLICATION: &str = "-10 minutes";
+
+#[derive(Debug, Clone, Serialize, serde::Deserialize, PartialEq, Default)]
+pub struct WordStatistic {
+    pub word: String,
+    pub seen_count: i32,
+    pub lookup_count: i32,
+    pub first_seen: Option<String>,
+    pub last_seen: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OccurrenceKind {
+    Seen,
+    Lookup,
+}
+
+impl OccurrenceKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            OccurrenceKind::Seen => "seen",
+            OccurrenceKind::Lookup => "lookup",
+        }
+    }
+}
+
+pub(super) fn open_connection() -> Result<Connection> {
+    create_table(
+        "CREATE TABLE IF NOT EXISTS word_occurrence (
+            id INTEGER PRIMARY KEY,
+            created_at TEXT NOT NULL DEFAULT current_timestamp,
+            word TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            history_id INTEGER REFERENCES history(id)
+        )",
+    )
+}
+
+pub fn store_word_occurrence(
+    word: &str,
+    kind: OccurrenceKind,
+    history_id: Option<i32>,
+) -> Result<()> {
+    let conn = open_connection()?;
+
+    match kind {
+        OccurrenceKind::Seen => conn.execute(
+            "INSERT INTO word_occurrence (word, kind, history_id) \
+                SELECT ?1, ?2, ?3 WHERE NOT EXISTS ( \
+                    SELECT 1 FROM word_occurrence \
+                    WHERE word = ?1 AND kind = ?2 AND history_id IS ?3 \
+                    AND created_at > datetime('now', ?4))",
+            params![word, kind.as_str(), history_id, SEEN_DEDUPLICATION],
+        )?,
+        OccurrenceKind::Lookup => conn.execute(
+            "INSERT INTO word_occurrence (word, kind, history_id) VALUES (?1, ?2, ?3)",
+            params![word, kind.as_str(), history_id],
+        )?,
+    };
+
+    Ok(())
+}
+
+impl WordStatistic {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        let word: String = row.get(0)?;
+        let seen_count: i32 = row.get(1)?;
+        let lookup_count: i32 = row.get(2)?;
+        let first_seen: Option<String> = row.get(3)?;
+        let last_seen: Option<String> = row.get(4)?;
+
+        rusqlite::Result::Ok(WordStatistic {
+            word,
+            seen_count,
+            lookup_count,
+            first_seen,
+            last_seen,
+        })
+    }
+}
+
+pub fn load_word_statistic(word: &str) -> Result<WordStatistic> {
+    let conn = open_connection()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT ?1, \
+            COUNT(CASE WHEN kind = 'seen' THEN 1 END), \
+            COUNT(CASE WHEN kind = 'lookup' THEN 1 END), \
+            MIN(CASE WHEN kind = 'seen' THEN created_at END), \
+            MAX(CASE WHEN kind = 'seen' THEN created_at END) \
+         FROM word_occurrence WHERE word = ?1",
+    )?;
+
+    let statistic: WordStatistic = stmt.query_row([word], WordStatistic::from_row)?;
+
+    Ok(statistic)
+}
+
+/// Sentences from the history in which the word occurred, most recent first.
+pub fn load_example_sentences(word: &str, limit: usize) -> Result<Vec<String>> {
+    let conn = open_connection()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT history.ocr FROM word_occurrence \
+            JOIN history ON history.id = word_occurrence.history_id \
+         WHERE word_occurrence.word = ?1 \
+         GROUP BY history.id \
+         ORDER BY MAX(word_occurrence.created_at) DESC, history.id DESC \
+         LIMIT ?2",
+    )?;
+
+    let sentences: Vec<String> = stmt
+        .query_map(params![word, limit as i64], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(sentences)
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use crate::database::store_ocr;
+    use crate::database::table::drop_table;
+
+    use super::*;
+
+    #[test]
+    #[serial]
+    fn store_and_load_word_statistic() {
+        drop_table("word_occurrence").unwrap();
+        drop_table("history").unwrap();
+
+        let history_id1 = store_ocr("sentence1").unwrap();
+        let history_id2 = store_ocr("sentence2").unwrap();
+
+        store_word_occurrence("word", OccurrenceKind::Seen, Some(history_id1)).unwrap();
+        store_word_occurrence("word", OccurrenceKind::Seen, Some(history_id1)).unwrap();
+        store_word_occurrence("word", OccurrenceKind::Seen, Some(history_id2)).unwrap();
+        store_word_occurrence("word", OccurrenceKind::Lookup, Some(history_id2)).unwrap();
+        store_word_occurrence("word", OccurrenceKind::Lookup, None).unwrap();
+        store_word_occurrence("other", OccurrenceKind::Seen, Some(history_id1)).unwrap();
+
+        let statistic = load_word_statistic("word").unwrap();
+        assert_eq!(statistic.seen_count, 2);
+        assert_eq!(statistic.lookup_count, 2);
+        assert!(statistic.first_seen.is_some());
+        assert!(statistic.last_seen.is_some());
+
+        let sentences = load_example_sentences("word", 10).unwrap();
+        assert_eq!(sentences, vec!["sentence2", "sentence1"]);
+
+        let statistic = load_word_statistic("unknown").unwrap();
+        assert_eq!(statistic.seen_count, 0);
+        assert!(statistic.first_seen.is_none());
+    }
+}
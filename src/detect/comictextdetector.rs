@@ -1,13 +1,15 @@
 use std::cmp::{max, min};
-use std::sync::{Arc, LazyLock, Mutex};
 
 use crate::detect::session_builder::create_session_builder;
+use crate::model::lifecycle::ManagedModel;
+use crate::model::store::{self, DETECTOR_MODEL, ModelVariant};
+use crate::ui::backend_status::Backend;
 use anyhow::Result;
 use image::imageops::FilterType;
 use image::{DynamicImage, GenericImageView};
 use imageproc::rect::Rect;
 use itertools::Itertools;
-use log::{debug, error};
+use log::{debug, warn};
 use ndarray::Array4;
 use ort::session::Session;
 use ort::value::TensorRef;
@@ -15,41 +17,41 @@ use ort::value::TensorRef;
 const INPUT_WIDTH: f32 = 1024.0;
 const INPUT_HEIGHT: f32 = 1024.0;
 
-pub static DETECT_STATE: LazyLock<DetectState> = LazyLock::new(DetectState::init);
+pub static DETECT_STATE: DetectState = DetectState {
+    model: ManagedModel::new("Comic Text Detector", load_detector),
+};
 
-#[derive(Clone)]
 pub struct DetectState {
-    pub session: Arc<Mutex<Option<Session>>>,
+    pub model: ManagedModel<Session>,
 }
 
 impl DetectState {
-    pub fn init() -> Self {
-        let data = load_model().ok();
-        let data = Mutex::new(data);
-        let session = Arc::new(data);
-
-        Self { session }
-    }
-
-    pub fn run_model(&self, img: &DynamicImage, threshold: f32) -> Vec<Boxes> {
-        let mut model = self.session.lock().unwrap();
-        if let Some(model) = model.as_mut() {
-            run_model(model, threshold, img).unwrap_or_else(|e| {
-                error!("run_model error: {}", e);
-                vec![]
-            })
-        } else {
-            vec![]
-        }
+    pub fn run_model(&self, img: &DynamicImage, threshold: f32) -> Result<Vec<Boxes>> {
+        self.model
+            .with(|model| run_model(model, threshold, img))
+            .and_then(|x| x)
     }
 }
 
-pub fn load_model() -> Result<Session> {
-    let builder = create_session_builder()?;
+fn load_detector() -> Result<Session> {
+    load_model(store::get_variant(&DETECTOR_MODEL))
+}
 
-    let detector_model = include_bytes!("../../resources/comictextdetector_blk.pt.onnx");
+pub fn load_model(variant: ModelVariant) -> Result<Session> {
+    let builder = create_session_builder(Backend::Detector)?;
 
-    let session = builder.commit_from_memory(detector_model)?;
+    // A model in the models directory overrides the bundled one
+    let file = variant.get_file_name(DETECTOR_MODEL.files[0]);
+    let session = match store::find_local_file(&DETECTOR_MODEL, &file)? {
+        Some(path) => builder.commit_from_file(path)?,
+        None => {
+            if variant != ModelVariant::Fp32 {
+                warn!("{file} not found, using the bundled fp32 detector");
+            }
+            let detector_model = include_bytes!("../../resources/comictextdetector_blk.pt.onnx");
+            builder.commit_from_memory(detector_model)?
+        }
+    };
     Ok(session)
 }
 
@@ -242,7 +244,7 @@ mod tests {
 
     #[test]
     fn test_load() {
-        let mut model = load_model().unwrap();
+        let mut model = load_model(ModelVariant::Fp32).unwrap();
         info!("Model loaded");
 
         vec![0.0, 0.01, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8]
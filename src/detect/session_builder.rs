@@ -1,28 +1,141 @@
+use crate::ui::backend_status::Backend;
+use crate::ui::inference_config::{InferenceConfig, OptimizationLevel, Provider};
 use log::{info, warn};
-use ort::execution_providers::{CUDAExecutionProvider, ExecutionProvider};
+use ort::execution_providers::{
+    CPUExecutionProvider, CUDAExecutionProvider, CoreMLExecutionProvider,
+    DirectMLExecutionProvider, ExecutionProvider, OpenVINOExecutionProvider, RegisterError,
+    TensorRTExecutionProvider,
+};
 use ort::session::Session;
-use ort::session::builder::SessionBuilder;
+use ort::session::builder::{GraphOptimizationLevel, SessionBuilder};
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+static INFERENCE_CONFIG: LazyLock<RwLock<InferenceConfig>> =
+    LazyLock::new(|| RwLock::new(InferenceConfig::default()));
+
+/// Provider of the last created session of each backend.
+static ACTIVE_PROVIDERS: LazyLock<RwLock<HashMap<Backend, Provider>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Sets the config for the sessions created afterwards, the models have to be reloaded to use it.
+pub fn set_inference_config(config: InferenceConfig) {
+    *INFERENCE_CONFIG
+        .write()
+        .expect("Inference config lock failed") = config;
+}
+
+pub fn get_active_provider(backend: &Backend) -> Option<Provider> {
+    ACTIVE_PROVIDERS
+        .read()
+        .expect("Active provider lock failed")
+        .get(backend)
+        .copied()
+}
+
+/// Providers used by any of the backends.
+pub fn get_active_providers() -> Vec<Provider> {
+    ACTIVE_PROVIDERS
+        .read()
+        .expect("Active provider lock failed")
+        .values()
+        .copied()
+        .collect()
+}
+
+pub fn is_provider_available(provider: Provider) -> bool {
+    let result = match provider {
+        Provider::Cpu => return true,
+        Provider::Cuda => CUDAExecutionProvider::default().is_available(),
+        Provider::TensorRt => TensorRTExecutionProvider::default().is_available(),
+        Provider::DirectMl => DirectMLExecutionProvider::default().is_available(),
+        Provider::CoreMl => CoreMLExecutionProvider::default().is_available(),
+        Provider::OpenVino => OpenVINOExecutionProvider::default().is_available(),
+    };
+    result.unwrap_or(false)
+}
+
+/// Session builder with the providers of the inference config, the provider which is used is
+/// recorded for the backend.
+pub fn create_session_builder(backend: Backend) -> anyhow::Result<SessionBuilder> {
+    let config = INFERENCE_CONFIG
+        .read()
+        .expect("Inference config lock failed")
+        .clone();
 
-pub fn create_session_builder() -> anyhow::Result<SessionBuilder> {
     let mut builder = Session::builder()?
-        .with_optimization_level(ort::session::builder::GraphOptimizationLevel::Level3)?
-        .with_intra_threads(4)?;
+        .with_optimization_level(get_optimization_level(config.optimization_level))?
+        .with_parallel_execution(config.parallel_execution)?
+        .with_memory_pattern(config.memory_pattern)?;
+    if config.intra_threads > 0 {
+        builder = builder.with_intra_threads(config.intra_threads)?;
+    }
+    if config.inter_threads > 0 {
+        builder = builder.with_inter_threads(config.inter_threads)?;
+    }
 
-    let cuda = CUDAExecutionProvider::default();
-    if cuda.is_available()? {
-        info!("CUDA is available");
-    } else {
-        warn!("CUDA is not available");
+    let provider = register_providers(&mut builder, &config);
+    ACTIVE_PROVIDERS
+        .write()
+        .expect("Active provider lock failed")
+        .insert(backend, provider);
+
+    Ok(builder)
+}
+
+/// Registers the first provider that works, the CPU is used if none does.
+fn register_providers(builder: &mut SessionBuilder, config: &InferenceConfig) -> Provider {
+    for provider in &config.providers {
+        if !is_provider_available(*provider) {
+            warn!("{} is not available", provider.get_label());
+            continue;
+        }
+
+        match register_provider(builder, *provider, config) {
+            Ok(()) => {
+                info!("Registered {}", provider.get_label());
+                return *provider;
+            }
+            Err(err) => warn!("Failed to register {}! {err}", provider.get_label()),
+        }
     }
 
-    let result = cuda.register(&mut builder);
-    if result.is_err() {
-        warn!("Failed to register CUDA! {}", result.unwrap_err());
-    } else {
-        info!("Registered CUDA");
+    if let Err(err) = register_provider(builder, Provider::Cpu, config) {
+        warn!("Failed to register CPU! {err}");
     }
+    Provider::Cpu
+}
 
-    Ok(builder)
+fn register_provider(
+    builder: &mut SessionBuilder,
+    provider: Provider,
+    config: &InferenceConfig,
+) -> Result<(), RegisterError> {
+    match provider {
+        Provider::Cpu => CPUExecutionProvider::default()
+            .with_arena_allocator(config.cpu_arena)
+            .register(builder),
+        Provider::Cuda => {
+            let mut cuda = CUDAExecutionProvider::default();
+            if config.gpu_memory_limit_mb > 0 {
+                cuda = cuda.with_memory_limit(config.gpu_memory_limit_mb * 1024 * 1024);
+            }
+            cuda.register(builder)
+        }
+        Provider::TensorRt => TensorRTExecutionProvider::default().register(builder),
+        Provider::DirectMl => DirectMLExecutionProvider::default().register(builder),
+        Provider::CoreMl => CoreMLExecutionProvider::default().register(builder),
+        Provider::OpenVino => OpenVINOExecutionProvider::default().register(builder),
+    }
+}
+
+fn get_optimization_level(level: OptimizationLevel) -> GraphOptimizationLevel {
+    match level {
+        OptimizationLevel::Disable => GraphOptimizationLevel::Disable,
+        OptimizationLevel::Level1 => GraphOptimizationLevel::Level1,
+        OptimizationLevel::Level2 => GraphOptimizationLevel::Level2,
+        OptimizationLevel::Level3 => GraphOptimizationLevel::Level3,
+    }
 }
 
 #[test]
@@ -40,3 +153,14 @@ fn is_cuda_working() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_cpu_fallback() {
+    let config = InferenceConfig {
+        providers: vec![],
+        ..Default::default()
+    };
+    let mut builder = Session::builder().unwrap();
+
+    assert_eq!(register_providers(&mut builder, &config), Provider::Cpu);
+}
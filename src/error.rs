@@ -0,0 +1,57 @@
+use thiserror::Error;
+
+/// Failure of an OCR run, shown as a notification in the overlay.
+#[derive(Debug, Error)]
+pub enum OcrError {
+    #[error("Screenshot failed: {0:#}")]
+    Screenshot(anyhow::Error),
+    #[error("All OCR backends failed: {}", .0.join("; "))]
+    Backends(Vec<String>),
+    #[error("Failed to store the {what}: {error:#}")]
+    Storage {
+        what: &'static str,
+        error: anyhow::Error,
+    },
+    #[error("Step {index} '{name}' failed: {error}")]
+    Step {
+        index: usize,
+        name: &'static str,
+        error: Box<OcrError>,
+    },
+    /// The task panicked or was cancelled
+    #[error("OCR task failed: {0}")]
+    Task(String),
+}
+
+impl OcrError {
+    pub fn storage(what: &'static str) -> impl FnOnce(anyhow::Error) -> Self {
+        move |error| OcrError::Storage { what, error }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let error = OcrError::Step {
+            index: 2,
+            name: "OCR Step",
+            error: Box::new(OcrError::Backends(vec![
+                "MangaOCR: not loaded".to_string(),
+                "Tesseract: not installed".to_string(),
+            ])),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Step 2 'OCR Step' failed: All OCR backends failed: MangaOCR: not loaded; Tesseract: not installed"
+        );
+
+        let error = OcrError::storage("ocr")(anyhow::anyhow!("database is locked"));
+        assert_eq!(
+            error.to_string(),
+            "Failed to store the ocr: database is locked"
+        );
+    }
+}
@@ -1,18 +1,24 @@
 use crate::OcrApp;
-use crate::action::ScreenshotResult;
-use crate::database::{HistoryData, KanjiStatistic};
+use crate::action::{self, ResultData, ScreenshotResult};
+use crate::database::{
+    DailyActivity, HistoryData, KanjiStatistic, OcrCorrection, Substitution, WordStatistic,
+};
 use crate::jpn::{JpnData, get_jpn_data};
-use crate::ocr::BackendResult;
+use crate::model::store::ModelInfo;
+use crate::ui::backend_status::{Backend, BackendStatus};
 use crate::ui::image_display::ImageWrapper;
-use crate::ui::settings::{Backend, BackendStatus};
+use crate::ui::kanji_detail_ui::KanjiDetail;
+use crate::ui::notification::NotificationLevel;
 use crate::ui::shutdown::TASK_TRACKER;
 use crate::ui::update_queue::enqueue_update;
 use eframe::epaint::textures::TextureOptions;
 use eframe::epaint::{ColorImage, TextureHandle};
 use egui::{Context, Id, Memory};
 use image::{DynamicImage, EncodableLayout};
+use itertools::Itertools;
 use log::debug;
 use std::cmp::max;
+use std::collections::HashSet;
 use std::ops::Add;
 use std::time::Duration;
 use tokio::time::Instant;
@@ -26,6 +32,21 @@ pub fn update_ocr_result(state: &mut OcrApp, index: usize, ocr: String) {
         && result_data.ocr != ocr
     {
         result_data.translation = "".to_string();
+
+        // Corrected characters are no longer in doubt
+        let old_chars = result_data.ocr.chars().collect_vec();
+        let new_chars = ocr.chars().collect_vec();
+        if old_chars.len() == new_chars.len() {
+            for (index, (old_char, new_char)) in old_chars.iter().zip(&new_chars).enumerate() {
+                if old_char != new_char
+                    && let Some(confidence) = result_data.char_confidences.get_mut(index)
+                {
+                    *confidence = 1.0;
+                }
+            }
+        } else {
+            result_data.char_confidences.clear();
+        }
         result_data.ocr = ocr.to_string();
 
         TASK_TRACKER.spawn(async move {
@@ -36,6 +57,50 @@ pub fn update_ocr_result(state: &mut OcrApp, index: usize, ocr: String) {
     }
 }
 
+/// Replaces the OCR text by a correction of the user and remembers it for later captures.
+pub fn correct_ocr_result(state: &mut OcrApp, index: usize, ocr: String) {
+    if let Some(result_data) = state
+        .background_rect
+        .screenshot_result
+        .ocr_results
+        .get(index)
+        && result_data.ocr != ocr
+    {
+        let original = result_data.ocr.clone();
+        let corrected = ocr.clone();
+        let crop_hash = result_data.crop_hash.clone();
+        TASK_TRACKER.spawn(async move {
+            action::store_ocr_correction(&original, &corrected, crop_hash.as_deref());
+        });
+    }
+
+    update_ocr_result(state, index, ocr);
+}
+
+pub fn undo_correction(state: &mut OcrApp, index: usize) {
+    let correction = state
+        .background_rect
+        .screenshot_result
+        .ocr_results
+        .get_mut(index)
+        .and_then(|x| x.correction.take());
+
+    if let Some(correction) = correction {
+        update_ocr_result(state, index, correction.original);
+
+        // The original text has the confidences of the OCR again
+        if let Some(result_data) = state
+            .background_rect
+            .screenshot_result
+            .ocr_results
+            .get_mut(index)
+        {
+            result_data.char_confidences = result_data.backend_result.get_char_confidences();
+            result_data.confidence = result_data.backend_result.get_confidence();
+        }
+    }
+}
+
 pub fn update_jpn_data(state: &mut OcrApp, index: usize, jpn_data: Vec<Vec<JpnData>>) {
     if let Some(result_data) = state
         .background_rect
@@ -59,17 +124,11 @@ pub fn update_screenshot_result(ctx: &Context, state: &mut OcrApp, data: Screens
         return;
     }
 
-    for x in data.ocr_results.iter().map(|x| &x.backend_result) {
-        match x {
-            BackendResult::MangaOcr(top) => {
-                for i in top {
-                    for j in i {
-                        print!("{}", j.kanji)
-                    }
-                    println!();
-                }
+    if log::log_enabled!(log::Level::Debug) {
+        for x in data.ocr_results.iter().map(|x| &x.backend_result) {
+            for alternatives in x.get_top_results().into_iter().flatten() {
+                debug!("{}", alternatives.iter().map(|x| &x.kanji).join(""));
             }
-            BackendResult::Unknown => {}
         }
     }
 
@@ -80,6 +139,12 @@ pub fn update_screenshot_result(ctx: &Context, state: &mut OcrApp, data: Screens
         reset_ocr_start_time();
     }
 
+    // Repeated captures of the same page, e.g. with the auto restart, are counted once
+    if let Some(difficulty) = data.difficulty
+        && !data.has_same_text(&background_rect.screenshot_result)
+    {
+        background_rect.session_difficulty.add_page(difficulty);
+    }
     background_rect.screenshot_result = data;
 }
 
@@ -96,8 +161,62 @@ pub fn update_kanji_statistic(ctx: &Context, state: &mut OcrApp, data: Vec<Kanji
     }
 }
 
-pub fn update_selected_jpn_data(state: &mut OcrApp, data: JpnData) {
+pub fn update_dictionary_results(state: &mut OcrApp, query: String, data: Vec<Vec<JpnData>>) {
+    let dictionary = &mut state.dictionary;
+    if dictionary.input != query {
+        //Outdated search result
+        return;
+    }
+
+    dictionary.selected = data.iter().flatten().find(|x| x.has_kanji_data()).cloned();
+    dictionary.results = data;
+}
+
+pub fn open_kanji_detail(state: &mut OcrApp, kanji: char) {
+    state.kanji_detail.set_kanji(kanji);
+    state.window_state.show_kanji_detail = true;
+}
+
+pub fn update_kanji_detail(state: &mut OcrApp, detail: KanjiDetail) {
+    state.kanji_detail.set_detail(detail);
+}
+
+pub fn update_activity(state: &mut OcrApp, activity: anyhow::Result<Vec<DailyActivity>>) {
+    state.dashboard.set_activity(activity);
+}
+
+pub fn update_corrections(
+    state: &mut OcrApp,
+    corrections: Vec<OcrCorrection>,
+    substitutions: Vec<Substitution>,
+) {
+    state
+        .corrections
+        .set_corrections(corrections, substitutions);
+}
+
+pub fn update_model_infos(state: &mut OcrApp, infos: Vec<ModelInfo>, message: Option<String>) {
+    state.models.set_model_infos(infos, message);
+}
+
+pub fn open_breakdown(state: &mut OcrApp, result: ResultData) {
+    state.breakdown.set_result(result);
+    state.window_state.show_breakdown = true;
+}
+
+pub fn update_breakdown_known_words(state: &mut OcrApp, known_words: HashSet<String>) {
+    state.breakdown.set_known_words(known_words);
+}
+
+pub fn update_selected_jpn_data(
+    state: &mut OcrApp,
+    data: JpnData,
+    word_statistic: WordStatistic,
+    example_sentences: Vec<String>,
+) {
     state.kanji_statistic.selected_jpn_data = data;
+    state.kanji_statistic.word_statistic = Some(word_statistic);
+    state.kanji_statistic.example_sentences = example_sentences;
 }
 
 pub fn update_backend_status(backend: Backend, backend_status: BackendStatus) {
@@ -110,6 +229,10 @@ pub fn update_backend_status(backend: Backend, backend_status: BackendStatus) {
     });
 }
 
+pub fn show_notification(level: NotificationLevel, message: String) {
+    enqueue_update(move |_, state| state.notifications.add(level, message));
+}
+
 pub fn reset_ui(ctx: &Context, state: &mut OcrApp) {
     ctx.memory_mut(|x| *x = Memory::default());
     state.reset();
@@ -1,13 +1,30 @@
+use crate::jpn::kana::{is_kanji, to_hiragana};
 use crate::ui::shutdown::TASK_TRACKER;
-use jmdict::{Entry, KanjiElement, ReadingElement};
+use itertools::Itertools;
+use jmdict::{Entry, GlossLanguage, KanjiElement, ReadingElement};
 use multimap::MultiMap;
 use std::sync::LazyLock;
 
 const WINDOW_SIZE: usize = 50;
 const LARGEST_WORD_SIZE: usize = 15;
 const STEP_SIZE: usize = WINDOW_SIZE - LARGEST_WORD_SIZE;
+const MAX_SEARCH_RESULTS: usize = 50;
 
 static JMDICT_MAP: LazyLock<MultiMap<char, Entry>> = LazyLock::new(create_jmdict_map);
+/// Entries by the kanji of their spellings.
+static KANJI_INDEX: LazyLock<MultiMap<char, Entry>> = LazyLock::new(create_kanji_index);
+/// Entries by their readings in hiragana.
+static READING_INDEX: LazyLock<MultiMap<String, Entry>> = LazyLock::new(create_reading_index);
+/// Entries by the lowercase words of their English glosses.
+static GLOSS_INDEX: LazyLock<MultiMap<String, Entry>> = LazyLock::new(create_gloss_index);
+
+/// Builds the indices of the dictionary, they are built on the first lookup otherwise.
+pub fn init_dictionary() {
+    LazyLock::force(&JMDICT_MAP);
+    LazyLock::force(&KANJI_INDEX);
+    LazyLock::force(&READING_INDEX);
+    LazyLock::force(&GLOSS_INDEX);
+}
 
 fn create_jmdict_map() -> MultiMap<char, Entry> {
     let x: Vec<(&'static str, Entry)> = jmdict::entries()
@@ -29,6 +46,58 @@ fn create_jmdict_map() -> MultiMap<char, Entry> {
     map
 }
 
+fn create_kanji_index() -> MultiMap<char, Entry> {
+    let mut map = MultiMap::new();
+    for entry in jmdict::entries() {
+        let kanji = entry
+            .kanji_elements()
+            .flat_map(|k| k.text.chars())
+            .filter(|c| is_kanji(*c))
+            .unique();
+        for c in kanji {
+            map.insert(c, entry);
+        }
+    }
+    map
+}
+
+fn create_reading_index() -> MultiMap<String, Entry> {
+    let mut map = MultiMap::new();
+    for entry in jmdict::entries() {
+        for reading in entry
+            .reading_elements()
+            .map(|r| to_hiragana(r.text))
+            .unique()
+        {
+            map.insert(reading, entry);
+        }
+    }
+    map
+}
+
+fn create_gloss_index() -> MultiMap<String, Entry> {
+    let mut map = MultiMap::new();
+    for entry in jmdict::entries() {
+        let words = entry
+            .senses()
+            .flat_map(|s| s.glosses())
+            .filter(|g| g.language == GlossLanguage::English)
+            .flat_map(|g| get_words(&g.text.to_lowercase()))
+            .unique();
+        for word in words {
+            map.insert(word, entry);
+        }
+    }
+    map
+}
+
+fn get_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.to_string())
+        .collect()
+}
+
 pub async fn async_extract_words(input: &str) -> Vec<(String, Vec<Entry>)> {
     let inter = input.chars().collect::<Vec<char>>();
 
@@ -106,7 +175,7 @@ pub fn remove_whitespace(s: &str) -> String {
     s.split_whitespace().collect()
 }
 
-fn extract_words(input: &str) -> Vec<(String, Vec<Entry>)> {
+pub fn extract_words(input: &str) -> Vec<(String, Vec<Entry>)> {
     let mut output: Vec<(String, Vec<Entry>)> = Vec::new();
     let mut rest: Option<&str> = Some(input);
     while let Some(x) = rest {
@@ -164,6 +233,102 @@ fn get_starting_matches(prefix: &str, entries: impl Iterator<Item = Entry>) -> V
     entries.filter(|e| e.has_prefix(prefix)).collect()
 }
 
+pub fn is_common(entry: &Entry) -> bool {
+    entry.kanji_elements().any(|k| k.priority.is_common())
+        || entry.reading_elements().any(|r| r.priority.is_common())
+}
+
+/// Sort key for entries: common words first, then by JMdict frequency bucket (nf01 - nf48).
+pub fn get_commonness_rank(entry: &Entry) -> (bool, u16) {
+    let frequency_bucket = entry
+        .kanji_elements()
+        .map(|k| k.priority.frequency_bucket)
+        .chain(
+            entry
+                .reading_elements()
+                .map(|r| r.priority.frequency_bucket),
+        )
+        .filter(|bucket| *bucket > 0)
+        .min()
+        .unwrap_or(u16::MAX);
+
+    (!is_common(entry), frequency_bucket)
+}
+
+pub fn search_by_kanji(kanji: char) -> Vec<Entry> {
+    KANJI_INDEX
+        .get_vec(&kanji)
+        .into_iter()
+        .flatten()
+        .copied()
+        .sorted_by_key(get_commonness_rank)
+        .take(MAX_SEARCH_RESULTS)
+        .collect()
+}
+
+/// Words with the reading, hiragana and katakana are not distinguished.
+pub fn search_by_reading(reading: &str) -> Vec<Entry> {
+    READING_INDEX
+        .get_vec(&to_hiragana(reading))
+        .into_iter()
+        .flatten()
+        .copied()
+        .sorted_by_key(|e| !is_common(e))
+        .take(MAX_SEARCH_RESULTS)
+        .collect()
+}
+
+pub fn search_by_gloss(query: &str) -> Vec<Entry> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return vec![];
+    }
+
+    // A gloss containing the query contains its longest word inside one of its words
+    let candidates: Vec<Entry> = match get_words(&query).into_iter().max_by_key(|x| x.len()) {
+        Some(word) => GLOSS_INDEX
+            .iter_all()
+            .filter(|(gloss_word, _)| gloss_word.contains(&word))
+            .flat_map(|(_, entries)| entries)
+            .copied()
+            .unique_by(|e| e.number)
+            .collect(),
+        None => jmdict::entries().collect(),
+    };
+
+    candidates
+        .into_iter()
+        .filter_map(|e| get_gloss_rank(&e, &query).map(|rank| (rank, !is_common(&e), e)))
+        .sorted_by_key(|(rank, uncommon, _)| (*rank, *uncommon))
+        .map(|(_, _, e)| e)
+        .take(MAX_SEARCH_RESULTS)
+        .collect()
+}
+
+/// Lower is better: 0 = whole gloss matches, 1 = whole word inside a gloss, 2 = substring.
+fn get_gloss_rank(entry: &Entry, query: &str) -> Option<u8> {
+    entry
+        .senses()
+        .flat_map(|s| s.glosses())
+        .filter(|g| g.language == GlossLanguage::English)
+        .filter_map(|g| {
+            let gloss = g.text.to_lowercase();
+            if gloss == query {
+                Some(0)
+            } else if gloss
+                .split(|c: char| !c.is_alphanumeric())
+                .any(|word| word == query)
+            {
+                Some(1)
+            } else if gloss.contains(query) {
+                Some(2)
+            } else {
+                None
+            }
+        })
+        .min()
+}
+
 trait HasText {
     fn get_text(&self) -> &'static str;
 }
@@ -231,6 +396,71 @@ mod tests {
 
 三業オネ各政タホ技九づッン題任ノリ載75左ゅとのあ豆条必野きりゅ一際最ナアカロ高8著ンごイな区港まさ日天よびド収金ょぽ。睦べむクふ実93家福ウツヘ競満万キハモソ長投せ強巨そ観条マセ速能続ぶづの使保ゆ試町ラア江雑コナ福富開王乏えか。悪どぜとせ遺意志ムヒ事経からス真取ぴぐっ芸験ざ闘調たざへ広上ぶ聞題メワテヘ阜13家ネサ家秋ラ経都チメヨ職左削幸績よし。";
 
+    #[test]
+    fn test_search_by_reading() {
+        let numbers = |reading| {
+            search_by_reading(reading)
+                .iter()
+                .map(|e| e.number)
+                .collect::<Vec<_>>()
+        };
+
+        let neko = numbers("ねこ");
+        assert!(
+            search_by_reading("ねこ")
+                .iter()
+                .any(|e| e.kanji_elements().any(|k| k.text == "猫"))
+        );
+        assert_eq!(numbers("ネコ"), neko);
+        assert_eq!(numbers("ねコ"), neko);
+
+        // Loanwords are written in katakana in the dictionary
+        assert!(!numbers("かめら").is_empty());
+        assert_eq!(numbers("かめら"), numbers("カメラ"));
+        assert!(numbers("").is_empty());
+    }
+
+    #[test]
+    fn test_search_katakana_input() {
+        // Katakana input finds the words with a hiragana reading
+        let entries = search_by_reading("タベル");
+        assert!(!entries.is_empty());
+        assert!(
+            entries
+                .iter()
+                .all(|e| e.reading_elements().any(|r| r.text == "たべる"))
+        );
+    }
+
+    #[test]
+    fn test_search_by_kanji() {
+        let entries = search_by_kanji('猫');
+        assert!(!entries.is_empty());
+        assert!(
+            entries
+                .iter()
+                .all(|e| e.kanji_elements().any(|k| k.text.contains('猫')))
+        );
+        assert!(search_by_kanji('a').is_empty());
+    }
+
+    #[test]
+    fn test_search_by_gloss() {
+        let entries = search_by_gloss("Cat");
+        assert!(
+            entries
+                .iter()
+                .take(10)
+                .any(|e| e.kanji_elements().any(|k| k.text == "猫"))
+        );
+        assert!(entries.iter().all(|e| get_gloss_rank(e, "cat").is_some()));
+
+        // Substrings of gloss words and several words are found
+        assert!(!search_by_gloss("caterpil").is_empty());
+        assert!(!search_by_gloss("to eat").is_empty());
+        assert!(search_by_gloss(" ").is_empty());
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn benchmark() {
         let input = LOREM.repeat(2);
@@ -0,0 +1,130 @@
+use crate::jpn::JpnData;
+use crate::jpn::kanji::KanjiData;
+use std::collections::HashSet;
+
+const LEVEL_WEIGHT: f32 = 0.5;
+const UNKNOWN_WEIGHT: f32 = 0.5;
+const MAX_WK_LEVEL: f32 = 60.0;
+
+/// Hardness of a kanji from 0.0 (N5, first WaniKani level or first grade) to 1.0 (N1 or no level at all).
+pub fn get_kanji_hardness(kanji_data: &KanjiData) -> f32 {
+    if let Some(jlpt) = kanji_data.jlpt_new {
+        return (5.0 - jlpt.clamp(1, 5) as f32) / 4.0;
+    }
+    if let Some(wk_level) = kanji_data.wk_level {
+        return (wk_level.max(1) as f32 - 1.0) / (MAX_WK_LEVEL - 1.0);
+    }
+    if let Some(grade) = kanji_data.grade {
+        return (grade.clamp(1, 9) as f32 - 1.0) / 8.0;
+    }
+    1.0
+}
+
+/// Hardness of the hardest kanji in the word, kana only words are 0.0.
+pub fn get_word_hardness(jpn_data: &JpnData) -> f32 {
+    jpn_data
+        .words
+        .iter()
+        .filter_map(|x| x.kanji_data.as_ref())
+        .map(get_kanji_hardness)
+        .fold(0.0, f32::max)
+}
+
+/// Difficulty from 0 to 100 combining the kanji levels and the share of unknown words.
+/// `None` if there are no dictionary words.
+pub fn get_difficulty<'a>(
+    words: impl IntoIterator<Item = &'a JpnData>,
+    known_words: &HashSet<String>,
+) -> Option<f32> {
+    let (count, hardness, unknown) = words.into_iter().filter(|x| x.has_kanji_data()).fold(
+        (0, 0.0, 0),
+        |(count, hardness, unknown), word| {
+            let is_unknown = !known_words.contains(&word.get_dictionary_form());
+            (
+                count + 1,
+                hardness + get_word_hardness(word),
+                unknown + is_unknown as usize,
+            )
+        },
+    );
+
+    if count == 0 {
+        return None;
+    }
+
+    let count = count as f32;
+    let score = LEVEL_WEIGHT * hardness / count + UNKNOWN_WEIGHT * unknown as f32 / count;
+    Some(100.0 * score)
+}
+
+/// Average difficulty of the pages captured since the start of the app or the last reset.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SessionDifficulty {
+    pub page_count: u32,
+    total: f32,
+}
+
+impl SessionDifficulty {
+    pub fn add_page(&mut self, difficulty: f32) {
+        self.page_count += 1;
+        self.total += difficulty;
+    }
+
+    pub fn get_average(&self) -> Option<f32> {
+        (self.page_count > 0).then(|| self.total / self.page_count as f32)
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jpn::JpnWordInfo;
+
+    fn kanji_data(jlpt_new: Option<u8>, wk_level: Option<u8>, grade: Option<u8>) -> KanjiData {
+        KanjiData {
+            jlpt_new,
+            wk_level,
+            grade,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_get_kanji_hardness() {
+        assert_eq!(
+            get_kanji_hardness(&kanji_data(Some(5), Some(60), None)),
+            0.0
+        );
+        assert_eq!(get_kanji_hardness(&kanji_data(Some(1), None, None)), 1.0);
+        assert_eq!(get_kanji_hardness(&kanji_data(Some(3), None, None)), 0.5);
+        assert_eq!(get_kanji_hardness(&kanji_data(None, Some(1), None)), 0.0);
+        assert_eq!(get_kanji_hardness(&kanji_data(None, None, Some(9))), 1.0);
+        assert_eq!(get_kanji_hardness(&kanji_data(None, None, None)), 1.0);
+    }
+
+    fn word(word: char, kanji_data: Option<KanjiData>) -> JpnData {
+        JpnData {
+            words: vec![JpnWordInfo { word, kanji_data }],
+            jm_dict: vec![],
+        }
+    }
+
+    #[test]
+    fn test_get_difficulty() {
+        let easy = word('日', Some(kanji_data(Some(5), None, None)));
+        let hard = word('鬱', Some(kanji_data(Some(1), None, None)));
+        let kana = word('の', None);
+
+        assert_eq!(get_difficulty([&kana], &HashSet::new()), None);
+        assert_eq!(get_difficulty([&easy, &kana], &HashSet::new()), Some(50.0));
+        assert_eq!(get_difficulty([&hard], &HashSet::new()), Some(100.0));
+
+        let known_words = HashSet::from(["日".to_string()]);
+        assert_eq!(get_difficulty([&easy], &known_words), Some(0.0));
+        assert_eq!(get_difficulty([&easy, &hard], &known_words), Some(50.0));
+    }
+}
@@ -0,0 +1,297 @@
+const KATAKANA_START: u32 = 0x30A1;
+const KATAKANA_END: u32 = 0x30F6;
+const KATAKANA_OFFSET: u32 = 0x60;
+
+pub fn to_hiragana(input: &str) -> String {
+    input
+        .chars()
+        .map(|c| {
+            let code = c as u32;
+            if (KATAKANA_START..=KATAKANA_END).contains(&code) {
+                char::from_u32(code - KATAKANA_OFFSET).unwrap_or(c)
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+pub fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{309F}' | '\u{30A0}'..='\u{30FF}')
+}
+
+pub fn is_kanji(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '々')
+}
+
+/// Splits a word into segments with their reading, kana segments like okurigana have no reading.
+pub fn split_furigana(surface: &str, reading: &str) -> Vec<(String, Option<String>)> {
+    let surface_chars: Vec<char> = surface.chars().collect();
+    let reading_chars: Vec<char> = to_hiragana(reading).chars().collect();
+    let normalized: Vec<char> = to_hiragana(surface).chars().collect();
+
+    let prefix = normalized
+        .iter()
+        .zip(reading_chars.iter())
+        .take_while(|(s, r)| is_kana(**s) && s == r)
+        .count();
+    let suffix = normalized[prefix..]
+        .iter()
+        .rev()
+        .zip(reading_chars[prefix..].iter().rev())
+        .take_while(|(s, r)| is_kana(**s) && s == r)
+        .count();
+
+    let core_end = surface_chars.len() - suffix;
+    let core_reading: String = reading_chars[prefix..reading_chars.len() - suffix]
+        .iter()
+        .collect();
+
+    let segments = [
+        (surface_chars[..prefix].iter().collect::<String>(), None),
+        (
+            surface_chars[prefix..core_end].iter().collect(),
+            Some(core_reading).filter(|x| !x.is_empty()),
+        ),
+        (surface_chars[core_end..].iter().collect(), None),
+    ];
+
+    segments
+        .into_iter()
+        .filter(|(text, _)| !text.is_empty())
+        .collect()
+}
+
+const ROMAJI_DIGRAPHS: [(&str, &str); 36] = [
+    ("きゃ", "kya"),
+    ("きゅ", "kyu"),
+    ("きょ", "kyo"),
+    ("しゃ", "sha"),
+    ("しゅ", "shu"),
+    ("しょ", "sho"),
+    ("ちゃ", "cha"),
+    ("ちゅ", "chu"),
+    ("ちょ", "cho"),
+    ("にゃ", "nya"),
+    ("にゅ", "nyu"),
+    ("にょ", "nyo"),
+    ("ひゃ", "hya"),
+    ("ひゅ", "hyu"),
+    ("ひょ", "hyo"),
+    ("みゃ", "mya"),
+    ("みゅ", "myu"),
+    ("みょ", "myo"),
+    ("りゃ", "rya"),
+    ("りゅ", "ryu"),
+    ("りょ", "ryo"),
+    ("ぎゃ", "gya"),
+    ("ぎゅ", "gyu"),
+    ("ぎょ", "gyo"),
+    ("じゃ", "ja"),
+    ("じゅ", "ju"),
+    ("じょ", "jo"),
+    ("びゃ", "bya"),
+    ("びゅ", "byu"),
+    ("びょ", "byo"),
+    ("ぴゃ", "pya"),
+    ("ぴゅ", "pyu"),
+    ("ぴょ", "pyo"),
+    ("ふぁ", "fa"),
+    ("てぃ", "ti"),
+    ("でぃ", "di"),
+];
+
+fn get_monograph(c: char) -> Option<&'static str> {
+    let romaji = match c {
+        'あ' => "a",
+        'い' => "i",
+        'う' => "u",
+        'え' => "e",
+        'お' => "o",
+        'か' => "ka",
+        'き' => "ki",
+        'く' => "ku",
+        'け' => "ke",
+        'こ' => "ko",
+        'さ' => "sa",
+        'し' => "shi",
+        'す' => "su",
+        'せ' => "se",
+        'そ' => "so",
+        'た' => "ta",
+        'ち' => "chi",
+        'つ' => "tsu",
+        'て' => "te",
+        'と' => "to",
+        'な' => "na",
+        'に' => "ni",
+        'ぬ' => "nu",
+        'ね' => "ne",
+        'の' => "no",
+        'は' => "ha",
+        'ひ' => "hi",
+        'ふ' => "fu",
+        'へ' => "he",
+        'ほ' => "ho",
+        'ま' => "ma",
+        'み' => "mi",
+        'む' => "mu",
+        'め' => "me",
+        'も' => "mo",
+        'や' => "ya",
+        'ゆ' => "yu",
+        'よ' => "yo",
+        'ら' => "ra",
+        'り' => "ri",
+        'る' => "ru",
+        'れ' => "re",
+        'ろ' => "ro",
+        'わ' => "wa",
+        'ゐ' => "wi",
+        'ゑ' => "we",
+        'を' => "wo",
+        'ん' => "n",
+        'が' => "ga",
+        'ぎ' => "gi",
+        'ぐ' => "gu",
+        'げ' => "ge",
+        'ご' => "go",
+        'ざ' => "za",
+        'じ' => "ji",
+        'ず' => "zu",
+        'ぜ' => "ze",
+        'ぞ' => "zo",
+        'だ' => "da",
+        'ぢ' => "ji",
+        'づ' => "zu",
+        'で' => "de",
+        'ど' => "do",
+        'ば' => "ba",
+        'び' => "bi",
+        'ぶ' => "bu",
+        'べ' => "be",
+        'ぼ' => "bo",
+        'ぱ' => "pa",
+        'ぴ' => "pi",
+        'ぷ' => "pu",
+        'ぺ' => "pe",
+        'ぽ' => "po",
+        'ゔ' => "vu",
+        'ぁ' => "a",
+        'ぃ' => "i",
+        'ぅ' => "u",
+        'ぇ' => "e",
+        'ぉ' => "o",
+        'ゃ' => "ya",
+        'ゅ' => "yu",
+        'ょ' => "yo",
+        'ゎ' => "wa",
+        '、' => ",",
+        '。' => ".",
+        '！' => "!",
+        '？' => "?",
+        _ => return None,
+    };
+    Some(romaji)
+}
+
+/// Hepburn romanization of hiragana and katakana, other characters are kept as they are.
+pub fn to_romaji(kana: &str) -> String {
+    let chars: Vec<char> = to_hiragana(kana).chars().collect();
+    let mut output = String::new();
+    let mut double_next = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == 'っ' {
+            double_next = true;
+            i += 1;
+            continue;
+        }
+
+        if c == 'ー' {
+            if let Some(vowel) = output.chars().last().filter(|x| "aeiou".contains(*x)) {
+                output.push(vowel);
+            }
+            i += 1;
+            continue;
+        }
+
+        let digraph: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+        let (romaji, length) = match ROMAJI_DIGRAPHS.iter().find(|(k, _)| *k == digraph) {
+            Some((_, romaji)) => (romaji.to_string(), 2),
+            None => match get_monograph(c) {
+                Some(romaji) => (romaji.to_string(), 1),
+                None => (c.to_string(), 1),
+            },
+        };
+
+        if double_next {
+            if let Some(first) = romaji.chars().next().filter(|x| x.is_ascii_alphabetic()) {
+                // Hepburn writes っち as "tchi"
+                output.push(if romaji.starts_with("ch") { 't' } else { first });
+            }
+            double_next = false;
+        }
+
+        // ん before a vowel or y is written as "n'"
+        if output.ends_with('n')
+            && i > 0
+            && chars[i - 1] == 'ん'
+            && romaji.starts_with(['a', 'i', 'u', 'e', 'o', 'y'])
+        {
+            output.push('\'');
+        }
+
+        output.push_str(&romaji);
+        i += length;
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_hiragana() {
+        assert_eq!(to_hiragana("カタカナ"), "かたかな");
+        assert_eq!(to_hiragana("ひらがな"), "ひらがな");
+        assert_eq!(to_hiragana("漢字とカナ"), "漢字とかな");
+    }
+
+    #[test]
+    fn test_split_furigana() {
+        assert_eq!(
+            split_furigana("食べる", "たべる"),
+            vec![
+                ("食".to_string(), Some("た".to_string())),
+                ("べる".to_string(), None)
+            ]
+        );
+        assert_eq!(
+            split_furigana("お茶", "おちゃ"),
+            vec![
+                ("お".to_string(), None),
+                ("茶".to_string(), Some("ちゃ".to_string()))
+            ]
+        );
+        assert_eq!(
+            split_furigana("漢字", "かんじ"),
+            vec![("漢字".to_string(), Some("かんじ".to_string()))]
+        );
+    }
+
+    #[test]
+    fn test_to_romaji() {
+        assert_eq!(to_romaji("かんじ"), "kanji");
+        assert_eq!(to_romaji("きょうと"), "kyouto");
+        assert_eq!(to_romaji("がっこう"), "gakkou");
+        assert_eq!(to_romaji("まっちゃ"), "matcha");
+        assert_eq!(to_romaji("きんえん"), "kin'en");
+        assert_eq!(to_romaji("コーヒー"), "koohii");
+    }
+}
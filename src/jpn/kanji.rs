@@ -1,3 +1,5 @@
+use crate::jpn::kana::to_hiragana;
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use serde_with::{DefaultOnNull, serde_as};
 use std::collections::HashMap;
@@ -33,6 +35,36 @@ pub fn get_kanji_data(word: char) -> Option<KanjiData> {
     KANJI_MAP.get(&word).cloned()
 }
 
+pub fn search_kanji_by_reading(reading: &str) -> Vec<char> {
+    let reading = to_hiragana(reading);
+    KANJI_MAP
+        .iter()
+        .filter(|(_, data)| data.has_reading(&reading))
+        .sorted_by_key(|(kanji, data)| (data.freq.unwrap_or(u16::MAX), **kanji))
+        .map(|(kanji, _)| *kanji)
+        .collect()
+}
+
+pub fn get_kanji_with_radical(radical: &str) -> Vec<char> {
+    KANJI_MAP
+        .iter()
+        .filter(|(_, data)| data.wk_radicals.iter().any(|x| x == radical))
+        .sorted_by_key(|(kanji, data)| (data.freq.unwrap_or(u16::MAX), **kanji))
+        .map(|(kanji, _)| *kanji)
+        .collect()
+}
+
+impl KanjiData {
+    /// Readings are stored with okurigana markers like `ひと.つ` or `-ぶり`, only the stem is compared.
+    fn has_reading(&self, reading: &str) -> bool {
+        self.readings_on
+            .iter()
+            .chain(self.readings_kun.iter())
+            .filter_map(|x| x.split('.').next())
+            .any(|x| x.trim_matches('-') == reading)
+    }
+}
+
 fn get_map_from_json(json: &str) -> HashMap<char, KanjiData> {
     serde_json::from_str(json).unwrap()
 }
@@ -0,0 +1,338 @@
+use crate::ui::shutdown::TASK_TRACKER;
+use anyhow::{Context, Result, bail};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// KanjiVG drawings use a 109x109 view box.
+pub const VIEW_BOX_SIZE: f32 = 109.0;
+const BEZIER_SEGMENTS: usize = 8;
+
+pub type Stroke = Vec<[f32; 2]>;
+
+#[derive(Debug, Clone)]
+pub enum StrokeState {
+    Loading,
+    Loaded(Arc<Vec<Stroke>>),
+    Error(String),
+}
+
+type StrokeCache = Mutex<HashMap<(String, char), StrokeState>>;
+
+static STROKE_CACHE: LazyLock<StrokeCache> = LazyLock::new(StrokeCache::default);
+
+/// Content of single file KanjiVG archives (kanjivg-*.xml) by path.
+static ARCHIVE_CACHE: LazyLock<Mutex<HashMap<String, Arc<String>>>> = LazyLock::new(Mutex::default);
+
+/// Returns the cached strokes and starts loading them in the background if they are missing.
+pub fn get_strokes(source: &str, kanji: char) -> StrokeState {
+    let key = (source.to_string(), kanji);
+    let mut cache = STROKE_CACHE.lock().unwrap();
+    if let Some(state) = cache.get(&key) {
+        return state.clone();
+    }
+    cache.insert(key.clone(), StrokeState::Loading);
+
+    // Parsing a whole archive takes a while, it must not block the async workers
+    TASK_TRACKER.spawn_blocking(move || {
+        let state = match load_strokes(&key.0, key.1) {
+            Ok(strokes) => StrokeState::Loaded(Arc::new(strokes)),
+            Err(err) => StrokeState::Error(err.to_string()),
+        };
+        STROKE_CACHE.lock().unwrap().insert(key, state);
+    });
+
+    StrokeState::Loading
+}
+
+pub fn clear_stroke_cache() {
+    STROKE_CACHE.lock().unwrap().clear();
+    ARCHIVE_CACHE.lock().unwrap().clear();
+}
+
+fn load_strokes(source: &str, kanji: char) -> Result<Vec<Stroke>> {
+    let path = Path::new(source);
+    let file_name = get_file_name(kanji);
+
+    let svg = if path.is_dir() {
+        let file = [path.join("kanji").join(&file_name), path.join(&file_name)]
+            .into_iter()
+            .find(|x| x.exists())
+            .with_context(|| format!("{file_name} not found in {source}"))?;
+        std::fs::read_to_string(file)?
+    } else if path.is_file() {
+        let archive = load_archive(source)?;
+        get_archive_entry(&archive, kanji)
+            .with_context(|| format!("{kanji} not found in {source}"))?
+            .to_string()
+    } else {
+        bail!("KanjiVG path '{source}' does not exist");
+    };
+
+    let strokes = parse_strokes(&svg);
+    if strokes.is_empty() {
+        bail!("No strokes found for {kanji}");
+    }
+    Ok(strokes)
+}
+
+fn get_file_name(kanji: char) -> String {
+    format!("{:05x}.svg", kanji as u32)
+}
+
+fn load_archive(source: &str) -> Result<Arc<String>> {
+    let mut cache = ARCHIVE_CACHE.lock().unwrap();
+    if let Some(archive) = cache.get(source) {
+        return Ok(archive.clone());
+    }
+
+    let archive = Arc::new(std::fs::read_to_string(source)?);
+    cache.insert(source.to_string(), archive.clone());
+    Ok(archive)
+}
+
+fn get_archive_entry(archive: &str, kanji: char) -> Option<&str> {
+    let start_tag = format!("<kanji id=\"kvg:kanji_{:05x}\">", kanji as u32);
+    let start = archive.find(&start_tag)?;
+    let end = archive[start..].find("</kanji>")? + start;
+    Some(&archive[start..end])
+}
+
+/// Extracts the `d` attribute of every `<path>` element in document order.
+fn parse_strokes(svg: &str) -> Vec<Stroke> {
+    svg.split("<path")
+        .skip(1)
+        .filter_map(|element| {
+            let element = &element[..element.find('>')?];
+            let start = element.find(" d=\"")? + 4;
+            let end = element[start..].find('"')? + start;
+            Some(parse_path(&element[start..end]))
+        })
+        .filter(|stroke| !stroke.is_empty())
+        .collect()
+}
+
+/// Minimal SVG path parser for the commands used by KanjiVG. Curves are flattened to polylines.
+fn parse_path(d: &str) -> Stroke {
+    let tokens = tokenize_path(d);
+    let mut points: Stroke = vec![];
+    let mut current = [0.0f32, 0.0];
+    let mut start = current;
+    let mut last_control: Option<[f32; 2]> = None;
+    let mut command = 'M';
+    let mut i = 0;
+
+    let number = |i: &mut usize| -> Option<f32> {
+        match tokens.get(*i) {
+            Some(PathToken::Number(x)) => {
+                *i += 1;
+                Some(*x)
+            }
+            _ => None,
+        }
+    };
+
+    while i < tokens.len() {
+        if let PathToken::Command(c) = tokens[i] {
+            command = c;
+            i += 1;
+        }
+
+        let relative = command.is_ascii_lowercase();
+        let offset = |p: [f32; 2], current: [f32; 2]| {
+            if relative {
+                [p[0] + current[0], p[1] + current[1]]
+            } else {
+                p
+            }
+        };
+
+        match command.to_ascii_uppercase() {
+            'M' | 'L' => {
+                let (Some(x), Some(y)) = (number(&mut i), number(&mut i)) else {
+                    break;
+                };
+                current = offset([x, y], current);
+                if command.eq_ignore_ascii_case(&'M') {
+                    start = current;
+                    // Following coordinate pairs are implicit line commands
+                    command = if relative { 'l' } else { 'L' };
+                }
+                points.push(current);
+                last_control = None;
+            }
+            'H' | 'V' => {
+                let Some(value) = number(&mut i) else {
+                    break;
+                };
+                let horizontal = command.eq_ignore_ascii_case(&'H');
+                let base = if relative { current } else { [0.0, 0.0] };
+                if horizontal {
+                    current[0] = base[0] + value;
+                } else {
+                    current[1] = base[1] + value;
+                }
+                points.push(current);
+                last_control = None;
+            }
+            'C' | 'S' => {
+                let smooth = command.eq_ignore_ascii_case(&'S');
+                let control1 = if smooth {
+                    last_control.map_or(current, |c| {
+                        [2.0 * current[0] - c[0], 2.0 * current[1] - c[1]]
+                    })
+                } else {
+                    let (Some(x), Some(y)) = (number(&mut i), number(&mut i)) else {
+                        break;
+                    };
+                    offset([x, y], current)
+                };
+                let (Some(x2), Some(y2), Some(x), Some(y)) = (
+                    number(&mut i),
+                    number(&mut i),
+                    number(&mut i),
+                    number(&mut i),
+                ) else {
+                    break;
+                };
+                let control2 = offset([x2, y2], current);
+                let end = offset([x, y], current);
+
+                points.extend(flatten_cubic(current, control1, control2, end));
+                current = end;
+                last_control = Some(control2);
+            }
+            'Z' => {
+                current = start;
+                points.push(current);
+                last_control = None;
+                // Close path has no arguments, stray numbers after it are skipped
+                while number(&mut i).is_some() {}
+            }
+            _ => {
+                // Unsupported command, skip its arguments
+                i += 1;
+            }
+        }
+    }
+
+    points
+}
+
+fn flatten_cubic(p0: [f32; 2], p1: [f32; 2], p2: [f32; 2], p3: [f32; 2]) -> Vec<[f32; 2]> {
+    (1..=BEZIER_SEGMENTS)
+        .map(|step| {
+            let t = step as f32 / BEZIER_SEGMENTS as f32;
+            let mt = 1.0 - t;
+            let a = mt * mt * mt;
+            let b = 3.0 * mt * mt * t;
+            let c = 3.0 * mt * t * t;
+            let d = t * t * t;
+            [
+                a * p0[0] + b * p1[0] + c * p2[0] + d * p3[0],
+                a * p0[1] + b * p1[1] + c * p2[1] + d * p3[1],
+            ]
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PathToken {
+    Command(char),
+    Number(f32),
+}
+
+fn tokenize_path(d: &str) -> Vec<PathToken> {
+    let mut tokens = vec![];
+    let mut number = String::new();
+
+    let flush = |number: &mut String, tokens: &mut Vec<PathToken>| {
+        if let Ok(x) = number.parse::<f32>() {
+            tokens.push(PathToken::Number(x));
+        }
+        number.clear();
+    };
+
+    for c in d.chars() {
+        match c {
+            c if c.is_ascii_alphabetic() && c != 'e' && c != 'E' => {
+                flush(&mut number, &mut tokens);
+                tokens.push(PathToken::Command(c));
+            }
+            '-' if !number.ends_with(['e', 'E']) => {
+                flush(&mut number, &mut tokens);
+                number.push(c);
+            }
+            '.' if number.contains('.') => {
+                // "0.5.5" is shorthand for "0.5 0.5"
+                flush(&mut number, &mut tokens);
+                number.push(c);
+            }
+            ',' | ' ' | '\n' | '\t' | '\r' => flush(&mut number, &mut tokens),
+            c => number.push(c),
+        }
+    }
+    flush(&mut number, &mut tokens);
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVG: &str = r#"<g id="kvg:StrokePaths_04e8c">
+<path id="kvg:04e8c-s1" kvg:type="㇐" d="M24.25,31.5c1.75,0.5,3.5,0.5,5.25,0.25"/>
+<path id="kvg:04e8c-s2" kvg:type="㇐" d="M11.25,78.5L97.5,76.75"/>
+</g>"#;
+
+    #[test]
+    fn test_parse_strokes() {
+        let strokes = parse_strokes(SVG);
+
+        assert_eq!(strokes.len(), 2);
+        assert_eq!(strokes[0][0], [24.25, 31.5]);
+        assert_eq!(strokes[0].len(), 1 + BEZIER_SEGMENTS);
+        let end = strokes[0].last().unwrap();
+        assert!((end[0] - 29.5).abs() < 0.001);
+        assert!((end[1] - 31.75).abs() < 0.001);
+        assert_eq!(strokes[1], vec![[11.25, 78.5], [97.5, 76.75]]);
+    }
+
+    #[test]
+    fn test_parse_path_close() {
+        assert_eq!(
+            parse_path("M1,1 L2,2 Z 3"),
+            vec![[1.0, 1.0], [2.0, 2.0], [1.0, 1.0]]
+        );
+        assert_eq!(
+            parse_path("M1,1 L2,2 z 3 4 L5,5"),
+            vec![[1.0, 1.0], [2.0, 2.0], [1.0, 1.0], [5.0, 5.0]]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_path() {
+        assert_eq!(
+            tokenize_path("M1-2.5.5c1e-1,2"),
+            vec![
+                PathToken::Command('M'),
+                PathToken::Number(1.0),
+                PathToken::Number(-2.5),
+                PathToken::Number(0.5),
+                PathToken::Command('c'),
+                PathToken::Number(0.1),
+                PathToken::Number(2.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_archive_entry() {
+        let archive = format!("<kanjivg><kanji id=\"kvg:kanji_04e8c\">{SVG}</kanji></kanjivg>");
+
+        let entry = get_archive_entry(&archive, '二').unwrap();
+        assert_eq!(parse_strokes(entry).len(), 2);
+        assert!(get_archive_entry(&archive, '一').is_none());
+    }
+}
@@ -1,9 +1,14 @@
-use crate::jpn::kanji::{KanjiData, get_kanji_data};
+use crate::jpn::kanji::{KanjiData, get_kanji_data, search_kanji_by_reading};
 use crate::ui::shutdown::TASK_TRACKER;
+use itertools::Itertools;
 use jmdict::{Entry, GlossLanguage};
+use std::cmp::Reverse;
 
 pub mod dict;
+pub mod difficulty;
+pub mod kana;
 pub mod kanji;
+pub mod kanjivg;
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Default, PartialEq, Clone)]
 #[serde(default)]
@@ -21,6 +26,17 @@ impl JpnData {
         Self { words, jm_dict }
     }
 
+    fn from_entry(entry: &Entry) -> Self {
+        let word = entry
+            .kanji_elements()
+            .map(|k| k.text)
+            .chain(entry.reading_elements().map(|r| r.text))
+            .next()
+            .unwrap_or_default();
+
+        Self::new(word, &[*entry])
+    }
+
     pub fn has_kanji_data(&self) -> bool {
         self.words.iter().any(|w| w.kanji_data.is_some())
             || self.jm_dict.iter().any(|w| !w.info.is_empty())
@@ -30,6 +46,113 @@ impl JpnData {
         self.words.iter().map(|x| x.word).collect()
     }
 
+    /// Selects the reading of the best matching JMdict entry, `None` if the word has no kanji.
+    pub fn get_reading(&self) -> Option<String> {
+        let surface = self.get_kanji();
+        if !surface.chars().any(kana::is_kanji) {
+            return None;
+        }
+
+        self.get_best_match()
+            .map(|(_, reading)| reading.clone())
+            .or_else(|| self.get_kanji_reading())
+    }
+
+    /// The JMdict entry which fits the surface form best, common entries are preferred for kana words.
+    pub fn get_best_entry(&self) -> Option<&JmDictInfo> {
+        self.get_best_match().map(|(entry, _)| entry).or_else(|| {
+            self.jm_dict
+                .iter()
+                .enumerate()
+                .max_by_key(|(index, entry)| (entry.common, Reverse(*index)))
+                .map(|(_, entry)| entry)
+        })
+    }
+
+    /// Dictionary form of the best entry, falls back to the surface form.
+    pub fn get_dictionary_form(&self) -> String {
+        self.get_best_entry()
+            .and_then(|entry| entry.kanji.first().or(entry.readings.first()))
+            .cloned()
+            .unwrap_or_else(|| self.get_kanji())
+    }
+
+    /// Highest WaniKani level of the kanji in the word.
+    pub fn get_wk_level(&self) -> Option<u8> {
+        self.words
+            .iter()
+            .filter_map(|x| x.kanji_data.as_ref()?.wk_level)
+            .max()
+    }
+
+    /// Highest school grade of the kanji in the word.
+    pub fn get_grade(&self) -> Option<u8> {
+        self.words
+            .iter()
+            .filter_map(|x| x.kanji_data.as_ref()?.grade)
+            .max()
+    }
+
+    /// Hardest JLPT level of the kanji in the word, 1 is N1.
+    pub fn get_jlpt_level(&self) -> Option<u8> {
+        self.words
+            .iter()
+            .filter_map(|x| x.kanji_data.as_ref()?.jlpt_new)
+            .min()
+    }
+
+    fn get_best_match(&self) -> Option<(&JmDictInfo, &String)> {
+        let surface = self.get_kanji();
+
+        self.jm_dict
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.kanji.contains(&surface))
+            .flat_map(|(entry_index, entry)| {
+                entry
+                    .readings
+                    .iter()
+                    .enumerate()
+                    .map(move |(reading_index, reading)| {
+                        (entry_index, entry, reading_index, reading)
+                    })
+            })
+            .max_by_key(|(entry_index, entry, reading_index, reading)| {
+                (
+                    matches_okurigana(&surface, reading),
+                    self.matches_kanji_readings(reading),
+                    entry.common,
+                    Reverse(*entry_index),
+                    Reverse(*reading_index),
+                )
+            })
+            .map(|(_, entry, _, reading)| (entry, reading))
+    }
+
+    /// Checks if the reading starts with a reading of the first kanji.
+    fn matches_kanji_readings(&self, reading: &str) -> bool {
+        let reading = kana::to_hiragana(reading);
+        self.words
+            .iter()
+            .find_map(|x| x.kanji_data.as_ref())
+            .is_some_and(|kanji_data| {
+                get_kanji_readings(kanji_data)
+                    .iter()
+                    .any(|x| reading.starts_with(x.as_str()))
+            })
+    }
+
+    /// Fallback reading for words without JMdict entry, combined from the kanji readings.
+    fn get_kanji_reading(&self) -> Option<String> {
+        self.words
+            .iter()
+            .map(|x| match &x.kanji_data {
+                Some(kanji_data) => get_kanji_readings(kanji_data).into_iter().next(),
+                None => Some(x.word.to_string()),
+            })
+            .collect()
+    }
+
     pub fn get_info_rows(&self) -> Vec<String> {
         if self.words.is_empty() {
             return vec![];
@@ -93,13 +216,81 @@ impl JpnWordInfo {
 #[serde(default)]
 pub struct JmDictInfo {
     pub info: Vec<String>,
+    pub kanji: Vec<String>,
+    /// Readings with common readings first
+    pub readings: Vec<String>,
+    pub common: bool,
+    pub senses: Vec<SenseInfo>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default, PartialEq, Clone)]
+#[serde(default)]
+pub struct SenseInfo {
+    pub parts_of_speech: Vec<String>,
+    /// English glosses
+    pub glosses: Vec<String>,
 }
 
 impl JmDictInfo {
     fn new(entry: &Entry) -> Self {
         let info: Vec<String> = get_info_from_entry(entry).into_iter().collect();
-        Self { info }
+        let kanji = entry.kanji_elements().map(|k| k.text.to_string()).collect();
+        let readings = entry
+            .reading_elements()
+            .sorted_by_key(|r| !r.priority.is_common())
+            .map(|r| r.text.to_string())
+            .collect();
+
+        Self {
+            info,
+            kanji,
+            readings,
+            common: dict::is_common(entry),
+            senses: get_senses_from_entry(entry),
+        }
+    }
+
+    pub fn get_top_gloss(&self) -> Option<&String> {
+        self.senses.iter().flat_map(|x| x.glosses.first()).next()
     }
+
+    pub fn get_parts_of_speech(&self) -> Vec<String> {
+        self.senses
+            .iter()
+            .flat_map(|x| x.parts_of_speech.iter().cloned())
+            .unique()
+            .collect()
+    }
+}
+
+/// Kun readings before on readings in hiragana without okurigana markers.
+fn get_kanji_readings(kanji_data: &KanjiData) -> Vec<String> {
+    kanji_data
+        .readings_kun
+        .iter()
+        .chain(kanji_data.readings_on.iter())
+        .filter_map(|x| x.split('.').next())
+        .map(|x| kana::to_hiragana(x.trim_matches('-')))
+        .filter(|x| !x.is_empty())
+        .collect()
+}
+
+/// Checks if the kana before and after the kanji of the word are part of the reading.
+fn matches_okurigana(surface: &str, reading: &str) -> bool {
+    let surface = kana::to_hiragana(surface);
+    let reading = kana::to_hiragana(reading);
+
+    let prefix: String = surface.chars().take_while(|c| kana::is_kana(*c)).collect();
+    let suffix: String = surface
+        .chars()
+        .rev()
+        .take_while(|c| kana::is_kana(*c))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+
+    reading.starts_with(&prefix) && reading.ends_with(&suffix)
 }
 
 pub async fn get_jpn_data(input: &str) -> Vec<Vec<JpnData>> {
@@ -125,6 +316,67 @@ pub async fn get_jpn_data(input: &str) -> Vec<Vec<JpnData>> {
     results
 }
 
+pub async fn search_jpn_data_by_gloss(query: &str) -> Vec<JpnData> {
+    let query = query.to_string();
+    TASK_TRACKER
+        .spawn_blocking(move || {
+            dict::search_by_gloss(&query)
+                .iter()
+                .map(JpnData::from_entry)
+                .collect()
+        })
+        .await
+        .unwrap_or_default()
+}
+
+pub async fn search_jpn_data_by_reading(reading: &str) -> Vec<JpnData> {
+    let reading = dict::remove_whitespace(reading);
+    TASK_TRACKER
+        .spawn_blocking(move || {
+            let words = dict::search_by_reading(&reading)
+                .iter()
+                .map(JpnData::from_entry)
+                .collect::<Vec<_>>();
+
+            let kanji = search_kanji_by_reading(&reading)
+                .into_iter()
+                .map(|x| JpnData::new(&x.to_string(), &[]));
+
+            words.into_iter().chain(kanji).collect()
+        })
+        .await
+        .unwrap_or_default()
+}
+
+/// JMdict words containing the kanji together with their common word flag.
+pub async fn get_kanji_words(kanji: char) -> Vec<(JpnData, bool)> {
+    TASK_TRACKER
+        .spawn_blocking(move || {
+            dict::search_by_kanji(kanji)
+                .iter()
+                .map(|e| (JpnData::from_entry(e), dict::is_common(e)))
+                .collect()
+        })
+        .await
+        .unwrap_or_default()
+}
+
+fn get_senses_from_entry(e: &Entry) -> Vec<SenseInfo> {
+    e.senses()
+        .map(|sense| SenseInfo {
+            parts_of_speech: sense
+                .parts_of_speech()
+                .map(|part| format!("{part}"))
+                .collect(),
+            glosses: sense
+                .glosses()
+                .filter(|g| g.language == GlossLanguage::English)
+                .map(|g| g.text.to_string())
+                .collect(),
+        })
+        .collect()
+}
+
 pub fn get_info_from_entry(e: &Entry) -> Vec<String> {
     let mut output: Vec<String> = Vec::new();
     for kanji in e.kanji_elements() {
@@ -10,18 +10,20 @@
 mod ui;
 
 use action::ScreenshotParameter;
+pub use model::evaluation::run_evaluation;
 pub use ui::app::OcrApp;
 
 use anyhow::{Context, Ok, Result};
 use image::{DynamicImage, RgbaImage};
-use rusty_tesseract::Args;
 use screenshots::Screen;
 
 pub(crate) mod action;
 pub(crate) mod database;
 pub(crate) mod detect;
+pub(crate) mod error;
 pub(crate) mod event;
 pub(crate) mod jpn;
+pub(crate) mod model;
 pub(crate) mod ocr;
 pub(crate) mod translation;
 
@@ -42,23 +44,3 @@ impl ScreenshotParameter {
         Ok(DynamicImage::ImageRgba8(image))
     }
 }
-
-pub struct OcrParameter {
-    pub args: Args,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub struct OcrResult {
-    pub ocr: String,
-    pub confidence: f32,
-    pub rects: Vec<OcrRect>,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub struct OcrRect {
-    symbol: String,
-    top: i32,
-    left: i32,
-    width: i32,
-    height: i32,
-}
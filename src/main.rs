@@ -1,13 +1,25 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 use log4rs::config::Deserializers;
-use manga_overlay::OcrApp;
+use manga_overlay::{OcrApp, run_evaluation};
 use std::{fs, path::Path};
 
+const APP_NAME: &str = "Manga Overlay";
+/// Written to the evaluated directory, release builds on Windows have no console
+const EVALUATION_REPORT: &str = "evaluation_report.txt";
+
 #[tokio::main]
 async fn main() -> eframe::Result {
     init_logger();
 
+    // `evaluate <dir>` compares the model variants on a directory of labelled crops
+    let args: Vec<String> = std::env::args().collect();
+    if let [_, command, dir] = args.as_slice()
+        && command == "evaluate"
+    {
+        std::process::exit(evaluate(Path::new(dir)));
+    }
+
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_transparent(true)
@@ -20,12 +32,40 @@ async fn main() -> eframe::Result {
         ..Default::default()
     };
     eframe::run_native(
-        "Manga Overlay",
+        APP_NAME,
         native_options,
         Box::new(|cc| Ok(Box::new(OcrApp::new(cc)))),
     )
 }
 
+/// Runs the evaluation with the models directory and inference settings of the overlay, returns
+/// the exit code.
+fn evaluate(dir: &Path) -> i32 {
+    let app = OcrApp::load_stored(APP_NAME).unwrap_or_else(|err| {
+        log::warn!("Using the default settings: {err:#}");
+        OcrApp::default()
+    });
+    app.init_model_config();
+
+    let result = run_evaluation(dir).and_then(|report| {
+        let path = dir.join(EVALUATION_REPORT);
+        fs::write(&path, &report)?;
+        Ok((report, path))
+    });
+    match result {
+        Ok((report, path)) => {
+            println!("{report}");
+            log::info!("Evaluation report written to {}", path.display());
+            0
+        }
+        Err(err) => {
+            eprintln!("Evaluation failed: {err:#}");
+            log::error!("Evaluation failed: {err:#}");
+            1
+        }
+    }
+}
+
 const LOG_CONFIG_DIR: &str = "config";
 const LOG_CONFIG: &str = "config/log4rs.yaml";
 
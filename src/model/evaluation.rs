@@ -0,0 +1,239 @@
+use crate::model::store::ModelVariant;
+use crate::ocr::manga_ocr::{MangaOCR, get_kanji_top_text};
+use anyhow::{Context, Result, bail};
+use image::DynamicImage;
+use log::info;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use strum::IntoEnumIterator;
+
+const IMAGE_EXTENSIONS: [&str; 4] = ["png", "jpg", "jpeg", "webp"];
+
+/// Text box image with the expected text.
+struct LabelledCrop {
+    name: String,
+    image: DynamicImage,
+    label: String,
+}
+
+/// Accuracy and speed of one variant of the OCR model.
+#[derive(Debug, Default)]
+struct VariantScore {
+    variant: ModelVariant,
+    crops: usize,
+    exact_matches: usize,
+    char_errors: usize,
+    label_chars: usize,
+    duration: Duration,
+    /// The variant could not be loaded
+    error: Option<String>,
+}
+
+impl VariantScore {
+    /// Character error rate, the edit distance divided by the length of the labels.
+    fn get_cer(&self) -> f32 {
+        self.char_errors as f32 / self.label_chars.max(1) as f32
+    }
+
+    fn get_accuracy(&self) -> f32 {
+        self.exact_matches as f32 / self.crops.max(1) as f32
+    }
+
+    fn get_ms_per_crop(&self) -> f32 {
+        self.duration.as_secs_f32() * 1000.0 / self.crops.max(1) as f32
+    }
+}
+
+/// Runs each variant of the MangaOCR model on the labelled crops of the directory and returns a
+/// report. A crop is an image with a `.txt` file of the same name containing the expected text.
+pub fn run_evaluation(dir: &Path) -> Result<String> {
+    let crops = load_crops(dir)?;
+    info!("Evaluating {} crops from {}", crops.len(), dir.display());
+
+    let scores: Vec<VariantScore> = ModelVariant::iter()
+        .map(|variant| evaluate_variant(variant, &crops))
+        .collect();
+
+    Ok(format_report(&scores))
+}
+
+fn load_crops(dir: &Path) -> Result<Vec<LabelledCrop>> {
+    let mut crops = vec![];
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let is_image = path
+            .extension()
+            .and_then(|x| x.to_str())
+            .is_some_and(|x| IMAGE_EXTENSIONS.contains(&x.to_lowercase().as_str()));
+        let label_path = path.with_extension("txt");
+        if !is_image || !label_path.is_file() {
+            continue;
+        }
+
+        let label = fs::read_to_string(&label_path)?;
+        crops.push(LabelledCrop {
+            name: path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            image: image::open(&path)
+                .with_context(|| format!("Failed to open {}", path.display()))?,
+            label: normalize_text(&label),
+        });
+    }
+
+    if crops.is_empty() {
+        bail!("No images with a .txt label found in {}", dir.display());
+    }
+    crops.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(crops)
+}
+
+/// Whitespace is ignored, MangaOCR does not output line breaks.
+fn normalize_text(text: &str) -> String {
+    text.chars().filter(|x| !x.is_whitespace()).collect()
+}
+
+fn evaluate_variant(variant: ModelVariant, crops: &[LabelledCrop]) -> VariantScore {
+    let mut score = VariantScore {
+        variant,
+        ..Default::default()
+    };
+
+    let mut model = match MangaOCR::new(variant) {
+        Ok(model) => model,
+        Err(err) => {
+            score.error = Some(format!("{err:#}"));
+            return score;
+        }
+    };
+
+    for crop in crops {
+        let start = Instant::now();
+        let result = model.inference(vec![&crop.image]);
+        score.duration += start.elapsed();
+
+        let text = result
+            .first()
+            .and_then(|x| get_kanji_top_text(x, 0))
+            .map(|x| normalize_text(&x))
+            .unwrap_or_default();
+        let errors = edit_distance(&crop.label, &text);
+        if errors > 0 {
+            info!(
+                "{} {}: expected {} but was {text}",
+                variant.get_label(),
+                crop.name,
+                crop.label
+            );
+        }
+
+        score.crops += 1;
+        score.exact_matches += usize::from(errors == 0);
+        score.char_errors += errors;
+        score.label_chars += crop.label.chars().count();
+    }
+    score
+}
+
+fn format_report(scores: &[VariantScore]) -> String {
+    let mut report = format!(
+        "{:<8}{:>8}{:>12}{:>10}{:>14}\n",
+        "Variant", "Crops", "Exact", "CER", "ms / Crop"
+    );
+    for score in scores {
+        match &score.error {
+            Some(err) => {
+                let _ = writeln!(report, "{:<8}not loaded: {err}", score.variant.get_label());
+            }
+            None => {
+                let _ = writeln!(
+                    report,
+                    "{:<8}{:>8}{:>11.1}%{:>9.1}%{:>14.1}",
+                    score.variant.get_label(),
+                    score.crops,
+                    score.get_accuracy() * 100.0,
+                    score.get_cer() * 100.0,
+                    score.get_ms_per_crop()
+                );
+            }
+        }
+    }
+    report
+}
+
+/// Levenshtein distance of the characters.
+fn edit_distance(expected: &str, actual: &str) -> usize {
+    let actual: Vec<char> = actual.chars().collect();
+    let mut previous: Vec<usize> = (0..=actual.len()).collect();
+
+    for (i, a) in expected.chars().enumerate() {
+        let mut current = vec![i + 1; actual.len() + 1];
+        for (j, b) in actual.iter().enumerate() {
+            current[j + 1] = (previous[j] + usize::from(a != *b))
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[actual.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("ロボット", "ロボット"), 0);
+        assert_eq!(edit_distance("ロボット", "口ボット"), 1);
+        assert_eq!(edit_distance("今日は", "今日"), 1);
+        assert_eq!(edit_distance("", "今日"), 2);
+    }
+
+    #[test]
+    fn test_load_crops() {
+        let dir = std::env::temp_dir().join("manga_overlay_evaluation_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        assert!(load_crops(&dir).is_err());
+
+        DynamicImage::new_rgb8(4, 4)
+            .save(dir.join("001.png"))
+            .unwrap();
+        fs::write(dir.join("001.txt"), "今日は\n 晴れ\n").unwrap();
+        // Images without a label are skipped
+        DynamicImage::new_rgb8(4, 4)
+            .save(dir.join("002.png"))
+            .unwrap();
+
+        let crops = load_crops(&dir).unwrap();
+        assert_eq!(crops.len(), 1);
+        assert_eq!(crops[0].label, "今日は晴れ");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_format_report() {
+        let scores = [VariantScore {
+            variant: ModelVariant::Int8,
+            crops: 4,
+            exact_matches: 3,
+            char_errors: 1,
+            label_chars: 20,
+            duration: Duration::from_millis(100),
+            error: None,
+        }];
+
+        let report = format_report(&scores);
+        assert!(report.contains("int8"));
+        assert!(report.contains("75.0%"));
+        assert!(report.contains("5.0%"));
+        assert!(report.contains("25.0"));
+    }
+}
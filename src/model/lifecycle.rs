@@ -0,0 +1,269 @@
+use anyhow::{Context, Result};
+use log::{error, info, warn};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Idle time in seconds after which the models are unloaded, 0 to keep them loaded.
+static IDLE_TIMEOUT_SECS: AtomicU64 = AtomicU64::new(0);
+
+pub fn set_idle_timeout(timeout: Duration) {
+    IDLE_TIMEOUT_SECS.store(timeout.as_secs(), Ordering::Relaxed);
+}
+
+pub fn get_idle_timeout() -> Option<Duration> {
+    match IDLE_TIMEOUT_SECS.load(Ordering::Relaxed) {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
+
+/// Model which is loaded on its first use and can be unloaded when it is idle.
+pub struct ManagedModel<T> {
+    name: &'static str,
+    loader: fn() -> Result<T>,
+    state: Mutex<ModelState<T>>,
+}
+
+struct ModelState<T> {
+    model: Option<T>,
+    last_used: Option<Instant>,
+}
+
+impl<T> ManagedModel<T> {
+    pub const fn new(name: &'static str, loader: fn() -> Result<T>) -> Self {
+        Self {
+            name,
+            loader,
+            state: Mutex::new(ModelState {
+                model: None,
+                last_used: None,
+            }),
+        }
+    }
+
+    /// Runs the function with the model, the model is loaded if it is not.
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> Result<R> {
+        let mut state = self.lock_state();
+        if state.model.is_none() {
+            state.model = Some(self.load_model()?);
+        }
+        state.last_used = Some(Instant::now());
+
+        let model = state.model.as_mut().expect("Model is loaded");
+        Ok(f(model))
+    }
+
+    /// Loads the model if it is not loaded.
+    pub fn load(&self) -> Result<()> {
+        self.with(|_| ())
+    }
+
+    /// Loads the model again.
+    pub fn reload(&self) -> Result<()> {
+        let mut state = self.lock_state();
+        // Free the old model before the new one is allocated
+        state.model = None;
+        state.model = Some(self.load_model()?);
+        state.last_used = Some(Instant::now());
+        Ok(())
+    }
+
+    pub fn unload(&self) {
+        let mut state = self.lock_state();
+        if state.model.take().is_some() {
+            info!("Unloaded {}", self.name);
+        }
+    }
+
+    /// Unloads the model if it was not used for the timeout, a model in use is kept. Returns if
+    /// the model was unloaded.
+    pub fn unload_if_idle(&self, timeout: Duration) -> bool {
+        let Ok(mut state) = self.state.try_lock() else {
+            return false;
+        };
+        let is_idle = state.last_used.is_none_or(|x| x.elapsed() >= timeout);
+        if state.model.is_none() || !is_idle {
+            return false;
+        }
+
+        state.model = None;
+        info!("Unloaded {} after being idle", self.name);
+        true
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.lock_state().model.is_some()
+    }
+
+    /// A panic while the model was used may leave it in a broken state, so it is dropped and
+    /// loaded again on the next use.
+    fn lock_state(&self) -> MutexGuard<'_, ModelState<T>> {
+        self.state.lock().unwrap_or_else(|err| {
+            warn!("{} panicked while it was used, unloading it", self.name);
+            self.state.clear_poison();
+            let mut state = err.into_inner();
+            state.model = None;
+            state
+        })
+    }
+
+    fn load_model(&self) -> Result<T> {
+        info!("Loading {}", self.name);
+        (self.loader)()
+            .inspect_err(|err| error!("Failed to load {}: {err:#}", self.name))
+            .with_context(|| format!("{} is not loaded", self.name))
+    }
+}
+
+/// Models which are loaded by a key like the model path, each is loaded on its first use and
+/// unloaded when it is idle.
+pub struct ManagedModels<K, T> {
+    name: &'static str,
+    models: Mutex<BTreeMap<K, (T, Instant)>>,
+}
+
+impl<K: Ord + Clone, T> ManagedModels<K, T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            models: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Runs the function with the model of the key, the model is loaded if it is not.
+    pub fn with<R>(
+        &self,
+        key: &K,
+        loader: impl FnOnce() -> Result<T>,
+        f: impl FnOnce(&mut T) -> R,
+    ) -> Result<R> {
+        let mut models = self.lock_models();
+        if !models.contains_key(key) {
+            info!("Loading {}", self.name);
+            let model =
+                loader().inspect_err(|err| error!("Failed to load {}: {err:#}", self.name))?;
+            models.insert(key.clone(), (model, Instant::now()));
+        }
+
+        let (model, last_used) = models.get_mut(key).expect("Model is loaded");
+        *last_used = Instant::now();
+        Ok(f(model))
+    }
+
+    pub fn unload(&self) {
+        let mut models = self.lock_models();
+        if !models.is_empty() {
+            models.clear();
+            info!("Unloaded {}", self.name);
+        }
+    }
+
+    /// Unloads the models which were not used for the timeout, models in use are kept. Returns if
+    /// a model was unloaded.
+    pub fn unload_if_idle(&self, timeout: Duration) -> bool {
+        let Ok(mut models) = self.models.try_lock() else {
+            return false;
+        };
+        let count = models.len();
+        models.retain(|_, (_, last_used)| last_used.elapsed() < timeout);
+        if models.len() == count {
+            return false;
+        }
+
+        info!("Unloaded {} after being idle", self.name);
+        true
+    }
+
+    fn lock_models(&self) -> MutexGuard<'_, BTreeMap<K, (T, Instant)>> {
+        self.models.lock().unwrap_or_else(|err| {
+            warn!("{} panicked while it was used, unloading it", self.name);
+            self.models.clear_poison();
+            let mut models = err.into_inner();
+            models.clear();
+            models
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    static LOAD_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    fn load_test_model() -> Result<usize> {
+        Ok(LOAD_COUNT.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    #[test]
+    fn test_managed_model() {
+        let model = ManagedModel::new("Test", load_test_model);
+        assert!(!model.is_loaded());
+
+        assert_eq!(model.with(|x| *x).unwrap(), 1);
+        assert!(model.is_loaded());
+        assert!(!model.unload_if_idle(Duration::from_secs(60)));
+
+        assert!(model.unload_if_idle(Duration::ZERO));
+        assert!(!model.is_loaded());
+        assert!(!model.unload_if_idle(Duration::ZERO));
+
+        // Loaded again on the next use
+        assert_eq!(model.with(|x| *x).unwrap(), 2);
+        assert!(model.reload().is_ok());
+        assert_eq!(model.with(|x| *x).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_poisoned_model() {
+        let model = ManagedModel::new("Poisoned", || Ok(0));
+        let result = std::panic::catch_unwind(|| {
+            model
+                .with(|x| {
+                    *x = 1;
+                    panic!("inference failed")
+                })
+                .unwrap()
+        });
+        assert!(result.is_err());
+
+        // The broken model is dropped and loaded again
+        assert!(!model.is_loaded());
+        assert_eq!(model.with(|x| *x).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_managed_models() {
+        let models: ManagedModels<String, usize> = ManagedModels::new("Test models");
+        let load = |len: usize| move || Ok(len);
+        assert_eq!(models.with(&"a".into(), load(1), |x| *x).unwrap(), 1);
+        assert_eq!(models.with(&"b".into(), load(2), |x| *x).unwrap(), 2);
+        // Loaded models are reused
+        assert_eq!(models.with(&"a".into(), load(3), |x| *x).unwrap(), 1);
+        assert!(
+            models
+                .with(&"c".into(), || Err(anyhow::anyhow!("missing")), |x| *x)
+                .is_err()
+        );
+
+        assert!(!models.unload_if_idle(Duration::from_secs(60)));
+        assert!(models.unload_if_idle(Duration::ZERO));
+        assert!(!models.unload_if_idle(Duration::ZERO));
+        // Loaded again on the next use
+        assert_eq!(models.with(&"a".into(), load(4), |x| *x).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_failed_load() {
+        let model: ManagedModel<usize> =
+            ManagedModel::new("Broken", || Err(anyhow::anyhow!("missing")));
+
+        let err = model.load().unwrap_err();
+        assert_eq!(format!("{err:#}"), "Broken is not loaded: missing");
+        assert!(model.reload().is_err());
+        assert!(!model.is_loaded());
+    }
+}
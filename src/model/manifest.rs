@@ -0,0 +1,108 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::path::Path;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Versions and SHA-256 checksums of the files in a models directory.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Default)]
+#[serde(default)]
+pub struct Manifest {
+    pub models: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Default)]
+#[serde(default)]
+pub struct ManifestEntry {
+    /// Revision of the download or the version of the imported manifest
+    pub version: String,
+    /// Checksum by file name
+    pub files: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    /// Loads the manifest of the directory, empty if it does not exist.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let json = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Invalid manifest {}", path.display()))
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(MANIFEST_FILE), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get_version(&self, id: &str) -> Option<&str> {
+        self.models.get(id).map(|x| x.version.as_str())
+    }
+
+    pub fn get_checksum(&self, id: &str, file: &str) -> Option<&str> {
+        self.models
+            .get(id)
+            .and_then(|x| x.files.get(file))
+            .map(|x| x.as_str())
+    }
+
+    pub fn set_checksum(&mut self, id: &str, version: &str, file: &str, checksum: String) {
+        let entry = self.models.entry(id.to_string()).or_default();
+        entry.version = version.to_string();
+        entry.files.insert(file.to_string(), checksum);
+    }
+}
+
+/// Lowercase hex SHA-256 of the file.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_roundtrip() {
+        let dir = std::env::temp_dir().join("manga_overlay_manifest_test");
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(Manifest::load(&dir).unwrap(), Manifest::default());
+
+        let mut manifest = Manifest::default();
+        manifest.set_checksum("manga-ocr", "main", "vocab.txt", "abc".to_string());
+        manifest.save(&dir).unwrap();
+
+        let loaded = Manifest::load(&dir).unwrap();
+        assert_eq!(loaded.get_version("manga-ocr"), Some("main"));
+        assert_eq!(loaded.get_checksum("manga-ocr", "vocab.txt"), Some("abc"));
+        assert_eq!(loaded.get_checksum("manga-ocr", "manga-ocr.onnx"), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sha256_file() {
+        let path = std::env::temp_dir().join("manga_overlay_sha256_test.txt");
+        fs::write(&path, "abc").unwrap();
+
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,4 @@
+pub mod evaluation;
+pub mod lifecycle;
+pub mod manifest;
+pub mod store;
@@ -0,0 +1,737 @@
+use crate::model::manifest::{Manifest, sha256_file};
+use anyhow::{Context, Result, bail};
+use hf_hub::api::sync::ApiBuilder;
+use hf_hub::{Cache, Repo, RepoType};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, RwLock};
+use strum::EnumIter;
+
+/// Version of the models which were imported without a manifest.
+const IMPORTED_VERSION: &str = "imported";
+const BUNDLED_VERSION: &str = "bundled";
+/// Download cache of hf-hub inside the models directory, the files are moved out of it
+const DOWNLOAD_DIR: &str = ".download";
+
+/// Precision of the onnx files of a model.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Default, EnumIter)]
+pub enum ModelVariant {
+    #[default]
+    Fp32,
+    /// Dynamically quantized weights, several times faster on the CPU
+    Int8,
+}
+
+impl ModelVariant {
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            ModelVariant::Fp32 => "fp32",
+            ModelVariant::Int8 => "int8",
+        }
+    }
+
+    /// Name of the file of the variant, the int8 onnx files are named like the output of the
+    /// dynamic quantization of Optimum, e.g. `manga-ocr_quantized.onnx`.
+    pub fn get_file_name(&self, file: &str) -> String {
+        match (self, file.strip_suffix(".onnx")) {
+            (ModelVariant::Int8, Some(stem)) => format!("{stem}_quantized.onnx"),
+            _ => file.to_string(),
+        }
+    }
+}
+
+/// Download of a model from its Hugging Face repository.
+pub struct ModelDownload {
+    pub repo: &'static str,
+    /// Commit SHA, a branch would change the files under the same version
+    pub revision: &'static str,
+    /// Expected SHA-256 by file name, a download of a file without one is trusted and its
+    /// checksum is recorded in the manifest so later changes are detected
+    pub checksums: &'static [(&'static str, &'static str)],
+}
+
+impl ModelDownload {
+    fn get_checksum(&self, file: &str) -> Option<&'static str> {
+        self.checksums
+            .iter()
+            .find(|(name, _)| *name == file)
+            .map(|(_, checksum)| *checksum)
+    }
+}
+
+pub struct ModelSpec {
+    /// Name of the sub directory in the models directory
+    pub id: &'static str,
+    pub name: &'static str,
+    /// `None` if the model can not be downloaded
+    pub download: Option<ModelDownload>,
+    pub files: &'static [&'static str],
+    /// Files which are used if they are available
+    pub optional_files: &'static [&'static str],
+    /// The model is embedded in the executable, a local file overrides it
+    pub bundled: bool,
+}
+
+impl ModelSpec {
+    /// Required files of the variant.
+    fn get_files(&self, variant: ModelVariant) -> impl Iterator<Item = String> {
+        self.files.iter().map(move |x| variant.get_file_name(x))
+    }
+
+    /// All required files of the variant have a pinned checksum.
+    fn is_pinned(&self, variant: ModelVariant) -> bool {
+        self.download.as_ref().is_some_and(|download| {
+            self.get_files(variant)
+                .all(|file| download.get_checksum(&file).is_some())
+        })
+    }
+
+    /// Files of all variants, the int8 files are optional.
+    fn get_all_files(&self) -> impl Iterator<Item = String> {
+        let files = self.files.iter().chain(self.optional_files);
+        let int8_files = files
+            .clone()
+            .filter(|x| x.ends_with(".onnx"))
+            .map(|x| ModelVariant::Int8.get_file_name(x));
+        files.map(|x| x.to_string()).chain(int8_files)
+    }
+}
+
+pub const MANGA_OCR_MODEL: ModelSpec = ModelSpec {
+    id: "manga-ocr",
+    name: "MangaOCR",
+    download: Some(ModelDownload {
+        repo: "mayocream/koharu",
+        // Not pinned to a commit and checksums yet, the checksums of the first download are
+        // recorded in the manifest
+        revision: "main",
+        checksums: &[],
+    }),
+    files: &["manga-ocr.onnx", "vocab.txt"],
+    optional_files: &["encoder_model.onnx", "decoder_model_merged.onnx"],
+    bundled: false,
+};
+
+pub const DETECTOR_MODEL: ModelSpec = ModelSpec {
+    id: "comictextdetector",
+    name: "Comic Text Detector",
+    download: None,
+    files: &["comictextdetector_blk.pt.onnx"],
+    optional_files: &[],
+    bundled: true,
+};
+
+pub const MODELS: [&ModelSpec; 2] = [&MANGA_OCR_MODEL, &DETECTOR_MODEL];
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ModelStoreConfig {
+    pub models_dir: String,
+    /// Only local files are used, missing models are not downloaded
+    pub offline: bool,
+    /// Selected variant by model id, fp32 if not set
+    pub variants: BTreeMap<String, ModelVariant>,
+}
+
+impl Default for ModelStoreConfig {
+    fn default() -> Self {
+        Self {
+            models_dir: "models".to_string(),
+            offline: false,
+            variants: BTreeMap::new(),
+        }
+    }
+}
+
+impl ModelStoreConfig {
+    /// A relative directory is resolved against the directory of the executable, the working
+    /// directory depends on how the overlay is started.
+    pub fn get_dir(&self) -> PathBuf {
+        let dir = PathBuf::from(self.models_dir.trim());
+        if dir.is_absolute() {
+            return dir;
+        }
+        std::env::current_exe()
+            .ok()
+            .and_then(|x| x.parent().map(|x| x.join(&dir)))
+            .unwrap_or(dir)
+    }
+
+    pub fn get_variant(&self, spec: &ModelSpec) -> ModelVariant {
+        self.variants.get(spec.id).copied().unwrap_or_default()
+    }
+}
+
+static CONFIG: LazyLock<RwLock<ModelStoreConfig>> =
+    LazyLock::new(|| RwLock::new(ModelStoreConfig::default()));
+
+/// Files which matched the manifest in this session, hashing large models is slow.
+static VERIFIED: LazyLock<Mutex<HashSet<PathBuf>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+pub fn set_config(config: ModelStoreConfig) {
+    *CONFIG.write().expect("Model store config lock failed") = config;
+    VERIFIED.lock().expect("Verified lock failed").clear();
+}
+
+pub fn get_config() -> ModelStoreConfig {
+    CONFIG
+        .read()
+        .expect("Model store config lock failed")
+        .clone()
+}
+
+/// Selected variant of the model.
+pub fn get_variant(spec: &ModelSpec) -> ModelVariant {
+    get_config().get_variant(spec)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ModelStatus {
+    /// All files match the checksums of the manifest
+    Verified,
+    /// The files exist but have no checksum in the manifest
+    Unverified,
+    /// The embedded model is used
+    Bundled,
+    Missing,
+    Error(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelInfo {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub variant: ModelVariant,
+    pub version: Option<String>,
+    pub status: ModelStatus,
+    pub missing_files: Vec<String>,
+    pub can_download: bool,
+    /// The download is pinned to a commit and checksums
+    pub pinned: bool,
+}
+
+/// Path of the model file in the models directory, the file is downloaded if it is missing and
+/// the offline mode is disabled.
+pub fn get_file(spec: &ModelSpec, file: &str) -> Result<PathBuf> {
+    if let Some(path) = find_local_file(spec, file)? {
+        return Ok(path);
+    }
+
+    let config = get_config();
+    let dir = config.get_dir();
+
+    // Earlier versions downloaded the model into the Hugging Face cache
+    if let Some(path) = import_from_cache(&dir, spec, &Cache::from_env(), file)? {
+        return Ok(path);
+    }
+
+    if config.offline {
+        bail!(
+            "{file} of {} is missing in {}, import the model or disable the offline mode",
+            spec.name,
+            dir.join(spec.id).display()
+        );
+    }
+
+    let download = spec
+        .download
+        .as_ref()
+        .with_context(|| format!("{file} of {} can not be downloaded", spec.name))?;
+    download_file(&dir, spec, download, file)
+}
+
+/// Imports the file from the Hugging Face cache, `None` if it is not in the cache.
+fn import_from_cache(
+    dir: &Path,
+    spec: &ModelSpec,
+    cache: &Cache,
+    file: &str,
+) -> Result<Option<PathBuf>> {
+    let Some(download) = &spec.download else {
+        return Ok(None);
+    };
+    // The earlier versions downloaded the main branch
+    let Some(cached) = cache.model(download.repo.to_string()).get(file) else {
+        return Ok(None);
+    };
+
+    info!(
+        "Importing {file} of {} from the Hugging Face cache {}",
+        spec.name,
+        cached.display()
+    );
+    verify_download(spec, file, download.get_checksum(file), &cached)?;
+    store_file(dir, spec, file, &cached, IMPORTED_VERSION).map(Some)
+}
+
+/// Verified path of the model file in the models directory, `None` if it does not exist.
+pub fn find_local_file(spec: &ModelSpec, file: &str) -> Result<Option<PathBuf>> {
+    let dir = get_config().get_dir();
+
+    let path = dir.join(spec.id).join(file);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    verify_file(&dir, spec, file, &path)?;
+    Ok(Some(path))
+}
+
+/// Downloads the missing files of the selected variant of the model.
+pub fn download_model(spec: &ModelSpec) -> Result<()> {
+    for file in spec.get_files(get_variant(spec)) {
+        get_file(spec, &file)?;
+    }
+    let download = spec.download.as_ref();
+    for file in spec.optional_files {
+        if download.is_none_or(|x| x.get_checksum(file).is_none()) {
+            continue;
+        }
+        if let Err(err) = get_file(spec, file) {
+            info!(
+                "Optional file {file} of {} not available: {err:#}",
+                spec.name
+            );
+        }
+    }
+    Ok(())
+}
+
+fn verify_file(dir: &Path, spec: &ModelSpec, file: &str, path: &Path) -> Result<()> {
+    if VERIFIED
+        .lock()
+        .expect("Verified lock failed")
+        .contains(path)
+    {
+        return Ok(());
+    }
+
+    let manifest = Manifest::load(dir)?;
+    let Some(expected) = manifest.get_checksum(spec.id, file) else {
+        warn!(
+            "{} is not in the manifest and was not verified",
+            path.display()
+        );
+        return Ok(());
+    };
+
+    let actual = sha256_file(path)?;
+    if actual != expected {
+        bail!(
+            "Checksum of {} does not match the manifest, expected {expected} but was {actual}",
+            path.display()
+        );
+    }
+
+    VERIFIED
+        .lock()
+        .expect("Verified lock failed")
+        .insert(path.to_path_buf());
+    Ok(())
+}
+
+fn download_file(
+    dir: &Path,
+    spec: &ModelSpec,
+    download: &ModelDownload,
+    file: &str,
+) -> Result<PathBuf> {
+    info!(
+        "Downloading {file} of {} from {} at {}",
+        spec.name, download.repo, download.revision
+    );
+
+    // The cache is in the models directory so the file is moved instead of copied
+    let cache_dir = dir.join(DOWNLOAD_DIR);
+    let result = fetch_file(&cache_dir, spec, download, file).and_then(|(blob, checksum)| {
+        let target = get_target_path(dir, spec, file)?;
+        fs::rename(&blob, &target)
+            .with_context(|| format!("Failed to move {} to the models", blob.display()))?;
+        record_file(dir, spec, file, &target, download.revision, checksum)
+    });
+
+    if let Err(err) = fs::remove_dir_all(&cache_dir) {
+        warn!("Failed to remove {}: {err}", cache_dir.display());
+    }
+    result
+}
+
+/// Downloads the file into the cache and returns the path of the verified blob and its checksum.
+fn fetch_file(
+    cache_dir: &Path,
+    spec: &ModelSpec,
+    download: &ModelDownload,
+    file: &str,
+) -> Result<(PathBuf, String)> {
+    let api = ApiBuilder::new()
+        .with_cache_dir(cache_dir.to_path_buf())
+        .build()?;
+    let repo = Repo::with_revision(
+        download.repo.to_string(),
+        RepoType::Model,
+        download.revision.to_string(),
+    );
+    let downloaded = api
+        .repo(repo)
+        .get(file)
+        .with_context(|| format!("Failed to download {file} of {}", spec.name))?;
+
+    // The snapshot is a link to the blob
+    let blob = fs::canonicalize(&downloaded)?;
+    let checksum = verify_download(spec, file, download.get_checksum(file), &blob)?;
+    Ok((blob, checksum))
+}
+
+/// Checks the file against the pinned checksum and returns its checksum.
+fn verify_download(
+    spec: &ModelSpec,
+    file: &str,
+    expected: Option<&str>,
+    path: &Path,
+) -> Result<String> {
+    let actual = sha256_file(path)?;
+    match expected {
+        Some(expected) if actual != expected => bail!(
+            "Checksum of {file} of {} does not match, expected {expected} but was {actual}",
+            spec.name
+        ),
+        Some(_) => {}
+        None => warn!(
+            "{file} of {} has no pinned checksum, recording {actual}",
+            spec.name
+        ),
+    }
+    Ok(actual)
+}
+
+/// Copies the file into the models directory and records its checksum in the manifest.
+fn store_file(
+    dir: &Path,
+    spec: &ModelSpec,
+    file: &str,
+    source: &Path,
+    version: &str,
+) -> Result<PathBuf> {
+    let target = get_target_path(dir, spec, file)?;
+    fs::copy(source, &target)
+        .with_context(|| format!("Failed to copy {} to the models", source.display()))?;
+    let checksum = sha256_file(&target)?;
+    record_file(dir, spec, file, &target, version, checksum)
+}
+
+fn get_target_path(dir: &Path, spec: &ModelSpec, file: &str) -> Result<PathBuf> {
+    let target_dir = dir.join(spec.id);
+    fs::create_dir_all(&target_dir)?;
+    Ok(target_dir.join(file))
+}
+
+fn record_file(
+    dir: &Path,
+    spec: &ModelSpec,
+    file: &str,
+    target: &Path,
+    version: &str,
+    checksum: String,
+) -> Result<PathBuf> {
+    let mut manifest = Manifest::load(dir)?;
+    manifest.set_checksum(spec.id, version, file, checksum);
+    manifest.save(dir)?;
+
+    VERIFIED
+        .lock()
+        .expect("Verified lock failed")
+        .insert(target.to_path_buf());
+    Ok(target.to_path_buf())
+}
+
+/// Imports all models found in the directory into the models directory, returns the names of
+/// the imported models.
+pub fn import_models(source: &Path) -> Result<Vec<&'static str>> {
+    import_models_into(&get_config().get_dir(), source)
+}
+
+/// The files of a model are expected in a sub directory named like the model id or directly in
+/// the source directory. A `manifest.json` in the source is used to verify the files and for the
+/// versions, so a models directory of another installation can be imported as is.
+fn import_models_into(dir: &Path, source: &Path) -> Result<Vec<&'static str>> {
+    if !source.is_dir() {
+        bail!("{} is not a directory", source.display());
+    }
+    if dir.exists() && fs::canonicalize(dir)? == fs::canonicalize(source)? {
+        bail!("{} is already the models directory", source.display());
+    }
+
+    let source_manifest = Manifest::load(source)?;
+    let mut imported = vec![];
+
+    for spec in MODELS {
+        let model_source = [source.join(spec.id), source.to_path_buf()]
+            .into_iter()
+            .find(|x| {
+                [ModelVariant::Fp32, ModelVariant::Int8]
+                    .into_iter()
+                    .any(|variant| spec.get_files(variant).all(|file| x.join(file).is_file()))
+            });
+        let Some(model_source) = model_source else {
+            continue;
+        };
+
+        let version = source_manifest
+            .get_version(spec.id)
+            .unwrap_or(IMPORTED_VERSION);
+        for file in spec.get_all_files() {
+            let path = model_source.join(&file);
+            if !path.is_file() {
+                continue;
+            }
+
+            if let Some(expected) = source_manifest.get_checksum(spec.id, &file)
+                && sha256_file(&path)? != expected
+            {
+                bail!("Checksum of {} does not match its manifest", path.display());
+            }
+            store_file(dir, spec, &file, &path, version)?;
+        }
+
+        info!("Imported {} from {}", spec.name, model_source.display());
+        imported.push(spec.name);
+    }
+
+    if imported.is_empty() {
+        bail!("No model files found in {}", source.display());
+    }
+    Ok(imported)
+}
+
+pub fn get_model_infos() -> Vec<ModelInfo> {
+    let config = get_config();
+    let dir = config.get_dir();
+    let manifest = Manifest::load(&dir).unwrap_or_else(|err| {
+        warn!("{err:#}");
+        Manifest::default()
+    });
+
+    MODELS
+        .iter()
+        .map(|spec| {
+            let variant = config.get_variant(spec);
+            get_model_info(&dir, &manifest, spec, variant, config.offline)
+        })
+        .collect()
+}
+
+fn get_model_info(
+    dir: &Path,
+    manifest: &Manifest,
+    spec: &ModelSpec,
+    variant: ModelVariant,
+    offline: bool,
+) -> ModelInfo {
+    let missing_files: Vec<String> = spec
+        .get_files(variant)
+        .filter(|file| !dir.join(spec.id).join(file).is_file())
+        .collect();
+
+    let status = if !missing_files.is_empty() {
+        // Only the fp32 model is embedded
+        if spec.bundled && variant == ModelVariant::Fp32 {
+            ModelStatus::Bundled
+        } else {
+            ModelStatus::Missing
+        }
+    } else {
+        get_files_status(dir, manifest, spec)
+    };
+
+    let version = match status {
+        ModelStatus::Bundled => Some(BUNDLED_VERSION.to_string()),
+        _ => manifest.get_version(spec.id).map(|x| x.to_string()),
+    };
+
+    ModelInfo {
+        id: spec.id,
+        name: spec.name,
+        variant,
+        version,
+        can_download: !offline && spec.download.is_some() && status == ModelStatus::Missing,
+        pinned: spec.is_pinned(variant),
+        status,
+        missing_files,
+    }
+}
+
+fn get_files_status(dir: &Path, manifest: &Manifest, spec: &ModelSpec) -> ModelStatus {
+    let mut status = ModelStatus::Verified;
+    for file in spec.get_all_files() {
+        let path = dir.join(spec.id).join(&file);
+        if !path.is_file() {
+            continue;
+        }
+        if manifest.get_checksum(spec.id, &file).is_none() {
+            status = ModelStatus::Unverified;
+            continue;
+        }
+        if let Err(err) = verify_file(dir, spec, &file, &path) {
+            return ModelStatus::Error(format!("{err:#}"));
+        }
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_files(dir: &Path, spec: &ModelSpec) {
+        fs::create_dir_all(dir).unwrap();
+        for file in spec.files {
+            fs::write(dir.join(file), file.as_bytes()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_import_models() {
+        let root = std::env::temp_dir().join("manga_overlay_model_store_test");
+        let _ = fs::remove_dir_all(&root);
+        let source = root.join("source");
+        let dir = root.join("models");
+
+        assert!(import_models_into(&dir, &source).is_err());
+
+        create_files(&source.join(MANGA_OCR_MODEL.id), &MANGA_OCR_MODEL);
+        assert_eq!(import_models_into(&dir, &source).unwrap(), vec!["MangaOCR"]);
+
+        let manifest = Manifest::load(&dir).unwrap();
+        assert_eq!(manifest.get_version("manga-ocr"), Some(IMPORTED_VERSION));
+        let info = get_model_info(&dir, &manifest, &MANGA_OCR_MODEL, ModelVariant::Fp32, true);
+        assert_eq!(info.status, ModelStatus::Verified);
+        let info = get_model_info(&dir, &manifest, &DETECTOR_MODEL, ModelVariant::Fp32, true);
+        assert_eq!(info.status, ModelStatus::Bundled);
+
+        // The imported models directory keeps the versions of its manifest
+        let copy = root.join("copy");
+        assert_eq!(import_models_into(&copy, &dir).unwrap(), vec!["MangaOCR"]);
+
+        fs::write(dir.join("manga-ocr").join("vocab.txt"), "changed").unwrap();
+        VERIFIED.lock().unwrap().clear();
+        let info = get_model_info(&dir, &manifest, &MANGA_OCR_MODEL, ModelVariant::Fp32, true);
+        assert!(matches!(info.status, ModelStatus::Error(_)));
+        assert!(import_models_into(&copy, &dir).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    const PINNED_SPEC: ModelSpec = ModelSpec {
+        id: "test",
+        name: "Test",
+        download: Some(ModelDownload {
+            repo: "test/test",
+            revision: "0123456789abcdef0123456789abcdef01234567",
+            checksums: &[("model.onnx", "abc"), ("vocab.txt", "def")],
+        }),
+        files: &["model.onnx", "vocab.txt"],
+        optional_files: &[],
+        bundled: false,
+    };
+
+    #[test]
+    fn test_is_pinned() {
+        assert!(PINNED_SPEC.is_pinned(ModelVariant::Fp32));
+        // The int8 file has no pinned checksum
+        assert!(!PINNED_SPEC.is_pinned(ModelVariant::Int8));
+        assert!(!MANGA_OCR_MODEL.is_pinned(ModelVariant::Fp32));
+    }
+
+    #[test]
+    fn test_verify_download() {
+        let root = std::env::temp_dir().join("manga_overlay_verify_download_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("vocab.txt");
+        fs::write(&path, "vocab.txt").unwrap();
+        let checksum = sha256_file(&path).unwrap();
+
+        assert_eq!(
+            verify_download(&PINNED_SPEC, "vocab.txt", Some(&checksum), &path).unwrap(),
+            checksum
+        );
+        assert!(verify_download(&PINNED_SPEC, "vocab.txt", Some("abc"), &path).is_err());
+        // An unpinned file is trusted and its checksum recorded
+        assert_eq!(
+            verify_download(&PINNED_SPEC, "vocab.txt", None, &path).unwrap(),
+            checksum
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_import_from_cache() {
+        let root = std::env::temp_dir().join("manga_overlay_hf_cache_test");
+        let _ = fs::remove_dir_all(&root);
+        let cache = Cache::new(root.join("hub"));
+        let dir = root.join("models");
+
+        assert_eq!(
+            import_from_cache(&dir, &MANGA_OCR_MODEL, &cache, "vocab.txt").unwrap(),
+            None
+        );
+
+        // Layout of hf-hub, the main branch points to a snapshot of the commit
+        let repo = root.join("hub").join("models--mayocream--koharu");
+        fs::create_dir_all(repo.join("refs")).unwrap();
+        fs::write(repo.join("refs").join("main"), "abc").unwrap();
+        let snapshot = repo.join("snapshots").join("abc");
+        fs::create_dir_all(&snapshot).unwrap();
+        fs::write(snapshot.join("vocab.txt"), "vocab").unwrap();
+
+        let path = import_from_cache(&dir, &MANGA_OCR_MODEL, &cache, "vocab.txt")
+            .unwrap()
+            .unwrap();
+        assert_eq!(path, dir.join("manga-ocr").join("vocab.txt"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "vocab");
+        let manifest = Manifest::load(&dir).unwrap();
+        assert!(manifest.get_checksum("manga-ocr", "vocab.txt").is_some());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_int8_variant() {
+        assert_eq!(
+            ModelVariant::Int8.get_file_name("manga-ocr.onnx"),
+            "manga-ocr_quantized.onnx"
+        );
+        assert_eq!(ModelVariant::Int8.get_file_name("vocab.txt"), "vocab.txt");
+        assert_eq!(
+            ModelVariant::Fp32.get_file_name("manga-ocr.onnx"),
+            "manga-ocr.onnx"
+        );
+
+        let root = std::env::temp_dir().join("manga_overlay_model_variant_test");
+        let _ = fs::remove_dir_all(&root);
+        let source = root.join("source");
+        let dir = root.join("models");
+
+        // A source with only the int8 model is imported
+        fs::create_dir_all(&source).unwrap();
+        for file in MANGA_OCR_MODEL.get_files(ModelVariant::Int8) {
+            fs::write(source.join(&file), file.as_bytes()).unwrap();
+        }
+        assert_eq!(import_models_into(&dir, &source).unwrap(), vec!["MangaOCR"]);
+
+        let manifest = Manifest::load(&dir).unwrap();
+        let info = get_model_info(&dir, &manifest, &MANGA_OCR_MODEL, ModelVariant::Int8, true);
+        assert_eq!(info.status, ModelStatus::Verified);
+        let info = get_model_info(&dir, &manifest, &MANGA_OCR_MODEL, ModelVariant::Fp32, true);
+        assert_eq!(info.missing_files, vec!["manga-ocr.onnx"]);
+
+        // The int8 detector is not bundled
+        let info = get_model_info(&dir, &manifest, &DETECTOR_MODEL, ModelVariant::Int8, true);
+        assert_eq!(info.status, ModelStatus::Missing);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
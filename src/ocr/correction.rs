@@ -0,0 +1,422 @@
+use crate::jpn::dict::extract_words;
+use crate::ocr::manga_ocr::KanjiTopResults;
+use image::DynamicImage;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Alternatives below this probability are only tried if they are a known look-alike.
+const MIN_ALTERNATIVE_PROBABILITY: f32 = 0.05;
+const MAX_ALTERNATIVES: usize = 3;
+/// Upper bound of the tried sequences, the most uncertain positions are searched first.
+const MAX_COMBINATIONS: usize = 64;
+/// Weight of the dictionary score against the log probability of the OCR.
+const DICTIONARY_WEIGHT: f32 = 0.5;
+
+/// Characters the OCR often confuses.
+const LOOK_ALIKES: [(char, char); 16] = [
+    ('ロ', '口'),
+    ('カ', '力'),
+    ('エ', '工'),
+    ('ニ', '二'),
+    ('ー', '一'),
+    ('タ', '夕'),
+    ('ト', '卜'),
+    ('ハ', '八'),
+    ('へ', 'ヘ'),
+    ('べ', 'ベ'),
+    ('ぺ', 'ペ'),
+    ('オ', '才'),
+    ('チ', '千'),
+    ('ル', '儿'),
+    ('メ', '乂'),
+    ('ミ', '三'),
+];
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Default)]
+pub struct Replacement {
+    pub index: usize,
+    pub original: String,
+    pub replacement: String,
+}
+
+/// Automatic correction of the OCR text, keeps the original for the undo.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Default)]
+pub struct Correction {
+    pub original: String,
+    /// Empty for a remembered correction of the same crop
+    pub replacements: Vec<Replacement>,
+}
+
+impl Correction {
+    pub fn get_summary(&self) -> String {
+        if self.replacements.is_empty() {
+            return "remembered text".to_string();
+        }
+        self.replacements
+            .iter()
+            .map(|x| format!("{} → {}", x.original, x.replacement))
+            .join(", ")
+    }
+
+    /// Probability of each character of the corrected text, a replaced position has the
+    /// probability of the alternative which was chosen.
+    pub fn get_char_confidences(&self, top: &KanjiTopResults) -> Vec<f32> {
+        top.iter()
+            .enumerate()
+            .filter_map(|(position, alternatives)| {
+                self.replacements
+                    .iter()
+                    .find(|x| x.index == position)
+                    .and_then(|replacement| {
+                        alternatives
+                            .iter()
+                            .find(|x| x.kanji == replacement.replacement)
+                    })
+                    .or(alternatives.first())
+            })
+            .flat_map(|x| std::iter::repeat_n(x.confidence, x.kanji.chars().count()))
+            .collect()
+    }
+}
+
+/// Substitutions learned from the user corrections, the replacement for each character.
+pub type Substitutions = HashMap<String, String>;
+
+/// Applies the learned substitutions if the replacement is one of the alternatives and chooses
+/// the alternatives of the other positions with the best combination of JMdict matches and
+/// OCR probability. Returns the corrected text if it differs from the most probable one.
+pub fn correct(
+    top: &KanjiTopResults,
+    substitutions: &Substitutions,
+    use_dictionary: bool,
+) -> Option<(String, Correction)> {
+    let learned = get_learned_choice(top, substitutions);
+    let choice = if use_dictionary {
+        search_best_choice(top, learned, get_dictionary_score)
+    } else {
+        learned
+    };
+    create_correction(top, &choice)
+}
+
+/// Index of the alternative for each position, 0 is the most probable one.
+type Choice = Vec<usize>;
+
+fn get_learned_choice(top: &KanjiTopResults, substitutions: &Substitutions) -> Choice {
+    top.iter()
+        .map(|alternatives| {
+            alternatives
+                .first()
+                .and_then(|first| substitutions.get(&first.kanji))
+                .and_then(|replacement| alternatives.iter().position(|x| &x.kanji == replacement))
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Tries the alternatives of the positions not fixed by `base`, returns `base` if nothing scores
+/// better.
+fn search_best_choice(
+    top: &KanjiTopResults,
+    base: Choice,
+    dictionary_score: impl Fn(&str) -> f32,
+) -> Choice {
+    let candidates: Vec<(usize, Vec<usize>)> = get_candidates(top)
+        .into_iter()
+        .filter(|(position, _)| base[*position] == 0)
+        .collect();
+
+    let score = |choice: &[usize]| {
+        let text = get_text(top, choice);
+        let probability: f32 = choice
+            .iter()
+            .zip(top)
+            .map(|(index, alternatives)| {
+                alternatives[*index].confidence.max(f32::MIN_POSITIVE).ln()
+            })
+            .sum();
+        DICTIONARY_WEIGHT * dictionary_score(&text) + probability
+    };
+
+    let best = candidates
+        .iter()
+        .map(|(_, alternatives)| alternatives.iter())
+        .multi_cartesian_product()
+        .map(|combination| {
+            let mut choice = base.clone();
+            for ((position, _), index) in candidates.iter().zip(combination) {
+                choice[*position] = *index;
+            }
+            (score(&choice), choice)
+        })
+        .max_by(|(a, _), (b, _)| a.total_cmp(b));
+
+    match best {
+        Some((best_score, best)) if best_score > score(&base) => best,
+        _ => base,
+    }
+}
+
+fn create_correction(top: &KanjiTopResults, choice: &[usize]) -> Option<(String, Correction)> {
+    if choice.iter().all(|x| *x == 0) {
+        return None;
+    }
+
+    let replacements = choice
+        .iter()
+        .enumerate()
+        .filter(|(_, index)| **index != 0)
+        .map(|(position, index)| Replacement {
+            index: position,
+            original: top[position][0].kanji.clone(),
+            replacement: top[position][*index].kanji.clone(),
+        })
+        .collect();
+
+    let correction = Correction {
+        original: get_text(top, &vec![0; top.len()]),
+        replacements,
+    };
+    Some((get_text(top, choice), correction))
+}
+
+/// The replaced character if the texts differ in exactly one character.
+pub fn get_substitution(original: &str, corrected: &str) -> Option<(String, String)> {
+    let original: Vec<char> = original.chars().collect();
+    let corrected: Vec<char> = corrected.chars().collect();
+    if original.len() != corrected.len() {
+        return None;
+    }
+
+    original
+        .iter()
+        .zip(&corrected)
+        .filter(|(a, b)| a != b)
+        .exactly_one()
+        .ok()
+        .map(|(a, b)| (a.to_string(), b.to_string()))
+}
+
+/// Stable FNV-1a hash of the size and the grayscale pixels of a text box.
+pub fn get_crop_hash(image: &DynamicImage) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    let gray = image.to_luma8();
+    let bytes = gray
+        .width()
+        .to_le_bytes()
+        .into_iter()
+        .chain(gray.height().to_le_bytes())
+        .chain(gray.as_raw().iter().copied());
+
+    let hash = bytes.fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(PRIME)
+    });
+    format!("{hash:016x}")
+}
+
+/// Positions with more than one plausible character and the indices of their alternatives,
+/// limited to `MAX_COMBINATIONS` sequences starting with the least certain positions.
+fn get_candidates(top: &KanjiTopResults) -> Vec<(usize, Vec<usize>)> {
+    let mut candidates: Vec<(usize, Vec<usize>)> = top
+        .iter()
+        .enumerate()
+        .filter_map(|(position, alternatives)| {
+            let first = alternatives.first()?;
+            let indices: Vec<usize> = alternatives
+                .iter()
+                .enumerate()
+                .skip(1)
+                .filter(|(_, x)| {
+                    x.confidence >= MIN_ALTERNATIVE_PROBABILITY
+                        || is_look_alike(&first.kanji, &x.kanji)
+                })
+                .map(|(index, _)| index)
+                .take(MAX_ALTERNATIVES)
+                .collect();
+
+            (!indices.is_empty()).then(|| (position, [vec![0], indices].concat()))
+        })
+        .sorted_by(|(a, _), (b, _)| top[*a][0].confidence.total_cmp(&top[*b][0].confidence))
+        .collect();
+
+    let mut combinations = 1;
+    let count = candidates
+        .iter()
+        .take_while(|(_, indices)| {
+            combinations *= indices.len();
+            combinations <= MAX_COMBINATIONS
+        })
+        .count();
+    candidates.truncate(count);
+    candidates
+}
+
+fn is_look_alike(a: &str, b: &str) -> bool {
+    let (Some(a), Some(b)) = (a.chars().exactly_one().ok(), b.chars().exactly_one().ok()) else {
+        return false;
+    };
+    LOOK_ALIKES.contains(&(a, b)) || LOOK_ALIKES.contains(&(b, a))
+}
+
+fn get_text(top: &KanjiTopResults, choice: &[usize]) -> String {
+    top.iter()
+        .zip(choice)
+        .map(|(alternatives, index)| alternatives[*index].kanji.as_str())
+        .join("")
+}
+
+/// Longer dictionary words score more than several short ones, characters without a match are
+/// penalised.
+fn get_dictionary_score(text: &str) -> f32 {
+    extract_words(text)
+        .iter()
+        .map(|(word, entries)| {
+            let length = word.chars().count() as f32;
+            if entries.is_empty() {
+                -length
+            } else {
+                length * length
+            }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::manga_ocr::KanjiConf;
+
+    fn position(alternatives: &[(&str, f32)]) -> Vec<KanjiConf> {
+        alternatives
+            .iter()
+            .map(|(kanji, confidence)| KanjiConf {
+                kanji: kanji.to_string(),
+                confidence: *confidence,
+            })
+            .collect()
+    }
+
+    fn correct_with(
+        top: &KanjiTopResults,
+        dictionary_score: impl Fn(&str) -> f32,
+    ) -> Option<(String, Correction)> {
+        let base = vec![0; top.len()];
+        create_correction(top, &search_best_choice(top, base, dictionary_score))
+    }
+
+    /// Every occurrence of the word counts as a dictionary match.
+    fn word_score(word: &'static str) -> impl Fn(&str) -> f32 {
+        move |text: &str| 4.0 * text.matches(word).count() as f32
+    }
+
+    #[test]
+    fn test_correct_look_alike() {
+        let top = vec![
+            position(&[("口", 0.6), ("ロ", 0.3)]),
+            position(&[("ボ", 0.99)]),
+            position(&[("ト", 0.99)]),
+        ];
+
+        let (text, correction) = correct_with(&top, word_score("ロボト")).unwrap();
+
+        assert_eq!(text, "ロボト");
+        assert_eq!(correction.original, "口ボト");
+        assert_eq!(correction.get_summary(), "口 → ロ");
+        assert_eq!(correction.get_char_confidences(&top), vec![0.3, 0.99, 0.99]);
+    }
+
+    #[test]
+    fn test_char_confidences_length() {
+        // The replacement is longer than the original token
+        let top = vec![
+            position(&[("ロ", 0.4), ("ロボ", 0.35)]),
+            position(&[("ト", 0.9)]),
+        ];
+        let correction = Correction {
+            original: "ロト".to_string(),
+            replacements: vec![Replacement {
+                index: 0,
+                original: "ロ".to_string(),
+                replacement: "ロボ".to_string(),
+            }],
+        };
+
+        let confidences = correction.get_char_confidences(&top);
+        assert_eq!(confidences, vec![0.35, 0.35, 0.9]);
+        assert_eq!(confidences.len(), "ロボト".chars().count());
+    }
+
+    #[test]
+    fn test_learned_substitution() {
+        let top = vec![
+            position(&[("日", 0.9), ("曰", 0.05)]),
+            position(&[("本", 0.9), ("木", 0.01)]),
+        ];
+        let substitutions = Substitutions::from([
+            ("日".to_string(), "曰".to_string()),
+            ("本".to_string(), "体".to_string()),
+        ]);
+
+        let (text, correction) = correct(&top, &substitutions, false).unwrap();
+
+        // 体 is not an alternative of the second position
+        assert_eq!(text, "曰本");
+        assert_eq!(correction.get_summary(), "日 → 曰");
+        assert_eq!(correct(&top, &Substitutions::new(), false), None);
+    }
+
+    #[test]
+    fn test_get_substitution() {
+        assert_eq!(
+            get_substitution("口ボット", "ロボット"),
+            Some(("口".to_string(), "ロ".to_string()))
+        );
+        assert_eq!(get_substitution("口ボット", "ロボツト"), None);
+        assert_eq!(get_substitution("口ボット", "ロボット!"), None);
+        assert_eq!(get_substitution("ロボット", "ロボット"), None);
+    }
+
+    #[test]
+    fn test_get_crop_hash() {
+        let image = DynamicImage::new_luma8(10, 20);
+        let other = DynamicImage::new_luma8(20, 10);
+
+        assert_eq!(get_crop_hash(&image), get_crop_hash(&image.clone()));
+        assert_ne!(get_crop_hash(&image), get_crop_hash(&other));
+        assert_eq!(get_crop_hash(&image).len(), 16);
+    }
+
+    #[test]
+    fn test_is_look_alike() {
+        assert!(is_look_alike("カ", "力"));
+        assert!(is_look_alike("力", "カ"));
+        assert!(!is_look_alike("力", "刀"));
+        assert!(!is_look_alike("カカ", "力"));
+    }
+
+    #[test]
+    fn test_keep_confident_text() {
+        let top = vec![
+            position(&[("力", 0.999), ("カ", 0.0001)]),
+            position(&[("学", 0.99)]),
+        ];
+
+        assert_eq!(correct_with(&top, word_score("カ学")), None);
+        assert_eq!(correct_with(&top, |_| 0.0), None);
+    }
+
+    #[test]
+    fn test_get_candidates_limit() {
+        let uncertain = position(&[("a", 0.4), ("b", 0.3), ("c", 0.2), ("d", 0.1)]);
+        let top = vec![uncertain; 10];
+
+        let candidates = get_candidates(&top);
+        let combinations: usize = candidates.iter().map(|(_, x)| x.len()).product();
+
+        assert_eq!(candidates.len(), 3);
+        assert!(combinations <= MAX_COMBINATIONS);
+    }
+}
@@ -0,0 +1,473 @@
+use crate::detect::session_builder::create_session_builder;
+use crate::model::lifecycle::ManagedModels;
+use crate::ocr::manga_ocr::{KanjiConf, KanjiTopResults};
+use crate::ui::backend_status::Backend;
+use anyhow::{Context, Result};
+use image::DynamicImage;
+use image::imageops::FilterType;
+use itertools::Itertools;
+use log::info;
+use ndarray::{Array4, Axis, Ix2};
+use ort::session::Session;
+use ort::value::TensorRef;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use strum::EnumIter;
+
+/// Index of the CTC blank, the characters of the dictionary start at 1.
+const BLANK: usize = 0;
+const MAX_ALTERNATIVES: usize = 10;
+/// Images taller than this ratio are rotated to a horizontal line.
+const VERTICAL_RATIO: f32 = 1.5;
+const MAX_INPUT_WIDTH: u32 = 3200;
+const IMAGENET_MEAN: [f32; 3] = [0.485, 0.456, 0.406];
+const IMAGENET_STD: [f32; 3] = [0.229, 0.224, 0.225];
+
+/// Loaded models by model and dictionary path.
+static CTC_MODELS: ManagedModels<(String, String), CtcModel> = ManagedModels::new("CTC models");
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Default, EnumIter)]
+pub enum Normalization {
+    /// `(x / 255 - 0.5) / 0.5`, used by PaddleOCR
+    #[default]
+    MinusOneToOne,
+    ZeroToOne,
+    ImageNet,
+}
+
+impl Normalization {
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            Normalization::MinusOneToOne => "-1 to 1",
+            Normalization::ZeroToOne => "0 to 1",
+            Normalization::ImageNet => "ImageNet",
+        }
+    }
+
+    pub fn apply(&self, value: u8, channel: usize) -> f32 {
+        let value = value as f32 / 255.0;
+        match self {
+            Normalization::MinusOneToOne => (value - 0.5) / 0.5,
+            Normalization::ZeroToOne => value,
+            Normalization::ImageNet => (value - IMAGENET_MEAN[channel]) / IMAGENET_STD[channel],
+        }
+    }
+
+    /// Pixel value of a model output, the inverse of `apply`.
+    pub fn revert(&self, value: f32, channel: usize) -> u8 {
+        let value = match self {
+            Normalization::MinusOneToOne => value * 0.5 + 0.5,
+            Normalization::ZeroToOne => value,
+            Normalization::ImageNet => value * IMAGENET_STD[channel] + IMAGENET_MEAN[channel],
+        };
+        (value * 255.0).round().clamp(0.0, 255.0) as u8
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum CtcDecoding {
+    #[default]
+    Greedy,
+    /// Prefix beam search, merges the paths which collapse to the same text
+    BeamSearch { beam_width: usize },
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct CtcConfig {
+    pub model_path: String,
+    /// One character per line, the line index + 1 is the class of the character
+    pub dictionary_path: String,
+    pub input_height: u32,
+    pub normalization: Normalization,
+    /// PaddleOCR models are trained on BGR images
+    pub bgr: bool,
+    /// Rotates tall text boxes counterclockwise so vertical text becomes a line
+    pub rotate_vertical: bool,
+    pub decoding: CtcDecoding,
+}
+
+impl Default for CtcConfig {
+    fn default() -> Self {
+        Self {
+            model_path: String::new(),
+            dictionary_path: String::new(),
+            input_height: 48,
+            normalization: Normalization::default(),
+            bgr: true,
+            rotate_vertical: true,
+            decoding: CtcDecoding::default(),
+        }
+    }
+}
+
+struct CtcModel {
+    session: Session,
+    dictionary: Vec<String>,
+    /// Fixed input width of the model, `None` for a dynamic width
+    fixed_width: Option<u32>,
+}
+
+impl CtcModel {
+    fn new(config: &CtcConfig) -> Result<Self> {
+        let session = create_session_builder(Backend::Ctc)?
+            .commit_from_file(&config.model_path)
+            .with_context(|| format!("Failed to load CTC model {}", config.model_path))?;
+
+        let dictionary = std::fs::read_to_string(&config.dictionary_path)
+            .with_context(|| format!("Failed to read dictionary {}", config.dictionary_path))?
+            .lines()
+            .map(|x| x.to_string())
+            .collect();
+
+        let fixed_width = session
+            .inputs
+            .first()
+            .and_then(|x| x.input_type.tensor_shape())
+            .and_then(|shape| shape.get(3).copied())
+            .filter(|width| *width > 0)
+            .map(|width| width as u32);
+
+        info!("Loaded CTC model {}", config.model_path);
+        Ok(Self {
+            session,
+            dictionary,
+            fixed_width,
+        })
+    }
+
+    fn recognize(&mut self, image: &DynamicImage, config: &CtcConfig) -> Result<KanjiTopResults> {
+        let tensor = create_image_tensor(image, config, self.fixed_width);
+        let frames = self.run_model(&tensor)?;
+
+        let path = match config.decoding {
+            CtcDecoding::Greedy => greedy_decode(&frames),
+            CtcDecoding::BeamSearch { beam_width } => prefix_beam_search(&frames, beam_width),
+        };
+
+        Ok(self.get_top_results(&frames, &path))
+    }
+
+    /// Probabilities of the classes for each frame.
+    fn run_model(&mut self, tensor: &Array4<f32>) -> Result<Vec<Vec<f32>>> {
+        let input_name = self.session.inputs[0].name.clone();
+
+        let outputs = self
+            .session
+            .run(ort::inputs![input_name => TensorRef::from_array_view(tensor)?])?;
+        let (_, output) = outputs.iter().next().context("CTC model has no output")?;
+        let output = output.try_extract_array::<f32>()?;
+
+        // batch, time, classes
+        let frames = output
+            .index_axis(Axis(0), 0)
+            .into_dimensionality::<Ix2>()?
+            .rows()
+            .into_iter()
+            .map(|row| get_probabilities(row.to_vec()))
+            .collect();
+
+        Ok(frames)
+    }
+
+    /// Alternatives of each decoded character from the frame it was emitted in.
+    fn get_top_results(&self, frames: &[Vec<f32>], path: &[(usize, usize)]) -> KanjiTopResults {
+        path.iter()
+            .map(|(token, frame)| {
+                let probabilities = &frames[*frame];
+                let alternatives = probabilities
+                    .iter()
+                    .enumerate()
+                    .filter(|(class, _)| *class != BLANK && class != token)
+                    .sorted_by(|(_, a), (_, b)| b.total_cmp(a))
+                    .take(MAX_ALTERNATIVES - 1);
+
+                std::iter::once((*token, &probabilities[*token]))
+                    .chain(alternatives)
+                    .map(|(class, confidence)| KanjiConf {
+                        kanji: self.get_char(class),
+                        confidence: *confidence,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Classes after the dictionary are the space character.
+    fn get_char(&self, class: usize) -> String {
+        self.dictionary
+            .get(class - 1)
+            .cloned()
+            .unwrap_or_else(|| " ".to_string())
+    }
+}
+
+/// Drops the loaded models so they are created again with the current inference config.
+pub fn unload_ctc_models() {
+    CTC_MODELS.unload();
+}
+
+/// Unloads the models which were not used for the timeout, returns if a model was unloaded.
+pub fn unload_idle_ctc_models(timeout: Duration) -> bool {
+    CTC_MODELS.unload_if_idle(timeout)
+}
+
+pub fn run_ctc(images: Vec<&DynamicImage>, config: &CtcConfig) -> Result<Vec<KanjiTopResults>> {
+    let key = (config.model_path.clone(), config.dictionary_path.clone());
+    CTC_MODELS.with(
+        &key,
+        || CtcModel::new(config),
+        |model| {
+            images
+                .into_iter()
+                .map(|image| model.recognize(image, config))
+                .collect()
+        },
+    )?
+}
+
+fn create_image_tensor(
+    image: &DynamicImage,
+    config: &CtcConfig,
+    fixed_width: Option<u32>,
+) -> Array4<f32> {
+    let image = if config.rotate_vertical
+        && image.height() as f32 >= image.width() as f32 * VERTICAL_RATIO
+    {
+        image.rotate270()
+    } else {
+        image.clone()
+    };
+
+    let height = config.input_height.max(1);
+    let max_width = fixed_width.unwrap_or(MAX_INPUT_WIDTH);
+    let width = get_resized_width(image.width(), image.height(), height).min(max_width);
+    let resized = image
+        .resize_exact(width, height, FilterType::Triangle)
+        .to_rgb8();
+
+    // Padded with zeros after the normalisation like PaddleOCR
+    let tensor_width = fixed_width.unwrap_or(width);
+    let mut tensor = Array4::<f32>::zeros((1, 3, height as usize, tensor_width as usize));
+    for (x, y, pixel) in resized.enumerate_pixels() {
+        for channel in 0..3 {
+            let source = if config.bgr { 2 - channel } else { channel };
+            tensor[[0, channel, y as usize, x as usize]] =
+                config.normalization.apply(pixel[source], source);
+        }
+    }
+    tensor
+}
+
+fn get_resized_width(width: u32, height: u32, target_height: u32) -> u32 {
+    let ratio = width as f32 / height.max(1) as f32;
+    ((target_height as f32 * ratio).ceil() as u32).max(1)
+}
+
+/// Applies a softmax if the model outputs logits instead of probabilities.
+fn get_probabilities(values: Vec<f32>) -> Vec<f32> {
+    let sum: f32 = values.iter().sum();
+    if values.iter().all(|x| (0.0..=1.0).contains(x)) && (sum - 1.0).abs() < 1e-3 {
+        return values;
+    }
+
+    let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let exp: Vec<f32> = values.iter().map(|x| (x - max).exp()).collect();
+    let sum: f32 = exp.iter().sum();
+    exp.into_iter().map(|x| x / sum).collect()
+}
+
+/// Best class of each frame with repeats collapsed and blanks removed, returns the class and the
+/// most confident frame of each character.
+fn greedy_decode(frames: &[Vec<f32>]) -> Vec<(usize, usize)> {
+    let best: Vec<(usize, f32)> = frames
+        .iter()
+        .map(|x| {
+            x.iter()
+                .copied()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .unwrap_or((BLANK, 0.0))
+        })
+        .collect();
+
+    best.iter()
+        .enumerate()
+        .chunk_by(|(_, (class, _))| *class)
+        .into_iter()
+        .filter(|(class, _)| *class != BLANK)
+        .filter_map(|(class, run)| {
+            run.max_by(|(_, (_, a)), (_, (_, b))| a.total_cmp(b))
+                .map(|(frame, _)| (class, frame))
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+struct Prefix {
+    /// Class and emitting frame of each character
+    path: Vec<(usize, usize)>,
+    /// Log probability of the paths ending with a blank
+    blank: f32,
+    /// Log probability of the paths ending with the last character
+    non_blank: f32,
+}
+
+impl Prefix {
+    fn total(&self) -> f32 {
+        log_add(self.blank, self.non_blank)
+    }
+}
+
+/// CTC prefix beam search, only the `beam_width` most likely classes of each frame are extended.
+fn prefix_beam_search(frames: &[Vec<f32>], beam_width: usize) -> Vec<(usize, usize)> {
+    let beam_width = beam_width.max(1);
+    let mut beams = vec![Prefix {
+        path: vec![],
+        blank: 0.0,
+        non_blank: f32::NEG_INFINITY,
+    }];
+
+    for (frame, probabilities) in frames.iter().enumerate() {
+        let classes: Vec<(usize, f32)> = probabilities
+            .iter()
+            .enumerate()
+            .sorted_by(|(_, a), (_, b)| b.total_cmp(a))
+            .take(beam_width)
+            .map(|(class, probability)| (class, probability.max(f32::MIN_POSITIVE).ln()))
+            .collect();
+
+        let mut next: HashMap<Vec<usize>, Prefix> = HashMap::new();
+        for beam in &beams {
+            let last = beam.path.last().map(|(class, _)| *class);
+            for (class, log_prob) in &classes {
+                if *class == BLANK {
+                    add_prefix(
+                        &mut next,
+                        &beam.path,
+                        beam.total() + log_prob,
+                        f32::NEG_INFINITY,
+                    );
+                    continue;
+                }
+
+                let mut extended = beam.path.clone();
+                extended.push((*class, frame));
+                if Some(*class) == last {
+                    // Repeats collapse unless they are separated by a blank
+                    add_prefix(
+                        &mut next,
+                        &beam.path,
+                        f32::NEG_INFINITY,
+                        beam.non_blank + log_prob,
+                    );
+                    add_prefix(
+                        &mut next,
+                        &extended,
+                        f32::NEG_INFINITY,
+                        beam.blank + log_prob,
+                    );
+                } else {
+                    add_prefix(
+                        &mut next,
+                        &extended,
+                        f32::NEG_INFINITY,
+                        beam.total() + log_prob,
+                    );
+                }
+            }
+        }
+
+        beams = next
+            .into_values()
+            .sorted_by(|a, b| b.total().total_cmp(&a.total()))
+            .take(beam_width)
+            .collect();
+    }
+
+    beams
+        .into_iter()
+        .max_by(|a, b| a.total().total_cmp(&b.total()))
+        .map(|x| x.path)
+        .unwrap_or_default()
+}
+
+fn add_prefix(
+    prefixes: &mut HashMap<Vec<usize>, Prefix>,
+    path: &[(usize, usize)],
+    blank: f32,
+    non_blank: f32,
+) {
+    let key = path.iter().map(|(class, _)| *class).collect();
+    let prefix = prefixes.entry(key).or_insert_with(|| Prefix {
+        path: path.to_vec(),
+        blank: f32::NEG_INFINITY,
+        non_blank: f32::NEG_INFINITY,
+    });
+    prefix.blank = log_add(prefix.blank, blank);
+    prefix.non_blank = log_add(prefix.non_blank, non_blank);
+}
+
+fn log_add(a: f32, b: f32) -> f32 {
+    let max = a.max(b);
+    if max == f32::NEG_INFINITY {
+        return max;
+    }
+    max + ((a - max).exp() + (b - max).exp()).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(probabilities: &[(usize, f32)]) -> Vec<f32> {
+        let mut values = vec![0.0; 4];
+        for (class, probability) in probabilities {
+            values[*class] = *probability;
+        }
+        values
+    }
+
+    #[test]
+    fn test_greedy_decode() {
+        let frames = vec![
+            frame(&[(1, 0.6), (0, 0.4)]),
+            frame(&[(1, 0.9), (0, 0.1)]),
+            frame(&[(0, 1.0)]),
+            frame(&[(1, 0.7), (2, 0.3)]),
+            frame(&[(2, 0.8), (0, 0.2)]),
+        ];
+
+        assert_eq!(greedy_decode(&frames), vec![(1, 1), (1, 3), (2, 4)]);
+    }
+
+    #[test]
+    fn test_prefix_beam_search_merges_paths() {
+        // Greedy reads a blank, but the paths of "1" have more probability in total
+        let frames = vec![
+            frame(&[(0, 0.4), (1, 0.35), (2, 0.25)]),
+            frame(&[(0, 0.4), (1, 0.35), (2, 0.25)]),
+        ];
+
+        assert!(greedy_decode(&frames).is_empty());
+        let path = prefix_beam_search(&frames, 3);
+        assert_eq!(path.iter().map(|x| x.0).collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn test_get_probabilities() {
+        let probabilities = vec![0.25, 0.75];
+        assert_eq!(get_probabilities(probabilities.clone()), probabilities);
+
+        let softmax = get_probabilities(vec![0.0, 0.0, 3.0]);
+        assert!((softmax.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        assert!(softmax[2] > 0.9);
+    }
+
+    #[test]
+    fn test_get_resized_width() {
+        assert_eq!(get_resized_width(200, 50, 48), 192);
+        assert_eq!(get_resized_width(1, 100, 48), 1);
+    }
+}
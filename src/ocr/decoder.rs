@@ -0,0 +1,229 @@
+use anyhow::{Context, bail};
+use ndarray::{Array1, Array2, Array4, ArrayD, Axis, IxDyn, s};
+use ort::inputs;
+use ort::session::{Session, SessionInputValue};
+use ort::value::{Tensor, TensorRef};
+
+use crate::detect::session_builder::create_session_builder;
+use crate::model::store::{self, MANGA_OCR_MODEL, ModelVariant};
+use crate::ui::backend_status::Backend;
+
+/// Split export of the model in the layout of `optimum-cli export onnx`, the encoder and the
+/// merged decoder with the `use_cache_branch` input.
+const ENCODER_MODEL: &str = "encoder_model.onnx";
+const DECODER_MODEL: &str = "decoder_model_merged.onnx";
+
+const PAST_PREFIX: &str = "past_key_values.";
+const PRESENT_PREFIX: &str = "present.";
+
+/// Encoder and decoder sessions, the image is encoded once and the decoder reuses the key value
+/// cache of the previous tokens.
+#[derive(Debug)]
+pub struct SplitModel {
+    encoder: Session,
+    decoder: Session,
+}
+
+impl SplitModel {
+    /// Loads the split model if its files are in the models directory, they are only
+    /// downloaded together with the model and not on every load.
+    pub fn load(variant: ModelVariant) -> anyhow::Result<Option<Self>> {
+        if !MANGA_OCR_MODEL.optional_files.contains(&ENCODER_MODEL)
+            || !MANGA_OCR_MODEL.optional_files.contains(&DECODER_MODEL)
+        {
+            return Ok(None);
+        }
+
+        let encoder_path =
+            store::find_local_file(&MANGA_OCR_MODEL, &variant.get_file_name(ENCODER_MODEL))?;
+        let decoder_path =
+            store::find_local_file(&MANGA_OCR_MODEL, &variant.get_file_name(DECODER_MODEL))?;
+        let (Some(encoder_path), Some(decoder_path)) = (encoder_path, decoder_path) else {
+            return Ok(None);
+        };
+
+        let encoder = create_session_builder(Backend::MangaOcr)?.commit_from_file(encoder_path)?;
+        let decoder = create_session_builder(Backend::MangaOcr)?.commit_from_file(decoder_path)?;
+
+        if !decoder
+            .inputs
+            .iter()
+            .any(|x| x.name.starts_with(PAST_PREFIX))
+        {
+            bail!("Decoder model has no past key value inputs");
+        }
+
+        Ok(Some(Self { encoder, decoder }))
+    }
+}
+
+/// Decoding state of the rows of one batch.
+///
+/// Each step passes the image of every row and the index of the row in the previous step
+/// it continues, so rows can be dropped (finished lines) or duplicated (beams).
+pub enum DecoderState {
+    /// Full model, the image and the whole token sequence are passed every step
+    Full { images: Array4<f32> },
+    /// Split model, only the last token is passed with the cache of the previous step
+    Cached {
+        encoder_hidden_states: ArrayD<f32>,
+        past: Vec<(String, ArrayD<f32>)>,
+    },
+}
+
+impl DecoderState {
+    pub fn full(images: Array4<f32>) -> Self {
+        DecoderState::Full { images }
+    }
+
+    pub fn cached(model: &mut SplitModel, images: &Array4<f32>) -> anyhow::Result<Self> {
+        let outputs = model.encoder.run(inputs! {
+            "pixel_values" => TensorRef::from_array_view(images)?,
+        })?;
+        let encoder_hidden_states = outputs["last_hidden_state"]
+            .try_extract_array::<f32>()?
+            .to_owned();
+
+        Ok(DecoderState::Cached {
+            encoder_hidden_states,
+            past: vec![],
+        })
+    }
+
+    pub fn get_batch_size(&self) -> usize {
+        match self {
+            DecoderState::Full { images } => images.len_of(Axis(0)),
+            DecoderState::Cached {
+                encoder_hidden_states,
+                ..
+            } => encoder_hidden_states.len_of(Axis(0)),
+        }
+    }
+
+    /// Runs one decoding step and returns the logits of the next token for each row.
+    pub fn step(
+        &mut self,
+        full_model: &mut Session,
+        split_model: Option<&mut SplitModel>,
+        image_indices: &[usize],
+        parent_rows: &[usize],
+        token_ids: &[Vec<i64>],
+    ) -> anyhow::Result<Array2<f32>> {
+        match self {
+            DecoderState::Full { images } => {
+                let images = images.select(Axis(0), image_indices);
+                let input = get_token_array(token_ids, false)?;
+
+                let outputs = full_model.run(inputs! {
+                    "image" => TensorRef::from_array_view(&images)?,
+                    "token_ids" => TensorRef::from_array_view(&input)?,
+                })?;
+                let logits = outputs["logits"].try_extract_array::<f32>()?;
+
+                Ok(logits.slice(s![.., -1, ..]).to_owned())
+            }
+            DecoderState::Cached {
+                encoder_hidden_states,
+                past,
+            } => {
+                let model = split_model.context("Split model is not loaded")?;
+                let use_cache = !past.is_empty();
+                let batch_size = image_indices.len();
+
+                let mut inputs: Vec<(String, SessionInputValue<'_>)> = vec![
+                    (
+                        "input_ids".to_string(),
+                        Tensor::from_array(get_token_array(token_ids, use_cache)?)?.into(),
+                    ),
+                    (
+                        "encoder_hidden_states".to_string(),
+                        Tensor::from_array(encoder_hidden_states.select(Axis(0), image_indices))?
+                            .into(),
+                    ),
+                    (
+                        "use_cache_branch".to_string(),
+                        Tensor::from_array(Array1::from_elem(1, use_cache))?.into(),
+                    ),
+                ];
+
+                if use_cache {
+                    for (name, value) in past.iter() {
+                        let value = value.select(Axis(0), parent_rows);
+                        inputs.push((name.clone(), Tensor::from_array(value)?.into()));
+                    }
+                } else {
+                    for input in &model.decoder.inputs {
+                        if !input.name.starts_with(PAST_PREFIX) {
+                            continue;
+                        }
+                        let shape = input
+                            .input_type
+                            .tensor_shape()
+                            .context("Past key value input is not a tensor")?;
+                        // batch, heads, sequence, head size with an empty sequence
+                        let dims = [
+                            batch_size,
+                            shape[1].max(1) as usize,
+                            0,
+                            shape[3].max(1) as usize,
+                        ];
+                        let empty = ArrayD::<f32>::zeros(IxDyn(&dims));
+                        inputs.push((input.name.clone(), Tensor::from_array(empty)?.into()));
+                    }
+                }
+
+                let outputs = model.decoder.run(inputs)?;
+
+                let mut next_past = vec![];
+                for (name, value) in outputs.iter() {
+                    if let Some(name) = name.strip_prefix(PRESENT_PREFIX) {
+                        let value = value.try_extract_array::<f32>()?.to_owned();
+                        next_past.push((format!("{PAST_PREFIX}{name}"), value));
+                    }
+                }
+                *past = next_past;
+
+                let logits = outputs["logits"].try_extract_array::<f32>()?;
+                Ok(logits.slice(s![.., -1, ..]).to_owned())
+            }
+        }
+    }
+}
+
+/// Token ids of all rows, only the last token of each row if the previous tokens are cached.
+fn get_token_array(token_ids: &[Vec<i64>], last_only: bool) -> anyhow::Result<Array2<i64>> {
+    let sequence_length = if last_only {
+        1
+    } else {
+        token_ids.first().map_or(0, |x| x.len())
+    };
+
+    let values = token_ids
+        .iter()
+        .flat_map(|x| &x[x.len() - sequence_length..])
+        .copied()
+        .collect();
+
+    Ok(Array2::from_shape_vec(
+        (token_ids.len(), sequence_length),
+        values,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_token_array() {
+        let token_ids = vec![vec![2, 10, 11], vec![2, 20, 21]];
+
+        let full = get_token_array(&token_ids, false).unwrap();
+        assert_eq!(full.shape(), &[2, 3]);
+        assert_eq!(full.row(1).to_vec(), vec![2, 20, 21]);
+
+        let last = get_token_array(&token_ids, true).unwrap();
+        assert_eq!(last.shape(), &[2, 1]);
+        assert_eq!(last.column(0).to_vec(), vec![11, 21]);
+    }
+}
@@ -0,0 +1,291 @@
+use crate::ocr::BackendResult;
+use crate::ocr::manga_ocr::{KanjiConf, KanjiTopResults, get_kanji_top_text};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+/// Lower bound of a vote so backends without a confidence still count.
+const MIN_WEIGHT: f32 = 0.01;
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Default)]
+pub struct BackendText {
+    pub backend: String,
+    pub text: String,
+    pub confidence: Option<f32>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Default)]
+pub struct EnsembleResult {
+    /// Voted characters with the share of the votes as confidence, the winner first
+    pub top: KanjiTopResults,
+    /// Text of each backend, the most confident first
+    pub texts: Vec<BackendText>,
+}
+
+impl EnsembleResult {
+    pub fn get_text(&self) -> Option<String> {
+        get_kanji_top_text(&self.top, 0)
+    }
+}
+
+/// Recognised text of one backend with the weight of each character.
+struct Hypothesis {
+    chars: Vec<char>,
+    weights: Vec<f32>,
+    /// Weight of a vote for no character at a position
+    weight: f32,
+}
+
+/// Aligned position, every hypothesis votes for a character or for no character.
+#[derive(Default)]
+struct Slot {
+    votes: Vec<(Option<char>, f32)>,
+}
+
+impl Slot {
+    fn get_weights(&self) -> Vec<(Option<char>, f32)> {
+        let mut weights: Vec<(Option<char>, f32)> = vec![];
+        for (vote, weight) in &self.votes {
+            match weights.iter_mut().find(|(x, _)| x == vote) {
+                Some((_, sum)) => *sum += weight,
+                None => weights.push((*vote, *weight)),
+            }
+        }
+        // Stable sort keeps the more confident backend first on a tie
+        weights.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        weights
+    }
+
+    /// Character the next hypothesis is aligned to.
+    fn get_reference(&self) -> Option<char> {
+        self.get_weights().into_iter().find_map(|(x, _)| x)
+    }
+}
+
+/// Combines the results of several backends for each image, the backends are given with their
+/// label and one result per image.
+pub fn vote(results: &[(&str, Vec<BackendResult>)], image_count: usize) -> Vec<BackendResult> {
+    (0..image_count)
+        .map(|index| {
+            let results = results
+                .iter()
+                .filter_map(|(label, x)| Some((*label, x.get(index)?)));
+            BackendResult::Ensemble(vote_image(results))
+        })
+        .collect()
+}
+
+fn vote_image<'a>(results: impl Iterator<Item = (&'a str, &'a BackendResult)>) -> EnsembleResult {
+    let (texts, hypotheses): (Vec<BackendText>, Vec<Hypothesis>) = results
+        .filter_map(|(label, result)| {
+            let text = result.get_text()?;
+            let confidence = result.get_confidence();
+            let hypothesis = create_hypothesis(&text, result.get_char_confidences(), confidence);
+            let text = BackendText {
+                backend: label.to_string(),
+                text,
+                confidence,
+            };
+            Some((text, hypothesis))
+        })
+        .sorted_by(|(_, a), (_, b)| b.weight.total_cmp(&a.weight))
+        .unzip();
+
+    EnsembleResult {
+        top: combine(&hypotheses),
+        texts,
+    }
+}
+
+fn create_hypothesis(text: &str, confidences: Vec<f32>, confidence: Option<f32>) -> Hypothesis {
+    let chars: Vec<char> = text.chars().collect();
+    let weight = confidence.unwrap_or(1.0).max(MIN_WEIGHT);
+    let weights = if confidences.len() == chars.len() {
+        confidences.into_iter().map(|x| x.max(MIN_WEIGHT)).collect()
+    } else {
+        vec![weight; chars.len()]
+    };
+
+    Hypothesis {
+        chars,
+        weights,
+        weight,
+    }
+}
+
+/// Aligns the hypotheses one after another to the slots of the previous ones and picks the
+/// character with the highest summed confidence in each slot.
+fn combine(hypotheses: &[Hypothesis]) -> KanjiTopResults {
+    let mut slots: Vec<Slot> = vec![];
+
+    for (index, hypothesis) in hypotheses.iter().enumerate() {
+        let references: Vec<Option<char>> = slots.iter().map(Slot::get_reference).collect();
+        let mut inserted = 0;
+
+        for step in align(&references, &hypothesis.chars) {
+            match step {
+                Step::Match(slot, char) => slots[slot + inserted]
+                    .votes
+                    .push((Some(hypothesis.chars[char]), hypothesis.weights[char])),
+                Step::Delete(slot) => slots[slot + inserted].votes.push((None, hypothesis.weight)),
+                Step::Insert(slot, char) => {
+                    // The previous hypotheses vote for no character at the new position
+                    let mut votes: Vec<(Option<char>, f32)> = hypotheses[..index]
+                        .iter()
+                        .map(|x| (None, x.weight))
+                        .collect();
+                    votes.push((Some(hypothesis.chars[char]), hypothesis.weights[char]));
+                    slots.insert(slot + inserted, Slot { votes });
+                    inserted += 1;
+                }
+            }
+        }
+    }
+
+    slots
+        .iter()
+        .filter_map(|slot| {
+            let weights = slot.get_weights();
+            let total: f32 = weights.iter().map(|(_, x)| x).sum();
+            // No character if most of the votes are for no character
+            weights.first()?.0?;
+
+            let alternatives = weights
+                .iter()
+                .filter_map(|(char, weight)| {
+                    Some(KanjiConf {
+                        kanji: (*char)?.to_string(),
+                        confidence: weight / total,
+                    })
+                })
+                .collect();
+            Some(alternatives)
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+enum Step {
+    /// Slot and character index, a match or a substitution
+    Match(usize, usize),
+    /// The hypothesis has no character for the slot
+    Delete(usize),
+    /// New slot before the slot index for the character
+    Insert(usize, usize),
+}
+
+/// Levenshtein alignment of the hypothesis to the slot references.
+fn align(references: &[Option<char>], chars: &[char]) -> Vec<Step> {
+    let (n, m) = (references.len(), chars.len());
+    let mut costs = vec![vec![0; m + 1]; n + 1];
+    for (i, row) in costs.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cost) in costs[0].iter_mut().enumerate() {
+        *cost = j;
+    }
+    for i in 1..=n {
+        for j in 1..=m {
+            let substitution = usize::from(references[i - 1] != Some(chars[j - 1]));
+            costs[i][j] = (costs[i - 1][j - 1] + substitution)
+                .min(costs[i - 1][j] + 1)
+                .min(costs[i][j - 1] + 1);
+        }
+    }
+
+    let mut steps = vec![];
+    let (mut i, mut j) = (n, m);
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 {
+            let substitution = usize::from(references[i - 1] != Some(chars[j - 1]));
+            if costs[i][j] == costs[i - 1][j - 1] + substitution {
+                steps.push(Step::Match(i - 1, j - 1));
+                i -= 1;
+                j -= 1;
+                continue;
+            }
+        }
+        if i > 0 && costs[i][j] == costs[i - 1][j] + 1 {
+            steps.push(Step::Delete(i - 1));
+            i -= 1;
+        } else {
+            steps.push(Step::Insert(i, j - 1));
+            j -= 1;
+        }
+    }
+    steps.reverse();
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hypothesis(text: &str, confidence: f32) -> Hypothesis {
+        create_hypothesis(text, vec![], Some(confidence))
+    }
+
+    fn get_text(top: &KanjiTopResults) -> String {
+        get_kanji_top_text(top, 0).unwrap()
+    }
+
+    #[test]
+    fn test_vote_substitution() {
+        let top = combine(&[
+            hypothesis("ロボット", 0.6),
+            hypothesis("口ボット", 0.7),
+            hypothesis("ロボツト", 0.5),
+        ]);
+
+        assert_eq!(get_text(&top), "ロボット");
+        assert_eq!(top[0][1].kanji, "口");
+        assert!((top[0][0].confidence - 1.1 / 1.8).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_vote_insertion_and_deletion() {
+        let top = combine(&[
+            hypothesis("今日", 0.9),
+            hypothesis("今日は", 0.8),
+            hypothesis("今日は", 0.7),
+        ]);
+        assert_eq!(get_text(&top), "今日は");
+
+        let top = combine(&[
+            hypothesis("今日", 0.9),
+            hypothesis("今日は", 0.8),
+            hypothesis("今日", 0.7),
+        ]);
+        assert_eq!(get_text(&top), "今日");
+    }
+
+    #[test]
+    fn test_char_confidences() {
+        let first = create_hypothesis("力学", vec![0.3, 0.9], Some(0.5));
+        let second = create_hypothesis("カ学", vec![0.8, 0.9], Some(0.4));
+
+        assert_eq!(get_text(&combine(&[first, second])), "カ学");
+
+        // Without character confidences, e.g. Tesseract, every character has the box confidence
+        let tesseract = create_hypothesis("今日", vec![], Some(0.6));
+        assert_eq!(tesseract.weights, vec![0.6, 0.6]);
+    }
+
+    #[test]
+    fn test_align() {
+        let references = vec![Some('a'), Some('b'), Some('c')];
+
+        assert_eq!(
+            align(&references, &['a', 'x', 'c', 'd']),
+            vec![
+                Step::Match(0, 0),
+                Step::Match(1, 1),
+                Step::Match(2, 2),
+                Step::Insert(3, 3)
+            ]
+        );
+        assert_eq!(
+            align(&references, &['a', 'c']),
+            vec![Step::Match(0, 0), Step::Delete(1), Step::Match(2, 1)]
+        );
+    }
+}
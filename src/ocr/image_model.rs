@@ -0,0 +1,298 @@
+use crate::detect::session_builder::create_session_builder;
+use crate::model::lifecycle::ManagedModels;
+use crate::ocr::ctc::Normalization;
+use crate::ui::backend_status::Backend;
+use anyhow::{Context, Result, bail};
+use image::{DynamicImage, GenericImage, GrayImage, Luma, Rgb, RgbImage};
+use log::info;
+use ndarray::{Array4, ArrayView3, Axis, Ix3};
+use ort::session::Session;
+use ort::value::TensorRef;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// Loaded image-to-image models by model path.
+static IMAGE_MODELS: ManagedModels<String, ImageModel> = ManagedModels::new("image models");
+
+/// Image-to-image model like Real-ESRGAN or waifu2x which upscales or denoises the text boxes.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ImageModelConfig {
+    pub model_path: String,
+    /// Output size relative to the input, 1 for denoising models
+    pub scale: u32,
+    /// Edge length of the tiles the image is split into, 0 runs the whole image at once
+    pub tile_size: u32,
+    /// Pixels shared by neighbouring tiles, hides the seams at the tile borders
+    pub tile_overlap: u32,
+    pub normalization: Normalization,
+    pub bgr: bool,
+}
+
+impl Default for ImageModelConfig {
+    fn default() -> Self {
+        Self {
+            model_path: String::new(),
+            scale: 2,
+            tile_size: 256,
+            tile_overlap: 16,
+            normalization: Normalization::ZeroToOne,
+            bgr: false,
+        }
+    }
+}
+
+impl ImageModelConfig {
+    fn get_scale(&self) -> u32 {
+        self.scale.max(1)
+    }
+}
+
+struct ImageModel {
+    session: Session,
+    /// 1 for models with a grayscale input
+    channels: usize,
+}
+
+impl ImageModel {
+    fn new(config: &ImageModelConfig) -> Result<Self> {
+        let session = create_session_builder(Backend::ImageModel)?
+            .commit_from_file(&config.model_path)
+            .with_context(|| format!("Failed to load image model {}", config.model_path))?;
+
+        let channels = session
+            .inputs
+            .first()
+            .and_then(|x| x.input_type.tensor_shape())
+            .and_then(|shape| shape.get(1).copied())
+            .filter(|channels| *channels == 1)
+            .map_or(3, |_| 1);
+
+        info!("Loaded image model {}", config.model_path);
+        Ok(Self { session, channels })
+    }
+
+    fn run(&mut self, image: &DynamicImage, config: &ImageModelConfig) -> Result<DynamicImage> {
+        let tensor = create_image_tensor(image, self.channels, config);
+        let input_name = self.session.inputs[0].name.clone();
+
+        let outputs = self
+            .session
+            .run(ort::inputs![input_name => TensorRef::from_array_view(&tensor)?])?;
+        let (_, output) = outputs.iter().next().context("Image model has no output")?;
+        let output = output.try_extract_array::<f32>()?;
+
+        // channel, height, width
+        let output = output.index_axis(Axis(0), 0).into_dimensionality::<Ix3>()?;
+        let (_, height, width) = output.dim();
+        let scale = config.get_scale();
+        let expected = (image.width() * scale, image.height() * scale);
+        if (width as u32, height as u32) != expected {
+            bail!(
+                "Image model output is {width}x{height}, expected {}x{} for the scale {scale}",
+                expected.0,
+                expected.1
+            );
+        }
+
+        Ok(create_image(&output, config))
+    }
+}
+
+/// Drops the loaded models so they are created again with the current inference config.
+pub fn unload_image_models() {
+    IMAGE_MODELS.unload();
+}
+
+/// Unloads the models which were not used for the timeout.
+pub fn unload_idle_image_models(timeout: Duration) {
+    IMAGE_MODELS.unload_if_idle(timeout);
+}
+
+pub fn run_image_model(image: &DynamicImage, config: &ImageModelConfig) -> Result<DynamicImage> {
+    // Checked before the model is loaded so a missing model does not retry the load every run
+    if config.model_path.trim().is_empty() {
+        bail!("No model path is set");
+    }
+    if !Path::new(&config.model_path).is_file() {
+        bail!("Model {} does not exist", config.model_path);
+    }
+
+    IMAGE_MODELS.with(
+        &config.model_path,
+        || ImageModel::new(config),
+        |model| run_tiled(image, config, |tile| model.run(tile, config)),
+    )?
+}
+
+/// Runs the model on overlapping tiles, the border of a tile is skipped where the previous tile
+/// already covers it.
+fn run_tiled(
+    image: &DynamicImage,
+    config: &ImageModelConfig,
+    mut run: impl FnMut(&DynamicImage) -> Result<DynamicImage>,
+) -> Result<DynamicImage> {
+    let tile_size = config.tile_size;
+    if tile_size == 0 || (image.width() <= tile_size && image.height() <= tile_size) {
+        return run(image);
+    }
+
+    let scale = config.get_scale();
+    let overlap = config.tile_overlap.min(tile_size / 2);
+    let mut output: Option<DynamicImage> = None;
+    for y in get_tile_starts(image.height(), tile_size, overlap) {
+        for x in get_tile_starts(image.width(), tile_size, overlap) {
+            let result = run(&image.crop_imm(x, y, tile_size, tile_size))?;
+            let output = output.get_or_insert_with(|| {
+                DynamicImage::new(
+                    image.width() * scale,
+                    image.height() * scale,
+                    result.color(),
+                )
+            });
+
+            let skip_x = if x == 0 { 0 } else { overlap / 2 } * scale;
+            let skip_y = if y == 0 { 0 } else { overlap / 2 } * scale;
+            let part = result.crop_imm(
+                skip_x,
+                skip_y,
+                result.width() - skip_x,
+                result.height() - skip_y,
+            );
+            output.copy_from(&part, x * scale + skip_x, y * scale + skip_y)?;
+        }
+    }
+    output.context("Image is empty")
+}
+
+/// Start of each tile along one axis, the last tile ends at the image border.
+fn get_tile_starts(length: u32, tile_size: u32, overlap: u32) -> Vec<u32> {
+    if length <= tile_size {
+        return vec![0];
+    }
+
+    let stride = tile_size.saturating_sub(overlap).max(1);
+    let mut starts: Vec<u32> = (0..length - tile_size).step_by(stride as usize).collect();
+    starts.push(length - tile_size);
+    starts
+}
+
+fn create_image_tensor(
+    image: &DynamicImage,
+    channels: usize,
+    config: &ImageModelConfig,
+) -> Array4<f32> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let mut tensor = Array4::<f32>::zeros((1, channels, height, width));
+
+    if channels == 1 {
+        for (x, y, pixel) in image.to_luma8().enumerate_pixels() {
+            tensor[[0, 0, y as usize, x as usize]] = config.normalization.apply(pixel[0], 0);
+        }
+        return tensor;
+    }
+
+    for (x, y, pixel) in image.to_rgb8().enumerate_pixels() {
+        for channel in 0..3 {
+            let source = if config.bgr { 2 - channel } else { channel };
+            tensor[[0, channel, y as usize, x as usize]] =
+                config.normalization.apply(pixel[source], source);
+        }
+    }
+    tensor
+}
+
+fn create_image(output: &ArrayView3<'_, f32>, config: &ImageModelConfig) -> DynamicImage {
+    let (channels, height, width) = output.dim();
+    let (width, height) = (width as u32, height as u32);
+
+    if channels < 3 {
+        let image = GrayImage::from_fn(width, height, |x, y| {
+            Luma([config
+                .normalization
+                .revert(output[[0, y as usize, x as usize]], 0)])
+        });
+        return image.into();
+    }
+
+    let image = RgbImage::from_fn(width, height, |x, y| {
+        let mut pixel = Rgb([0; 3]);
+        for channel in 0..3 {
+            let source = if config.bgr { 2 - channel } else { channel };
+            pixel[channel] = config
+                .normalization
+                .revert(output[[source, y as usize, x as usize]], channel);
+        }
+        pixel
+    });
+    image.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::imageops::FilterType;
+
+    #[test]
+    fn test_tile_starts() {
+        assert_eq!(get_tile_starts(100, 256, 16), vec![0]);
+        assert_eq!(get_tile_starts(10, 4, 1), vec![0, 3, 6]);
+        assert_eq!(get_tile_starts(9, 4, 0), vec![0, 4, 5]);
+    }
+
+    #[test]
+    fn test_run_tiled() {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(37, 21, |x, y| {
+            Rgb([(x * 6) as u8, (y * 12) as u8, ((x + y) * 4) as u8])
+        }));
+        let config = ImageModelConfig {
+            scale: 2,
+            tile_size: 8,
+            tile_overlap: 4,
+            ..Default::default()
+        };
+        let upscale = |tile: &DynamicImage| {
+            Ok(tile.resize_exact(tile.width() * 2, tile.height() * 2, FilterType::Nearest))
+        };
+
+        let mut tiles = 0;
+        let output = run_tiled(&image, &config, |tile| {
+            tiles += 1;
+            upscale(tile)
+        })
+        .unwrap();
+        assert!(tiles > 1);
+        assert_eq!(output, upscale(&image).unwrap());
+    }
+
+    #[test]
+    fn test_missing_model() {
+        let image = DynamicImage::new_rgb8(4, 4);
+        let err = run_image_model(&image, &ImageModelConfig::default()).unwrap_err();
+        assert_eq!(err.to_string(), "No model path is set");
+
+        let config = ImageModelConfig {
+            model_path: "missing.onnx".to_string(),
+            ..Default::default()
+        };
+        let err = run_image_model(&image, &config).unwrap_err();
+        assert_eq!(err.to_string(), "Model missing.onnx does not exist");
+    }
+
+    #[test]
+    fn test_normalization() {
+        let config = ImageModelConfig {
+            normalization: Normalization::ImageNet,
+            bgr: true,
+            ..Default::default()
+        };
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(3, 2, |x, y| {
+            Rgb([(x * 80) as u8, (y * 100) as u8, 200])
+        }));
+
+        let tensor = create_image_tensor(&image, 3, &config);
+        let output = create_image(&tensor.index_axis(Axis(0), 0), &config);
+        assert_eq!(output, image);
+    }
+}
@@ -0,0 +1,233 @@
+use image::{DynamicImage, GrayImage};
+use imageproc::contrast::otsu_level;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Gaps between ink runs smaller than this fraction of the line size are not line breaks.
+const MIN_GAP_FACTOR: f32 = 0.05;
+
+/// Estimated position of a character relative to the top left corner of its text box.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub struct CharRect {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl CharRect {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x as f32
+            && x < (self.x + self.w) as f32
+            && y >= self.y as f32
+            && y < (self.y + self.h) as f32
+    }
+}
+
+/// Estimates the positions of `char_count` characters in a text box.
+///
+/// Boxes higher than wide are read as vertical text with the lines from right to left,
+/// others as horizontal text from top to bottom. Lines are split with the ink projection profile
+/// and the characters are distributed over the lines by their length.
+pub fn estimate_char_rects(image: &DynamicImage, char_count: usize) -> Vec<CharRect> {
+    let (width, height) = (image.width(), image.height());
+    if char_count == 0 || width == 0 || height == 0 {
+        return vec![];
+    }
+
+    let ink = get_ink_mask(&image.to_luma8());
+    let vertical = height >= width;
+
+    // For vertical text the lines are columns
+    let (line_axis_size, reading_axis_size) = if vertical {
+        (width, height)
+    } else {
+        (height, width)
+    };
+    let is_ink = |line_pos: u32, reading_pos: u32| {
+        if vertical {
+            ink[(reading_pos * width + line_pos) as usize]
+        } else {
+            ink[(line_pos * width + reading_pos) as usize]
+        }
+    };
+
+    let profile: Vec<u32> = (0..line_axis_size)
+        .map(|line_pos| {
+            (0..reading_axis_size)
+                .filter(|reading_pos| is_ink(line_pos, *reading_pos))
+                .count() as u32
+        })
+        .collect();
+
+    let mut lines = split_lines(&profile);
+    if lines.is_empty() {
+        lines.push(0..line_axis_size);
+    }
+    if vertical {
+        lines.reverse();
+    }
+
+    let extents: Vec<Range<u32>> = lines
+        .iter()
+        .map(|line| {
+            let has_ink = |reading_pos: &u32| line.clone().any(|x| is_ink(x, *reading_pos));
+            let start = (0..reading_axis_size).find(has_ink).unwrap_or(0);
+            let end = (0..reading_axis_size)
+                .rev()
+                .find(has_ink)
+                .map_or(reading_axis_size, |x| x + 1);
+            start..end.max(start + 1)
+        })
+        .collect();
+
+    let capacities: Vec<f32> = lines
+        .iter()
+        .zip(&extents)
+        .map(|(line, extent)| extent.len() as f32 / line.len().max(1) as f32)
+        .collect();
+    let counts = distribute(char_count, &capacities);
+
+    lines
+        .iter()
+        .zip(&extents)
+        .zip(counts)
+        .flat_map(|((line, extent), count)| {
+            let step = extent.len() as f32 / count.max(1) as f32;
+            (0..count).map(move |index| {
+                let start = extent.start as f32 + index as f32 * step;
+                let (x, w, y, h) = (
+                    line.start as i32,
+                    line.len() as i32,
+                    start.round() as i32,
+                    step.round().max(1.0) as i32,
+                );
+                if vertical {
+                    CharRect { x, y, w, h }
+                } else {
+                    CharRect {
+                        x: y,
+                        y: x,
+                        w: h,
+                        h: w,
+                    }
+                }
+            })
+        })
+        .collect()
+}
+
+/// Pixels of the text, the darker side of the otsu threshold unless most of the box is dark.
+fn get_ink_mask(image: &GrayImage) -> Vec<bool> {
+    let level = otsu_level(image);
+    let dark: Vec<bool> = image.pixels().map(|p| p.0[0] <= level).collect();
+
+    let dark_count = dark.iter().filter(|x| **x).count();
+    if dark_count * 2 > dark.len() {
+        dark.iter().map(|x| !x).collect()
+    } else {
+        dark
+    }
+}
+
+/// Runs of positions with ink, small gaps inside a run are ignored.
+fn split_lines(profile: &[u32]) -> Vec<Range<u32>> {
+    let min_gap = ((profile.len() as f32 * MIN_GAP_FACTOR) as u32).max(1);
+
+    let mut lines: Vec<Range<u32>> = vec![];
+    for (pos, value) in profile.iter().enumerate() {
+        let pos = pos as u32;
+        if *value == 0 {
+            continue;
+        }
+        match lines.last_mut() {
+            Some(line) if pos - line.end < min_gap => line.end = pos + 1,
+            _ => lines.push(pos..pos + 1),
+        }
+    }
+    lines
+}
+
+/// Distributes the characters over the lines proportionally to their capacity (largest remainder).
+fn distribute(char_count: usize, capacities: &[f32]) -> Vec<usize> {
+    let total: f32 = capacities.iter().sum();
+    if capacities.is_empty() || total <= 0.0 {
+        return vec![char_count];
+    }
+
+    let shares: Vec<f32> = capacities
+        .iter()
+        .map(|x| char_count as f32 * x / total)
+        .collect();
+    let mut counts: Vec<usize> = shares.iter().map(|x| x.floor() as usize).collect();
+
+    let mut remainders: Vec<(usize, f32)> = shares
+        .iter()
+        .enumerate()
+        .map(|(index, share)| (index, share - share.floor()))
+        .collect();
+    remainders.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let missing = char_count - counts.iter().sum::<usize>();
+    for (index, _) in remainders.into_iter().cycle().take(missing) {
+        counts[index] += 1;
+    }
+
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    fn draw_block(image: &mut GrayImage, x: u32, y: u32, w: u32, h: u32) {
+        for px in x..x + w {
+            for py in y..y + h {
+                image.put_pixel(px, py, Luma([0]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_vertical_two_lines() {
+        let mut image = GrayImage::from_pixel(50, 100, Luma([255]));
+        // Right line with 4 characters, left line with 2 characters
+        for index in 0..4 {
+            draw_block(&mut image, 32, 5 + index * 20, 14, 14);
+        }
+        for index in 0..2 {
+            draw_block(&mut image, 6, 5 + index * 20, 14, 14);
+        }
+
+        let rects = estimate_char_rects(&DynamicImage::ImageLuma8(image), 6);
+
+        assert_eq!(rects.len(), 6);
+        assert!(rects[..4].iter().all(|x| x.x == 32));
+        assert!(rects[4..].iter().all(|x| x.x == 6));
+        assert!(rects[0].contains(38.0, 10.0));
+        assert!(rects[5].contains(12.0, 30.0));
+    }
+
+    #[test]
+    fn test_horizontal_line() {
+        let mut image = GrayImage::from_pixel(100, 20, Luma([255]));
+        for index in 0..5 {
+            draw_block(&mut image, index * 20, 3, 14, 14);
+        }
+
+        let rects = estimate_char_rects(&DynamicImage::ImageLuma8(image), 5);
+
+        assert_eq!(rects.len(), 5);
+        assert!(rects[0].contains(5.0, 10.0));
+        assert!(rects[4].contains(90.0, 10.0));
+        assert!(rects.windows(2).all(|x| x[0].x < x[1].x));
+    }
+
+    #[test]
+    fn test_distribute() {
+        assert_eq!(distribute(6, &[4.0, 2.0]), vec![4, 2]);
+        assert_eq!(distribute(5, &[1.0, 1.0]).iter().sum::<usize>(), 5);
+        assert_eq!(distribute(3, &[]), vec![3]);
+    }
+}
@@ -1,22 +1,35 @@
 use crate::detect::session_builder::create_session_builder;
-use hf_hub::api::sync::Api;
+use crate::model::lifecycle::ManagedModel;
+use crate::model::store::{self, MANGA_OCR_MODEL, ModelVariant, get_variant};
+use crate::ocr::decoder::{DecoderState, SplitModel};
+use crate::ui::backend_status::Backend;
 use image::DynamicImage;
 use itertools::Itertools;
-use ndarray::{Array3, Array4, ArrayBase, Axis, Dim, Ix, OwnedRepr, s, stack};
-use ort::value::TensorRef;
-use ort::{inputs, session::Session};
+use log::{info, warn};
+use ndarray::{Array3, Array4, Axis, stack};
+use ort::session::Session;
 use serde::{Deserialize, Serialize};
 use std::ops::{Div, Sub};
-use std::sync::{Arc, LazyLock, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 
-type MangaOcrState = Arc<Mutex<anyhow::Result<MangaOCR>>>;
+pub static MANGA_OCR: ManagedModel<MangaOCR> = ManagedModel::new("MangaOCR", load_manga_ocr);
 
-pub static MANGA_OCR: LazyLock<MangaOcrState> =
-    LazyLock::new(|| Arc::new(Mutex::new(MangaOCR::new())));
+/// The last loaded model has the split encoder and decoder.
+static SPLIT_LOADED: AtomicBool = AtomicBool::new(false);
+
+pub fn is_split_model_loaded() -> bool {
+    SPLIT_LOADED.load(Ordering::Relaxed)
+}
+
+fn load_manga_ocr() -> anyhow::Result<MangaOCR> {
+    MangaOCR::new(get_variant(&MANGA_OCR_MODEL))
+}
 
 #[derive(Debug)]
 pub struct MangaOCR {
     model: Session,
+    /// Faster encoder decoder split, `None` if the export is not available or failed to load
+    split: Option<SplitModel>,
     vocab: Vec<String>,
 }
 
@@ -50,6 +63,7 @@ impl KanjiConf {
 #[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq)]
 pub struct TokenConf {
     pub token_id: i64,
+    /// Softmax probability of the token
     pub confidence: f32,
 }
 
@@ -85,15 +99,71 @@ pub fn get_kanji_top_text(result: &KanjiTopResults, top: usize) -> Option<String
 }
 
 const MAX_TOP_KANJI: usize = 20;
+const MAX_TOKENS: usize = 300;
+const START_TOKEN: i64 = 2;
+const END_TOKEN: i64 = 3;
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Default)]
+pub enum Decoding {
+    #[default]
+    Greedy,
+    BeamSearch {
+        beam_width: usize,
+        /// Scores are divided by `length ^ length_penalty`, 0 disables the normalisation
+        length_penalty: f32,
+        n_best: usize,
+    },
+}
+
+impl Decoding {
+    pub fn default_beam_search() -> Self {
+        Decoding::BeamSearch {
+            beam_width: 5,
+            length_penalty: 1.0,
+            n_best: 5,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq)]
+pub struct Hypothesis {
+    pub text: String,
+    /// Length normalised log probability
+    pub score: f32,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq)]
+pub struct BeamResult {
+    /// Best hypothesis first
+    pub hypotheses: Vec<Hypothesis>,
+    /// Alternatives per character of the best hypothesis with the chosen character first
+    pub top: KanjiTopResults,
+}
+
+#[derive(Clone, Debug)]
+struct Beam {
+    image_index: usize,
+    /// Row in the last decoding step, the cache of this row is continued
+    row: usize,
+    token_ids: Vec<i64>,
+    log_prob: f32,
+    steps: Vec<Vec<TokenConf>>,
+}
+
+impl Beam {
+    fn get_score(&self, length_penalty: f32) -> f32 {
+        let length = self.steps.len().max(1) as f32;
+        self.log_prob / length.powf(length_penalty)
+    }
+}
 
 impl MangaOCR {
-    pub fn new() -> anyhow::Result<Self> {
-        let api = Api::new()?;
-        let repo = api.model("mayocream/koharu".to_string());
-        let model_path = repo.get("manga-ocr.onnx")?;
-        let vocab_path = repo.get("vocab.txt")?;
+    pub fn new(variant: ModelVariant) -> anyhow::Result<Self> {
+        let model_path =
+            store::get_file(&MANGA_OCR_MODEL, &variant.get_file_name("manga-ocr.onnx"))?;
+        let vocab_path = store::get_file(&MANGA_OCR_MODEL, "vocab.txt")?;
 
-        let builder = create_session_builder()?;
+        let builder = create_session_builder(Backend::MangaOcr)?;
 
         let model = builder.commit_from_file(model_path)?;
 
@@ -103,7 +173,20 @@ impl MangaOCR {
             .map(|s| s.to_string())
             .collect::<Vec<_>>();
 
-        Ok(Self { model, vocab })
+        let split = SplitModel::load(variant)
+            .inspect_err(|err| warn!("Failed to load the split MangaOCR model: {err:#}"))
+            .ok()
+            .flatten();
+        if split.is_none() {
+            info!("Split MangaOCR model not available, using the full model");
+        }
+        SPLIT_LOADED.store(split.is_some(), Ordering::Relaxed);
+
+        Ok(Self {
+            model,
+            split,
+            vocab,
+        })
     }
 
     pub fn inference(&mut self, images: Vec<&DynamicImage>) -> Vec<KanjiTopResults> {
@@ -114,11 +197,175 @@ impl MangaOCR {
         let batch_size = images.len();
         let tensor = Self::create_image_tensor(images);
 
-        let token_ids = self.get_token_ids(batch_size, tensor).unwrap_or_default();
+        let token_ids = self
+            .decode_with_fallback(tensor, |model, state| {
+                model.get_token_ids(batch_size, state)
+            })
+            .unwrap_or_default();
 
         self.decode_tokens(&token_ids)
     }
 
+    /// Decodes with the split model and falls back to the full model for this call if it fails,
+    /// the split model is kept for the next calls.
+    fn decode_with_fallback<T>(
+        &mut self,
+        tensor: Array4<f32>,
+        decode: impl Fn(&mut Self, DecoderState) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        if let Some(split) = self.split.as_mut() {
+            let result = DecoderState::cached(split, &tensor).and_then(|state| decode(self, state));
+            match result {
+                Ok(result) => return Ok(result),
+                Err(err) => {
+                    warn!("Split MangaOCR decoding failed, using the full model: {err:#}");
+                }
+            }
+        }
+
+        decode(self, DecoderState::full(tensor))
+    }
+
+    /// Beam search decoding, returns the `n_best` hypotheses for each image.
+    pub fn beam_search(
+        &mut self,
+        images: Vec<&DynamicImage>,
+        beam_width: usize,
+        length_penalty: f32,
+        n_best: usize,
+    ) -> anyhow::Result<Vec<BeamResult>> {
+        if images.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let tensor = Self::create_image_tensor(images);
+
+        self.decode_with_fallback(tensor, |model, state| {
+            model.run_beam_search(state, beam_width, length_penalty, n_best)
+        })
+    }
+
+    fn run_beam_search(
+        &mut self,
+        mut state: DecoderState,
+        beam_width: usize,
+        length_penalty: f32,
+        n_best: usize,
+    ) -> anyhow::Result<Vec<BeamResult>> {
+        let batch_size = state.get_batch_size();
+        let beam_width = beam_width.max(1);
+
+        let mut active: Vec<Beam> = (0..batch_size)
+            .map(|image_index| Beam {
+                image_index,
+                row: image_index,
+                token_ids: vec![START_TOKEN],
+                log_prob: 0.0,
+                steps: vec![],
+            })
+            .collect();
+        let mut finished: Vec<Vec<Beam>> = vec![vec![]; batch_size];
+
+        for run in 0..MAX_TOKENS {
+            if active.is_empty() {
+                break;
+            }
+
+            let image_indices: Vec<usize> = active.iter().map(|x| x.image_index).collect();
+            let parent_rows: Vec<usize> = active.iter().map(|x| x.row).collect();
+            let token_ids: Vec<Vec<i64>> = active.iter().map(|x| x.token_ids.clone()).collect();
+
+            let logits = state.step(
+                &mut self.model,
+                self.split.as_mut(),
+                &image_indices,
+                &parent_rows,
+                &token_ids,
+            )?;
+
+            let mut candidates: Vec<Vec<Beam>> = vec![vec![]; batch_size];
+            for (i, beam) in active.iter().enumerate() {
+                let top = get_top_tokens(logits.row(i).iter().copied());
+
+                // The first token is not part of the text, like in the greedy decoding
+                let expand_count = if run == 0 { 1 } else { beam_width };
+
+                for (rank, token) in top.iter().take(expand_count).enumerate() {
+                    let mut next = beam.clone();
+                    next.row = i;
+                    next.token_ids.push(token.token_id);
+                    next.log_prob += token.confidence.max(f32::MIN_POSITIVE).ln();
+
+                    if run > 0 && token.token_id != END_TOKEN {
+                        let mut step = top.clone();
+                        step.swap(0, rank);
+                        next.steps.push(step);
+                    }
+                    candidates[beam.image_index].push(next);
+                }
+            }
+
+            active = vec![];
+            for (image_index, mut image_candidates) in candidates.into_iter().enumerate() {
+                image_candidates.sort_by(|a, b| b.log_prob.total_cmp(&a.log_prob));
+
+                for beam in image_candidates.into_iter().take(beam_width) {
+                    if finished[image_index].len() >= beam_width {
+                        break;
+                    }
+                    if beam.token_ids.last() == Some(&END_TOKEN) {
+                        finished[image_index].push(beam);
+                    } else {
+                        active.push(beam);
+                    }
+                }
+            }
+        }
+
+        // Beams which reached the token limit
+        for beam in active {
+            finished[beam.image_index].push(beam);
+        }
+
+        let results = finished
+            .into_iter()
+            .map(|beams| {
+                let beams: Vec<Beam> = beams
+                    .into_iter()
+                    .sorted_by(|a, b| {
+                        b.get_score(length_penalty)
+                            .total_cmp(&a.get_score(length_penalty))
+                    })
+                    .take(n_best.max(1))
+                    .collect();
+
+                let mut top_results: Vec<KanjiTopResults> = beams
+                    .iter()
+                    .map(|x| self.get_kanji_top_results(&x.steps))
+                    .collect();
+
+                let hypotheses = beams
+                    .iter()
+                    .zip(&top_results)
+                    .map(|(beam, top)| Hypothesis {
+                        text: get_kanji_top_text(top, 0).unwrap_or_default(),
+                        score: beam.get_score(length_penalty),
+                    })
+                    .collect();
+
+                let top = if top_results.is_empty() {
+                    vec![]
+                } else {
+                    top_results.swap_remove(0)
+                };
+
+                BeamResult { hypotheses, top }
+            })
+            .collect();
+
+        Ok(results)
+    }
+
     fn decode_tokens(&self, token_ids: &TokenConfVec) -> Vec<KanjiTopResults> {
         token_ids
             .iter()
@@ -146,90 +393,54 @@ impl MangaOCR {
     fn get_token_ids(
         &mut self,
         batch_size: usize,
-        tensor: ArrayBase<OwnedRepr<f32>, Dim<[Ix; 4]>>,
+        mut state: DecoderState,
     ) -> anyhow::Result<TokenConfVec> {
-        let mut done_state: Vec<bool> = vec![false; batch_size];
-        let mut token_ids: Vec<Vec<i64>> = vec![vec![2i64]; batch_size]; // Start token
-
+        let mut token_ids: Vec<Vec<i64>> = vec![vec![START_TOKEN]; batch_size];
         let mut token_confs: TokenConfVec = vec![Vec::new(); batch_size];
 
-        'outer: for run in 0..300 {
-            let active_indices: Vec<usize> = done_state
-                .iter()
-                .enumerate()
-                .filter_map(|(i, &done)| (!done).then_some(i))
-                .collect();
+        let mut active_indices: Vec<usize> = (0..batch_size).collect();
+        let mut parent_rows: Vec<usize> = (0..batch_size).collect();
 
-            let tensor_slice = tensor.select(Axis(0), &active_indices);
+        for run in 0..MAX_TOKENS {
+            if active_indices.is_empty() {
+                break;
+            }
 
-            // Create input tensors
-            let input_token_ids: Vec<i64> = active_indices
+            let input: Vec<Vec<i64>> = active_indices
                 .iter()
-                .flat_map(|&i| token_ids[i].iter().cloned())
+                .map(|&i| token_ids[i].clone())
                 .collect();
 
-            let active_len = active_indices.len();
-            let input = ndarray::Array::from_shape_vec(
-                (active_len, token_ids[active_indices[0]].len()),
-                input_token_ids,
-            )
-            .expect("Input Shape is invalid");
-
-            let inputs = inputs! {
-                "image" => TensorRef::from_array_view(&tensor_slice)?,
-                "token_ids" => TensorRef::from_array_view(&input)?,
-            };
-
-            // Run inference
-            let outputs = self.model.run(inputs)?;
+            let logits = state.step(
+                &mut self.model,
+                self.split.as_mut(),
+                &active_indices,
+                &parent_rows,
+                &input,
+            )?;
 
-            // Extract logits from output
-            let logits = outputs["logits"].try_extract_array::<f32>()?;
+            let mut next_active_indices = vec![];
+            parent_rows = vec![];
 
-            // Get last token logits and find argmax
-            let logits_view = logits.view();
+            for (row, &original_i) in active_indices.iter().enumerate() {
+                let top_ten = get_top_tokens(logits.row(row).iter().copied());
+                let token_id = top_ten[0].token_id;
 
-            for i in 0..active_len {
-                let original_i = active_indices[i];
+                token_ids[original_i].push(token_id);
 
-                if done_state[original_i] {
-                    token_ids[original_i].push(3);
+                if token_id == END_TOKEN {
                     continue;
                 }
 
-                let last_token_logits = logits_view.slice(s![i, -1, ..]);
-
-                let top_ten: Vec<TokenConf> = last_token_logits
-                    .iter()
-                    .enumerate()
-                    .map(|(id, &conf)| TokenConf {
-                        token_id: id as i64,
-                        confidence: conf,
-                    })
-                    .sorted_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap())
-                    .take(MAX_TOP_KANJI)
-                    .collect();
-
-                let token_id = top_ten[0].token_id;
-
-                token_ids[original_i].push(token_id);
-
                 if run > 0 {
                     token_confs[original_i].push(top_ten);
                 }
 
-                // Break if end token
-                if token_id == 3 {
-                    done_state[original_i] = true;
-                }
+                next_active_indices.push(original_i);
+                parent_rows.push(row);
             }
 
-            if done_state.iter().all(|&x| x) {
-                token_confs.iter_mut().for_each(|x| {
-                    x.pop();
-                });
-                break 'outer;
-            }
+            active_indices = next_active_indices;
         }
         Ok(token_confs)
     }
@@ -272,6 +483,25 @@ impl MangaOCR {
     }
 }
 
+/// The most likely tokens with their softmax probability.
+fn get_top_tokens(logits: impl Iterator<Item = f32> + Clone) -> Vec<TokenConf> {
+    let log_sum_exp = log_sum_exp(logits.clone());
+    logits
+        .enumerate()
+        .map(|(id, logit)| TokenConf {
+            token_id: id as i64,
+            confidence: (logit - log_sum_exp).exp(),
+        })
+        .sorted_by(|a, b| b.confidence.total_cmp(&a.confidence))
+        .take(MAX_TOP_KANJI)
+        .collect()
+}
+
+fn log_sum_exp(values: impl Iterator<Item = f32> + Clone) -> f32 {
+    let max = values.clone().fold(f32::NEG_INFINITY, f32::max);
+    max + values.map(|x| (x - max).exp()).sum::<f32>().ln()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -284,10 +514,8 @@ mod tests {
         let original_img = image::open(input_path.as_path()).unwrap();
         let images = vec![&original_img];
 
-        let mut model = MANGA_OCR.lock().unwrap();
-        if let Ok(model) = model.as_mut() {
-            let result = model.inference(images);
-
+        let result = MANGA_OCR.with(|model| model.inference(images));
+        if let Ok(result) = result {
             for data in result.iter() {
                 for i in 0..10 {
                     let text = get_kanji_top_text_with_conf(data, i)
@@ -298,6 +526,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_get_top_tokens() {
+        let top = get_top_tokens([0.1, 3.0, -1.0, 2.0].into_iter());
+        assert_eq!(
+            top.iter().map(|x| x.token_id).collect_vec(),
+            vec![1, 3, 0, 2]
+        );
+
+        let sum: f32 = top.iter().map(|x| x.confidence).sum();
+        assert!((sum - 1.0).abs() < 1e-5);
+        assert!(top.iter().all(|x| (0.0..=1.0).contains(&x.confidence)));
+    }
+
+    #[test]
+    fn test_log_sum_exp() {
+        let values = [1.0f32, 2.0, 3.0];
+        let expected = values.iter().map(|x| x.exp()).sum::<f32>().ln();
+        assert!((log_sum_exp(values.into_iter()) - expected).abs() < 1e-5);
+
+        // Large logits must not overflow
+        assert!((log_sum_exp([1000.0f32, 1000.0].into_iter()) - (1000.0 + 2f32.ln())).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_beam_score_length_normalisation() {
+        let beam = |log_prob: f32, length: usize| Beam {
+            image_index: 0,
+            row: 0,
+            token_ids: vec![],
+            log_prob,
+            steps: vec![vec![]; length],
+        };
+
+        // Without normalisation the shorter hypothesis wins, with it the longer one
+        let short = beam(-2.0, 2);
+        let long = beam(-3.0, 6);
+        assert!(short.get_score(0.0) > long.get_score(0.0));
+        assert!(short.get_score(1.0) < long.get_score(1.0));
+        assert_eq!(beam(-2.0, 0).get_score(1.0), -2.0);
+    }
+
     pub fn get_kanji_top_text_with_conf(result: &KanjiTopResults, top: usize) -> Option<String> {
         let ocr = result
             .iter()
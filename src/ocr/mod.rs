@@ -1,10 +1,22 @@
-use crate::ocr::manga_ocr::{KanjiTopResults, MANGA_OCR};
-use anyhow::{Result, bail};
+use crate::ocr::ctc::{CtcConfig, run_ctc};
+use crate::ocr::ensemble::EnsembleResult;
+use crate::ocr::layout::CharRect;
+use crate::ocr::manga_ocr::{BeamResult, Decoding, KanjiTopResults, MANGA_OCR, get_kanji_top_text};
+use crate::ocr::tesseract::{OcrResult, TesseractConfig, run_tesseract};
+use anyhow::Result;
 use image::DynamicImage;
 use serde::{Deserialize, Serialize};
 use strum::{EnumIter, EnumString};
 
+pub mod correction;
+pub mod ctc;
+pub mod decoder;
+pub mod ensemble;
+pub mod image_model;
+pub mod layout;
 pub mod manga_ocr;
+pub mod preprocess;
+pub mod tesseract;
 
 #[derive(
     Debug, Clone, PartialEq, strum::Display, EnumString, EnumIter, Serialize, Deserialize, Default,
@@ -13,32 +25,171 @@ pub enum OcrBackend {
     #[strum(ascii_case_insensitive)]
     #[default]
     MangaOcr,
+    #[strum(ascii_case_insensitive)]
+    Tesseract(TesseractConfig),
+    #[strum(ascii_case_insensitive)]
+    Ctc(CtcConfig),
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Default)]
 pub enum BackendResult {
     MangaOcr(KanjiTopResults),
+    MangaOcrBeam(BeamResult),
+    Tesseract(OcrResult),
+    Ctc(KanjiTopResults),
+    Ensemble(EnsembleResult),
     #[default]
     Unknown,
 }
 
-impl OcrBackend {
-    pub fn run_backend(&self, images: Vec<&DynamicImage>) -> Result<Vec<BackendResult>> {
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Default, EnumIter)]
+pub enum LowConfidenceAction {
+    #[default]
+    Flag,
+    Drop,
+}
+
+/// Boxes with a confidence below the threshold are flagged or dropped,
+/// characters below the threshold are highlighted.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ConfidenceFilter {
+    pub threshold: f32,
+    pub action: LowConfidenceAction,
+}
+
+impl Default for ConfidenceFilter {
+    fn default() -> Self {
+        Self {
+            threshold: 0.5,
+            action: LowConfidenceAction::Flag,
+        }
+    }
+}
+
+impl BackendResult {
+    /// Alternatives for each character of the recognised text.
+    pub fn get_top_results(&self) -> Option<&KanjiTopResults> {
+        match self {
+            BackendResult::MangaOcr(top) | BackendResult::Ctc(top) => Some(top),
+            BackendResult::MangaOcrBeam(beam) => Some(&beam.top),
+            BackendResult::Ensemble(ensemble) => Some(&ensemble.top),
+            BackendResult::Tesseract(_) | BackendResult::Unknown => None,
+        }
+    }
+
+    /// Recognised character positions, `None` if the backend does not report them.
+    pub fn get_char_rects(&self) -> Option<Vec<CharRect>> {
+        match self {
+            BackendResult::Tesseract(result) => Some(
+                result
+                    .rects
+                    .iter()
+                    .flat_map(|x| std::iter::repeat_n(x.get_char_rect(), x.symbol.chars().count()))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Probability of each character of the recognised text, empty for Tesseract which only
+    /// reports word confidences.
+    pub fn get_char_confidences(&self) -> Vec<f32> {
+        if let BackendResult::Tesseract(_) = self {
+            return vec![];
+        }
+
+        self.get_top_results()
+            .into_iter()
+            .flatten()
+            .flat_map(|x| x.first())
+            .flat_map(|x| std::iter::repeat_n(x.confidence, x.kanji.chars().count()))
+            .collect()
+    }
+
+    /// Geometric mean of the character probabilities, the mean word confidence for Tesseract,
+    /// `None` without characters.
+    pub fn get_confidence(&self) -> Option<f32> {
+        match self {
+            BackendResult::Tesseract(result) if result.rects.is_empty() => None,
+            BackendResult::Tesseract(result) => Some(result.confidence),
+            _ => get_mean_confidence(&self.get_char_confidences()),
+        }
+    }
+
+    pub fn get_text(&self) -> Option<String> {
         match self {
-            OcrBackend::MangaOcr => run_manga_ocr(images),
+            BackendResult::MangaOcr(top) | BackendResult::Ctc(top) => get_kanji_top_text(top, 0),
+            BackendResult::MangaOcrBeam(beam) => beam.hypotheses.first().map(|x| x.text.clone()),
+            BackendResult::Tesseract(result) => Some(result.ocr.clone()),
+            BackendResult::Ensemble(ensemble) => ensemble.get_text(),
+            BackendResult::Unknown => None,
         }
     }
 }
 
-fn run_manga_ocr(images: Vec<&DynamicImage>) -> Result<Vec<BackendResult>> {
-    let mut model = MANGA_OCR.lock().expect("Manga OCR lock failed");
-    if let Ok(model) = model.as_mut() {
-        let result = model.inference(images);
-        let result = result.into_iter().map(BackendResult::MangaOcr).collect();
+/// Geometric mean of the probabilities, `None` if there are none.
+pub fn get_mean_confidence(confidences: &[f32]) -> Option<f32> {
+    if confidences.is_empty() {
+        return None;
+    }
+    let log_sum: f32 = confidences
+        .iter()
+        .map(|x| x.max(f32::MIN_POSITIVE).ln())
+        .sum();
+    Some((log_sum / confidences.len() as f32).exp())
+}
 
-        return Ok(result);
+impl OcrBackend {
+    pub fn run_backend(
+        &self,
+        images: Vec<&DynamicImage>,
+        decoding: &Decoding,
+    ) -> Result<Vec<BackendResult>> {
+        match self {
+            OcrBackend::MangaOcr => run_manga_ocr(images, decoding),
+            OcrBackend::Tesseract(config) => {
+                let result = run_tesseract(images, config)?;
+                Ok(result.into_iter().map(BackendResult::Tesseract).collect())
+            }
+            OcrBackend::Ctc(config) => {
+                let result = run_ctc(images, config)?;
+                Ok(result.into_iter().map(BackendResult::Ctc).collect())
+            }
+        }
     }
-    bail!("MangaOCR model not found")
+
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            OcrBackend::MangaOcr => "MangaOCR",
+            OcrBackend::Tesseract(_) => "Tesseract",
+            OcrBackend::Ctc(_) => "CTC",
+        }
+    }
+}
+
+fn run_manga_ocr(images: Vec<&DynamicImage>, decoding: &Decoding) -> Result<Vec<BackendResult>> {
+    MANGA_OCR.with(|model| {
+        let result = match decoding {
+            Decoding::Greedy => {
+                let result = model.inference(images);
+                result.into_iter().map(BackendResult::MangaOcr).collect()
+            }
+            Decoding::BeamSearch {
+                beam_width,
+                length_penalty,
+                n_best,
+            } => {
+                let result = model.beam_search(images, *beam_width, *length_penalty, *n_best)?;
+                result
+                    .into_iter()
+                    .map(BackendResult::MangaOcrBeam)
+                    .collect()
+            }
+        };
+
+        Ok(result)
+    })?
 }
 
 #[cfg(test)]
@@ -48,17 +199,51 @@ mod tests {
 
     use crate::OcrApp;
     use crate::action::{OcrPipeline, ResultData, run_ocr};
-    use crate::ocr::OcrBackend;
-    use crate::ocr::OcrBackend::MangaOcr;
+    use crate::ocr::OcrBackend::{MangaOcr, Tesseract};
+    use crate::ocr::manga_ocr::KanjiConf;
+    use crate::ocr::tesseract::{OcrRect, OcrResult, TesseractConfig};
+    use crate::ocr::{BackendResult, OcrBackend};
     use crate::ui::update_queue::update_state;
 
+    #[test]
+    fn backend_result_confidence() {
+        let kanji = |kanji: &str, confidence: f32| KanjiConf {
+            kanji: kanji.to_string(),
+            confidence,
+        };
+        let result = BackendResult::MangaOcr(vec![
+            vec![kanji("今", 0.9), kanji("令", 0.1)],
+            vec![kanji("日", 0.4)],
+        ]);
+
+        assert_eq!(result.get_char_confidences(), vec![0.9, 0.4]);
+        assert!((result.get_confidence().unwrap() - 0.6).abs() < 1e-5);
+        assert_eq!(BackendResult::Unknown.get_confidence(), None);
+
+        // Word confidences are not used for single characters
+        let result = BackendResult::Tesseract(OcrResult {
+            ocr: "今日".to_string(),
+            confidence: 0.7,
+            rects: vec![OcrRect {
+                symbol: "今日".to_string(),
+                confidence: 0.7,
+                ..Default::default()
+            }],
+        });
+        assert!(result.get_char_confidences().is_empty());
+        assert_eq!(result.get_confidence(), Some(0.7));
+    }
+
     #[test]
     fn ocr_backend_serialize() {
-        let backends: Vec<OcrBackend> = vec![MangaOcr];
+        let backends: Vec<OcrBackend> = vec![MangaOcr, Tesseract(TesseractConfig::default())];
 
         let json = serde_json::to_string(&backends).unwrap();
         info!("json: {}", json);
-        assert_eq!(json, r#"["MangaOcr"]"#);
+        assert_eq!(
+            json,
+            r#"["MangaOcr",{"Tesseract":{"language":"Jpn","psm":6}}]"#
+        );
 
         let result: Vec<OcrBackend> = serde_json::from_str(&json).unwrap();
         info!("parsed: {:?}", result);
@@ -131,7 +316,7 @@ mod tests {
 
     async fn run_test(expected: &[ResultData]) {
         let image = image::open("input/input.jpg").expect("Failed to open image");
-        run_ocr(image, OcrPipeline::default()).await;
+        run_ocr(image, OcrPipeline::default()).await.unwrap();
 
         let ctx = Context::default();
         let mut app = OcrApp::default();
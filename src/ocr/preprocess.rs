@@ -0,0 +1,606 @@
+use egui::Ui;
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage, Luma, Rgb, RgbImage};
+use imageproc::contrast::{ThresholdType, otsu_level, threshold};
+use imageproc::filter::{box_filter, median_filter, sharpen_gaussian};
+use imageproc::morphology::{Mask, grayscale_close, grayscale_open};
+use imageproc::stats::percentile;
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Default, EnumIter)]
+pub enum BinarizeMethod {
+    /// One threshold for the whole image
+    #[default]
+    Otsu,
+    /// Threshold from the mean of the surrounding block, for uneven backgrounds
+    Adaptive,
+}
+
+impl BinarizeMethod {
+    fn get_label(&self) -> &'static str {
+        match self {
+            BinarizeMethod::Otsu => "Otsu",
+            BinarizeMethod::Adaptive => "Adaptive",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Default, EnumIter)]
+pub enum MorphologyOperation {
+    /// Minimum followed by maximum, removes bright specks and fills gaps in dark strokes
+    #[default]
+    Open,
+    /// Maximum followed by minimum, removes dark specks, strokes thinner than the mask vanish too
+    Close,
+}
+
+impl MorphologyOperation {
+    fn get_label(&self) -> &'static str {
+        match self {
+            MorphologyOperation::Open => "Open",
+            MorphologyOperation::Close => "Close",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Default, EnumIter)]
+pub enum UpscaleFilter {
+    Nearest,
+    Triangle,
+    #[default]
+    CatmullRom,
+    Lanczos3,
+}
+
+impl UpscaleFilter {
+    fn get_label(&self) -> &'static str {
+        match self {
+            UpscaleFilter::Nearest => "Nearest",
+            UpscaleFilter::Triangle => "Linear",
+            UpscaleFilter::CatmullRom => "Cubic",
+            UpscaleFilter::Lanczos3 => "Lanczos",
+        }
+    }
+
+    fn get_filter_type(&self) -> FilterType {
+        match self {
+            UpscaleFilter::Nearest => FilterType::Nearest,
+            UpscaleFilter::Triangle => FilterType::Triangle,
+            UpscaleFilter::CatmullRom => FilterType::CatmullRom,
+            UpscaleFilter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+/// Image processing step of the pipeline. The image is converted to grayscale unless the step
+/// preserves the colour.
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
+pub enum PreprocessConfig {
+    SharpenGaussian {
+        sigma: f32,
+        amount: f32,
+        /// Missing in configs saved before the colour mode existed
+        #[serde(default)]
+        preserve_color: bool,
+    },
+    Binarize {
+        method: BinarizeMethod,
+        block_radius: u32,
+        /// A pixel is black if it is darker than the block mean minus the offset
+        offset: i32,
+    },
+    ContrastStretch {
+        /// Percentile of the pixels which become black
+        low_percentile: u8,
+        /// Percentile of the pixels which become white
+        high_percentile: u8,
+        preserve_color: bool,
+    },
+    Gamma {
+        /// Values below 1 brighten and values above 1 darken the image
+        gamma: f32,
+        preserve_color: bool,
+    },
+    Invert {
+        preserve_color: bool,
+    },
+    MedianDenoise {
+        radius: u32,
+        preserve_color: bool,
+    },
+    BilateralDenoise {
+        radius: u32,
+        sigma_color: f32,
+        sigma_spatial: f32,
+        preserve_color: bool,
+    },
+    Morphology {
+        operation: MorphologyOperation,
+        radius: u8,
+    },
+    Upscale {
+        factor: f32,
+        filter: UpscaleFilter,
+        preserve_color: bool,
+    },
+}
+
+impl Default for PreprocessConfig {
+    fn default() -> Self {
+        PreprocessConfig::SharpenGaussian {
+            sigma: 5.0,
+            amount: 10.0,
+            preserve_color: false,
+        }
+    }
+}
+
+impl PreprocessConfig {
+    /// One step of each kind with its default parameters.
+    pub fn get_defaults() -> Vec<PreprocessConfig> {
+        vec![
+            PreprocessConfig::default(),
+            PreprocessConfig::Binarize {
+                method: BinarizeMethod::Otsu,
+                block_radius: 15,
+                offset: 5,
+            },
+            PreprocessConfig::ContrastStretch {
+                low_percentile: 2,
+                high_percentile: 98,
+                preserve_color: false,
+            },
+            PreprocessConfig::Gamma {
+                gamma: 1.5,
+                preserve_color: false,
+            },
+            PreprocessConfig::Invert {
+                preserve_color: false,
+            },
+            PreprocessConfig::MedianDenoise {
+                radius: 1,
+                preserve_color: false,
+            },
+            PreprocessConfig::BilateralDenoise {
+                radius: 2,
+                sigma_color: 25.0,
+                sigma_spatial: 2.0,
+                preserve_color: false,
+            },
+            PreprocessConfig::Morphology {
+                operation: MorphologyOperation::Open,
+                radius: 1,
+            },
+            PreprocessConfig::Upscale {
+                factor: 2.0,
+                filter: UpscaleFilter::CatmullRom,
+                preserve_color: false,
+            },
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PreprocessConfig::SharpenGaussian { .. } => "Sharpen Gaussian",
+            PreprocessConfig::Binarize { .. } => "Binarize",
+            PreprocessConfig::ContrastStretch { .. } => "Contrast Stretch",
+            PreprocessConfig::Gamma { .. } => "Gamma",
+            PreprocessConfig::Invert { .. } => "Invert",
+            PreprocessConfig::MedianDenoise { .. } => "Median Denoise",
+            PreprocessConfig::BilateralDenoise { .. } => "Bilateral Denoise",
+            PreprocessConfig::Morphology { .. } => "Morphology",
+            PreprocessConfig::Upscale { .. } => "Upscale",
+        }
+    }
+
+    /// Size of the output relative to the input.
+    pub fn get_scale(&self) -> f32 {
+        match self {
+            PreprocessConfig::Upscale { factor, .. } => *factor,
+            _ => 1.0,
+        }
+    }
+
+    /// `None` for the steps which only work on grayscale images.
+    fn get_preserve_color(&mut self) -> Option<&mut bool> {
+        match self {
+            PreprocessConfig::SharpenGaussian { preserve_color, .. }
+            | PreprocessConfig::ContrastStretch { preserve_color, .. }
+            | PreprocessConfig::Gamma { preserve_color, .. }
+            | PreprocessConfig::Invert { preserve_color }
+            | PreprocessConfig::MedianDenoise { preserve_color, .. }
+            | PreprocessConfig::BilateralDenoise { preserve_color, .. }
+            | PreprocessConfig::Upscale { preserve_color, .. } => Some(preserve_color),
+            PreprocessConfig::Binarize { .. } | PreprocessConfig::Morphology { .. } => None,
+        }
+    }
+
+    fn preserves_color(&self) -> bool {
+        match self {
+            PreprocessConfig::SharpenGaussian { preserve_color, .. }
+            | PreprocessConfig::ContrastStretch { preserve_color, .. }
+            | PreprocessConfig::Gamma { preserve_color, .. }
+            | PreprocessConfig::Invert { preserve_color }
+            | PreprocessConfig::MedianDenoise { preserve_color, .. }
+            | PreprocessConfig::BilateralDenoise { preserve_color, .. }
+            | PreprocessConfig::Upscale { preserve_color, .. } => *preserve_color,
+            PreprocessConfig::Binarize { .. } | PreprocessConfig::Morphology { .. } => false,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut Ui) {
+        match self {
+            PreprocessConfig::SharpenGaussian { sigma, amount, .. } => {
+                ui.add(egui::Slider::new(sigma, 0.1..=100.0).text("Sigma"));
+                ui.add(egui::Slider::new(amount, -100.0..=100.0).text("Amount"));
+            }
+            PreprocessConfig::Binarize {
+                method,
+                block_radius,
+                offset,
+            } => {
+                ui.horizontal(|ui| {
+                    ui.label("Method:");
+                    for option in BinarizeMethod::iter() {
+                        ui.selectable_value(method, option, option.get_label());
+                    }
+                });
+                if *method == BinarizeMethod::Adaptive {
+                    ui.add(egui::Slider::new(block_radius, 1..=100).text("Block Radius"));
+                    ui.add(egui::Slider::new(offset, -50..=50).text("Offset"))
+                        .on_hover_text("Higher values remove more of the background noise");
+                }
+            }
+            PreprocessConfig::ContrastStretch {
+                low_percentile,
+                high_percentile,
+                ..
+            } => {
+                ui.add(egui::Slider::new(low_percentile, 0..=49).text("Black Percentile"));
+                ui.add(egui::Slider::new(high_percentile, 51..=100).text("White Percentile"));
+            }
+            PreprocessConfig::Gamma { gamma, .. } => {
+                ui.add(egui::Slider::new(gamma, 0.1..=5.0).text("Gamma"))
+                    .on_hover_text("Values above 1 darken the image and make thin strokes bolder");
+            }
+            PreprocessConfig::Invert { .. } => {
+                ui.label("White text on a dark background becomes black on white");
+            }
+            PreprocessConfig::MedianDenoise { radius, .. } => {
+                ui.add(egui::Slider::new(radius, 1..=10).text("Radius"));
+            }
+            PreprocessConfig::BilateralDenoise {
+                radius,
+                sigma_color,
+                sigma_spatial,
+                ..
+            } => {
+                ui.add(egui::Slider::new(radius, 1..=10).text("Radius"));
+                ui.add(egui::Slider::new(sigma_color, 1.0..=100.0).text("Sigma Color"));
+                ui.add(egui::Slider::new(sigma_spatial, 0.5..=10.0).text("Sigma Spatial"));
+            }
+            PreprocessConfig::Morphology { operation, radius } => {
+                ui.horizontal(|ui| {
+                    ui.label("Operation:");
+                    for option in MorphologyOperation::iter() {
+                        ui.selectable_value(operation, option, option.get_label());
+                    }
+                });
+                ui.add(egui::Slider::new(radius, 1..=10).text("Radius"));
+            }
+            PreprocessConfig::Upscale { factor, filter, .. } => {
+                ui.add(egui::Slider::new(factor, 1.0..=4.0).text("Factor"));
+                ui.horizontal(|ui| {
+                    ui.label("Filter:");
+                    for option in UpscaleFilter::iter() {
+                        ui.selectable_value(filter, option, option.get_label());
+                    }
+                });
+            }
+        }
+
+        if let Some(preserve_color) = self.get_preserve_color() {
+            ui.checkbox(preserve_color, "Preserve Color").on_hover_text(
+                "Filter each colour channel instead of converting the image to grayscale",
+            );
+        }
+    }
+
+    pub fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        if self.preserves_color() {
+            self.apply_rgb(&image.to_rgb8()).into()
+        } else {
+            self.apply_gray(&image.to_luma8()).into()
+        }
+    }
+
+    fn apply_gray(&self, image: &GrayImage) -> GrayImage {
+        match self {
+            PreprocessConfig::SharpenGaussian { sigma, amount, .. } => {
+                sharpen_gaussian(image, *sigma, *amount)
+            }
+            PreprocessConfig::Binarize {
+                method,
+                block_radius,
+                offset,
+            } => match method {
+                BinarizeMethod::Otsu => threshold(image, otsu_level(image), ThresholdType::Binary),
+                BinarizeMethod::Adaptive => adaptive_threshold(image, *block_radius, *offset),
+            },
+            PreprocessConfig::ContrastStretch { .. }
+            | PreprocessConfig::Gamma { .. }
+            | PreprocessConfig::Invert { .. } => {
+                let lut = self.get_lut(image);
+                let mut image = image.clone();
+                image.iter_mut().for_each(|x| *x = lut[*x as usize]);
+                image
+            }
+            PreprocessConfig::MedianDenoise { radius, .. } => {
+                median_filter(image, *radius, *radius)
+            }
+            PreprocessConfig::BilateralDenoise {
+                radius,
+                sigma_color,
+                sigma_spatial,
+                ..
+            } => bilateral_filter(image, *radius, *sigma_color, *sigma_spatial),
+            PreprocessConfig::Morphology { operation, radius } => match operation {
+                MorphologyOperation::Open => grayscale_open(image, &Mask::square(*radius)),
+                MorphologyOperation::Close => grayscale_close(image, &Mask::square(*radius)),
+            },
+            PreprocessConfig::Upscale { factor, filter, .. } => {
+                let (width, height) = get_scaled_size(image.width(), image.height(), *factor);
+                image::imageops::resize(image, width, height, filter.get_filter_type())
+            }
+        }
+    }
+
+    fn apply_rgb(&self, image: &RgbImage) -> RgbImage {
+        match self {
+            PreprocessConfig::ContrastStretch { .. }
+            | PreprocessConfig::Gamma { .. }
+            | PreprocessConfig::Invert { .. } => {
+                // The same curve for all channels keeps the hue
+                let lut = self.get_lut(&DynamicImage::ImageRgb8(image.clone()).to_luma8());
+                let mut image = image.clone();
+                image.iter_mut().for_each(|x| *x = lut[*x as usize]);
+                image
+            }
+            PreprocessConfig::MedianDenoise { radius, .. } => {
+                median_filter(image, *radius, *radius)
+            }
+            PreprocessConfig::Upscale { factor, filter, .. } => {
+                let (width, height) = get_scaled_size(image.width(), image.height(), *factor);
+                image::imageops::resize(image, width, height, filter.get_filter_type())
+            }
+            _ => map_channels(image, |x| self.apply_gray(x)),
+        }
+    }
+
+    /// Lookup table of the output value for each intensity.
+    fn get_lut(&self, luma: &GrayImage) -> [u8; 256] {
+        let mut lut = [0; 256];
+        for (value, output) in lut.iter_mut().enumerate() {
+            *output = value as u8;
+        }
+
+        match self {
+            PreprocessConfig::ContrastStretch {
+                low_percentile,
+                high_percentile,
+                ..
+            } => {
+                if luma.is_empty() {
+                    return lut;
+                }
+                let low = percentile(luma, *low_percentile) as f32;
+                let high = percentile(luma, *high_percentile) as f32;
+                if high <= low {
+                    return lut;
+                }
+                for (value, output) in lut.iter_mut().enumerate() {
+                    *output = ((value as f32 - low) / (high - low) * 255.0).clamp(0.0, 255.0) as u8;
+                }
+            }
+            PreprocessConfig::Gamma { gamma, .. } => {
+                for (value, output) in lut.iter_mut().enumerate() {
+                    *output = ((value as f32 / 255.0).powf(*gamma) * 255.0).round() as u8;
+                }
+            }
+            PreprocessConfig::Invert { .. } => {
+                for (value, output) in lut.iter_mut().enumerate() {
+                    *output = 255 - value as u8;
+                }
+            }
+            _ => {}
+        }
+        lut
+    }
+}
+
+pub fn get_scaled_size(width: u32, height: u32, factor: f32) -> (u32, u32) {
+    let scale = |x: u32| ((x as f32 * factor).round() as u32).max(1);
+    (scale(width), scale(height))
+}
+
+/// Applies the grayscale filter to each channel.
+fn map_channels(image: &RgbImage, filter: impl Fn(&GrayImage) -> GrayImage) -> RgbImage {
+    let channels: Vec<GrayImage> = (0..3)
+        .map(|channel| {
+            let plane = GrayImage::from_fn(image.width(), image.height(), |x, y| {
+                Luma([image.get_pixel(x, y)[channel]])
+            });
+            filter(&plane)
+        })
+        .collect();
+
+    let (width, height) = channels[0].dimensions();
+    RgbImage::from_fn(width, height, |x, y| {
+        Rgb([
+            channels[0].get_pixel(x, y)[0],
+            channels[1].get_pixel(x, y)[0],
+            channels[2].get_pixel(x, y)[0],
+        ])
+    })
+}
+
+/// Pixels darker than the mean of the surrounding block minus the offset become black.
+fn adaptive_threshold(image: &GrayImage, block_radius: u32, offset: i32) -> GrayImage {
+    let mean = box_filter(image, block_radius, block_radius);
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let value = image.get_pixel(x, y)[0] as i32;
+        let threshold = mean.get_pixel(x, y)[0] as i32 - offset;
+        Luma([if value < threshold { 0 } else { 255 }])
+    })
+}
+
+/// Smooths the image while keeping the edges, neighbours with a different intensity have less
+/// weight.
+fn bilateral_filter(
+    image: &GrayImage,
+    radius: u32,
+    sigma_color: f32,
+    sigma_spatial: f32,
+) -> GrayImage {
+    let radius = radius as i32;
+    let color_weights: Vec<f32> = (0..256)
+        .map(|x| (-((x * x) as f32) / (2.0 * sigma_color * sigma_color)).exp())
+        .collect();
+    let spatial_weight = |dx: i32, dy: i32| {
+        (-((dx * dx + dy * dy) as f32) / (2.0 * sigma_spatial * sigma_spatial)).exp()
+    };
+
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let center = image.get_pixel(x, y)[0] as i32;
+        let mut sum = 0.0;
+        let mut weights = 0.0;
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let nx = (x as i32 + dx).clamp(0, width - 1);
+                let ny = (y as i32 + dy).clamp(0, height - 1);
+                let value = image.get_pixel(nx as u32, ny as u32)[0] as i32;
+                let weight = spatial_weight(dx, dy)
+                    * color_weights[(value - center).unsigned_abs() as usize];
+                sum += weight * value as f32;
+                weights += weight;
+            }
+        }
+        Luma([(sum / weights).round() as u8])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_gradient() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(16, 8, |x, _| {
+            let value = (x * 16) as u8;
+            Rgb([value, value / 2, 255 - value])
+        }))
+    }
+
+    #[test]
+    fn test_all_steps() {
+        let image = get_gradient();
+        for mut config in PreprocessConfig::get_defaults() {
+            let output = config.apply(&image);
+            let scale = config.get_scale();
+            assert_eq!(
+                (output.width(), output.height()),
+                get_scaled_size(16, 8, scale),
+                "{}",
+                config.name()
+            );
+            assert!(output.as_luma8().is_some(), "{}", config.name());
+
+            if let Some(preserve_color) = config.get_preserve_color() {
+                *preserve_color = true;
+                assert!(
+                    config.apply(&image).as_rgb8().is_some(),
+                    "{}",
+                    config.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_binarize() {
+        let image = get_gradient();
+        for method in BinarizeMethod::iter() {
+            let config = PreprocessConfig::Binarize {
+                method,
+                block_radius: 3,
+                offset: 0,
+            };
+            let output = config.apply(&image).to_luma8();
+            assert!(output.iter().all(|x| *x == 0 || *x == 255));
+        }
+    }
+
+    #[test]
+    fn test_morphology() {
+        // Dark stroke with a one pixel dark speck next to it and a bright speck inside
+        let image = GrayImage::from_fn(15, 15, |x, y| match (x, y) {
+            (12, 12) => Luma([0]),
+            (6, 7) => Luma([255]),
+            (4..8, 2..13) => Luma([20]),
+            _ => Luma([230]),
+        });
+        let image = DynamicImage::ImageLuma8(image);
+
+        let close = PreprocessConfig::Morphology {
+            operation: MorphologyOperation::Close,
+            radius: 1,
+        };
+        let output = close.apply(&image).to_luma8();
+        assert_eq!(output.get_pixel(12, 12)[0], 230);
+        assert_eq!(output.get_pixel(5, 4)[0], 20);
+        assert_eq!(output.get_pixel(1, 1)[0], 230);
+
+        let open = PreprocessConfig::Morphology {
+            operation: MorphologyOperation::Open,
+            radius: 1,
+        };
+        let output = open.apply(&image).to_luma8();
+        assert_eq!(output.get_pixel(6, 7)[0], 20);
+        assert_eq!(output.get_pixel(5, 4)[0], 20);
+        assert_eq!(output.get_pixel(1, 1)[0], 230);
+    }
+
+    #[test]
+    fn test_lut() {
+        let image = GrayImage::from_fn(256, 1, |x, _| Luma([x as u8]));
+
+        let invert = PreprocessConfig::Invert {
+            preserve_color: false,
+        };
+        assert_eq!(invert.get_lut(&image)[0], 255);
+
+        let gamma = PreprocessConfig::Gamma {
+            gamma: 2.0,
+            preserve_color: false,
+        };
+        let lut = gamma.get_lut(&image);
+        assert_eq!((lut[0], lut[128], lut[255]), (0, 64, 255));
+
+        let stretch = PreprocessConfig::ContrastStretch {
+            low_percentile: 10,
+            high_percentile: 90,
+            preserve_color: false,
+        };
+        let lut = stretch.get_lut(&image);
+        assert_eq!(lut[10], 0);
+        assert_eq!(lut[240], 255);
+    }
+
+    #[test]
+    fn test_old_config() {
+        let config: PreprocessConfig =
+            serde_json::from_str(r#"{"SharpenGaussian":{"sigma":5.0,"amount":10.0}}"#).unwrap();
+        assert_eq!(config, PreprocessConfig::default());
+    }
+}
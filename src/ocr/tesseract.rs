@@ -0,0 +1,228 @@
+use crate::ocr::layout::CharRect;
+use anyhow::{Context, Result};
+use image::DynamicImage;
+use rusty_tesseract::{Args, Image};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use strum::EnumIter;
+
+/// Tesseract reports the word confidence in percent, -1 for non text elements. The `tsv` output
+/// has no symbol level, so the characters of a word share its box and confidence.
+const MAX_CONFIDENCE: f32 = 100.0;
+/// Level of the words in the `image_to_data` output.
+const WORD_LEVEL: i32 = 5;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Default, EnumIter)]
+pub enum TesseractLanguage {
+    #[default]
+    Jpn,
+    JpnVert,
+}
+
+impl TesseractLanguage {
+    pub fn get_code(&self) -> &'static str {
+        match self {
+            TesseractLanguage::Jpn => "jpn",
+            TesseractLanguage::JpnVert => "jpn_vert",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct TesseractConfig {
+    pub language: TesseractLanguage,
+    /// Page segmentation mode, 6 is a single block of text and 5 a vertical block
+    pub psm: i32,
+}
+
+impl Default for TesseractConfig {
+    fn default() -> Self {
+        Self {
+            language: TesseractLanguage::Jpn,
+            psm: 6,
+        }
+    }
+}
+
+impl TesseractConfig {
+    fn get_args(&self) -> Args {
+        Args {
+            lang: self.language.get_code().to_string(),
+            config_variables: HashMap::new(),
+            dpi: Some(150),
+            psm: Some(self.psm),
+            oem: Some(3),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Default)]
+pub struct OcrResult {
+    pub ocr: String,
+    /// Mean of the word confidences of the symbols from 0 to 1
+    pub confidence: f32,
+    pub rects: Vec<OcrRect>,
+}
+
+/// Recognised symbol with its position relative to the top left corner of the text box.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Default)]
+pub struct OcrRect {
+    pub symbol: String,
+    pub top: i32,
+    pub left: i32,
+    pub width: i32,
+    pub height: i32,
+    /// Confidence of the word of the symbol, Tesseract does not report symbol confidences
+    pub confidence: f32,
+}
+
+impl OcrRect {
+    pub fn get_char_rect(&self) -> CharRect {
+        CharRect {
+            x: self.left,
+            y: self.top,
+            w: self.width,
+            h: self.height,
+        }
+    }
+}
+
+/// Version of the installed Tesseract, fails if the executable is not found.
+pub fn get_tesseract_version() -> Result<String> {
+    rusty_tesseract::get_tesseract_version()
+        .map_err(|err| anyhow::anyhow!("Tesseract is not available: {err}"))
+}
+
+pub fn run_tesseract(
+    images: Vec<&DynamicImage>,
+    config: &TesseractConfig,
+) -> Result<Vec<OcrResult>> {
+    let args = config.get_args();
+
+    images
+        .into_iter()
+        .map(|image| recognize(image, &args))
+        .collect()
+}
+
+fn recognize(image: &DynamicImage, args: &Args) -> Result<OcrResult> {
+    let tesseract_image =
+        Image::from_dynamic_image(image).context("Failed to convert image for tesseract")?;
+
+    let data = rusty_tesseract::image_to_data(&tesseract_image, args)
+        .context("Tesseract failed, is it installed with the jpn and jpn_vert languages?")?;
+
+    let rects: Vec<OcrRect> = data
+        .data
+        .iter()
+        .filter(|x| x.level == WORD_LEVEL && x.conf >= 0.0 && !x.text.trim().is_empty())
+        .flat_map(|x| {
+            split_word(&OcrRect {
+                symbol: x.text.trim().to_string(),
+                top: x.top,
+                left: x.left,
+                width: x.width,
+                height: x.height,
+                confidence: x.conf / MAX_CONFIDENCE,
+            })
+        })
+        .collect();
+
+    Ok(create_result(rects))
+}
+
+/// Splits the box of the word evenly between its characters, from top to bottom for a word
+/// higher than wide.
+fn split_word(word: &OcrRect) -> Vec<OcrRect> {
+    let chars: Vec<char> = word.symbol.chars().collect();
+    let count = chars.len() as i32;
+    let vertical = word.height > word.width;
+    let length = if vertical { word.height } else { word.width };
+
+    chars
+        .iter()
+        .enumerate()
+        .map(|(index, symbol)| {
+            let start = index as i32 * length / count;
+            let end = (index as i32 + 1) * length / count;
+            let mut rect = OcrRect {
+                symbol: symbol.to_string(),
+                ..word.clone()
+            };
+            if vertical {
+                rect.top += start;
+                rect.height = end - start;
+            } else {
+                rect.left += start;
+                rect.width = end - start;
+            }
+            rect
+        })
+        .collect()
+}
+
+fn create_result(rects: Vec<OcrRect>) -> OcrResult {
+    let ocr = rects.iter().map(|x| x.symbol.as_str()).collect();
+    let confidence = if rects.is_empty() {
+        0.0
+    } else {
+        rects.iter().map(|x| x.confidence).sum::<f32>() / rects.len() as f32
+    };
+
+    OcrResult {
+        ocr,
+        confidence,
+        rects,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(symbol: &str, left: i32, top: i32, size: i32, confidence: f32) -> OcrRect {
+        OcrRect {
+            symbol: symbol.to_string(),
+            top,
+            left,
+            width: size,
+            height: size,
+            confidence,
+        }
+    }
+
+    #[test]
+    fn test_split_word() {
+        let horizontal = OcrRect {
+            width: 60,
+            ..rect("今日は", 10, 5, 20, 0.9)
+        };
+        assert_eq!(
+            split_word(&horizontal),
+            vec![
+                rect("今", 10, 5, 20, 0.9),
+                rect("日", 30, 5, 20, 0.9),
+                rect("は", 50, 5, 20, 0.9),
+            ]
+        );
+
+        let vertical = OcrRect {
+            height: 40,
+            ..rect("今日", 10, 5, 20, 0.5)
+        };
+        assert_eq!(
+            split_word(&vertical),
+            vec![rect("今", 10, 5, 20, 0.5), rect("日", 10, 25, 20, 0.5)]
+        );
+    }
+
+    #[test]
+    fn test_create_result() {
+        let result = create_result(vec![rect("今", 0, 0, 20, 0.8), rect("日", 20, 0, 20, 0.4)]);
+
+        assert_eq!(result.ocr, "今日");
+        assert!((result.confidence - 0.6).abs() < 1e-5);
+        assert_eq!(create_result(vec![]).confidence, 0.0);
+    }
+}
@@ -1,16 +1,31 @@
+use super::backend_status::{Backend, BackendStatus, load_dictionary};
 use super::background_rect::BackgroundRect;
+use super::breakdown_ui::BreakdownUi;
+use super::correction_ui::CorrectionUi;
+use super::dashboard_ui::DashboardUi;
+use super::dictionary_ui::DictionaryUi;
+use super::kanji_detail_ui::KanjiDetailUi;
 use super::kanji_history_ui::{HistoryDataUi, init_history_updater};
 use super::kanji_statistic_ui::{KanjiStatisticUi, init_kanji_statistic_updater};
-use super::settings::{AppSettings, Backend, BackendStatus, WindowState};
+use super::model_ui::ModelUi;
+use super::notification::Notifications;
+use super::settings::{AppSettings, WindowState};
 use crate::detect::comictextdetector::DETECT_STATE;
+use crate::detect::session_builder::set_inference_config;
 use crate::event::event::{update_backend_status, update_decorations};
+use crate::model::lifecycle::get_idle_timeout;
+use crate::ocr::ctc::{unload_ctc_models, unload_idle_ctc_models};
+use crate::ocr::image_model::{unload_idle_image_models, unload_image_models};
 use crate::ocr::manga_ocr::MANGA_OCR;
 use crate::ui::shutdown::{TASK_TRACKER, shutdown_tasks};
 use crate::ui::update_queue::update_state;
+use anyhow::Context as _;
 use eframe::epaint::Color32;
 use egui::Context;
-use futures::join;
-use std::sync::LazyLock;
+use std::collections::HashMap;
+use std::fs;
+use std::time::Duration;
+use tokio::time::sleep;
 
 #[derive(serde::Deserialize, serde::Serialize, Default)]
 #[serde(default)]
@@ -19,7 +34,15 @@ pub struct OcrApp {
     pub background_rect: BackgroundRect,
     pub kanji_statistic: KanjiStatisticUi,
     pub history: HistoryDataUi,
+    pub dictionary: DictionaryUi,
+    pub kanji_detail: KanjiDetailUi,
+    pub breakdown: BreakdownUi,
+    pub dashboard: DashboardUi,
+    pub corrections: CorrectionUi,
+    pub models: ModelUi,
     pub window_state: WindowState,
+    #[serde(skip)]
+    pub notifications: Notifications,
 }
 
 impl OcrApp {
@@ -39,13 +62,36 @@ impl OcrApp {
 
         init_history_updater();
         init_kanji_statistic_updater();
+        init_idle_unloader();
 
         ocr_app.init();
 
         ocr_app
     }
 
+    /// Settings saved by the overlay, for the commands which run without a window.
+    pub fn load_stored(app_name: &str) -> anyhow::Result<Self> {
+        let path = eframe::storage_dir(app_name)
+            .context("No data directory")?
+            .join("app.ron");
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let values: HashMap<String, String> = ron::from_str(&content)?;
+        let app = values
+            .get(eframe::APP_KEY)
+            .context("No settings are stored")?;
+        Ok(ron::from_str(app)?)
+    }
+
+    /// Applies the model store and inference settings, models loaded afterwards use them.
+    pub fn init_model_config(&self) {
+        self.models.init();
+        set_inference_config(self.settings.inference.clone());
+    }
+
     pub fn init(&mut self) {
+        self.init_model_config();
+        self.settings.init_idle_timeout();
         Self::init_backends();
         self.settings.shortcut.init();
     }
@@ -56,23 +102,41 @@ impl OcrApp {
         self.init()
     }
 
+    /// Loads the components independently, a failed one is shown with its error.
     fn init_backends() {
+        Self::load_backend(Backend::Detector, || DETECT_STATE.model.load());
+        Self::load_backend(Backend::MangaOcr, || MANGA_OCR.load());
+
+        update_backend_status(Backend::Dictionary, BackendStatus::Loading);
+        TASK_TRACKER.spawn(async move {
+            let result = load_dictionary().await;
+            update_backend_status(Backend::Dictionary, BackendStatus::from_result(&result));
+        });
+    }
+
+    /// Loads the models again, e.g. after they were imported.
+    pub fn reload_backends() {
+        Self::load_backend(Backend::Detector, || DETECT_STATE.model.reload());
+        Self::load_backend(Backend::MangaOcr, || MANGA_OCR.reload());
+    }
+
+    fn load_backend(backend: Backend, load: fn() -> anyhow::Result<()>) {
+        update_backend_status(backend.clone(), BackendStatus::Loading);
         TASK_TRACKER.spawn(async move {
-            let init1 = TASK_TRACKER.spawn(async { LazyLock::force(&MANGA_OCR) });
-            let init2 = TASK_TRACKER.spawn(async { LazyLock::force(&DETECT_STATE) });
-            let (result1, result2) = join!(init1, init2);
-
-            update_backend_status(
-                Backend::MangaOcr,
-                if result1.is_ok() && result2.is_ok() {
-                    BackendStatus::Ready
-                } else {
-                    BackendStatus::Error
-                },
-            );
+            update_backend_status(backend, BackendStatus::from_result(&load()));
         });
     }
 
+    /// Frees the memory of the models, they are loaded again on the next OCR.
+    pub fn unload_backends() {
+        MANGA_OCR.unload();
+        DETECT_STATE.model.unload();
+        unload_ctc_models();
+        unload_image_models();
+        update_backend_status(Backend::Detector, BackendStatus::Unloaded);
+        update_backend_status(Backend::MangaOcr, BackendStatus::Unloaded);
+    }
+
     fn show(&mut self, ctx: &Context) {
         if ctx.input(|i| i.viewport().close_requested()) {
             shutdown_tasks();
@@ -83,9 +147,35 @@ impl OcrApp {
 
         self.settings.show(ctx, &mut self.window_state);
 
-        self.kanji_statistic
-            .show(ctx, &mut self.window_state.show_statistics);
-        self.history.show(ctx, &mut self.window_state.show_history);
+        self.kanji_statistic.show(
+            ctx,
+            &mut self.window_state.show_statistics,
+            &self.settings.stroke_order,
+        );
+        self.history.show(
+            ctx,
+            &mut self.window_state.show_history,
+            &mut self.background_rect.session_difficulty,
+        );
+        self.dictionary
+            .show(ctx, &mut self.window_state.show_dictionary);
+        self.kanji_detail.show(
+            ctx,
+            &mut self.window_state.show_kanji_detail,
+            &self.settings.stroke_order,
+        );
+        self.dashboard
+            .show(ctx, &mut self.window_state.show_dashboard);
+        self.corrections
+            .show(ctx, &mut self.window_state.show_corrections);
+        self.models.show(ctx, &mut self.window_state.show_models);
+        self.breakdown.show(
+            ctx,
+            &mut self.window_state.show_breakdown,
+            &self.settings.furigana,
+        );
+
+        self.notifications.show(ctx);
 
         self.update_mouse_passthrough(ctx);
 
@@ -117,6 +207,29 @@ impl eframe::App for OcrApp {
     }
 }
 
+/// Unloads the models when no OCR ran for the idle timeout of the settings.
+fn init_idle_unloader() {
+    TASK_TRACKER.spawn(async move {
+        loop {
+            sleep(Duration::from_secs(10)).await;
+
+            let Some(timeout) = get_idle_timeout() else {
+                continue;
+            };
+            if DETECT_STATE.model.unload_if_idle(timeout) {
+                update_backend_status(Backend::Detector, BackendStatus::Unloaded);
+            }
+            if MANGA_OCR.unload_if_idle(timeout) {
+                update_backend_status(Backend::MangaOcr, BackendStatus::Unloaded);
+            }
+            if unload_idle_ctc_models(timeout) {
+                update_backend_status(Backend::Ctc, BackendStatus::Unloaded);
+            }
+            unload_idle_image_models(timeout);
+        }
+    });
+}
+
 fn init_font(ctx: &Context) {
     let mut fonts = egui::FontDefinitions::default();
 
@@ -0,0 +1,240 @@
+use crate::detect::comictextdetector::DETECT_STATE;
+use crate::detect::session_builder::get_active_provider;
+use crate::event::event::update_backend_status;
+use crate::jpn::dict;
+use crate::ocr::OcrBackend;
+use crate::ocr::ctc::unload_ctc_models;
+use crate::ocr::image_model::unload_image_models;
+use crate::ocr::manga_ocr::{MANGA_OCR, is_split_model_loaded};
+use crate::ocr::tesseract::get_tesseract_version;
+use crate::translation::google::translate;
+use crate::ui::shutdown::TASK_TRACKER;
+use anyhow::{Result, anyhow};
+use egui::{Color32, Id, RichText, Spinner};
+use strum::{EnumIter, IntoEnumIterator};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BackendStatus {
+    Loading,
+    Ready,
+    Running,
+    /// Freed after being idle, loaded again on the next OCR
+    Unloaded,
+    Error(String),
+}
+
+impl BackendStatus {
+    pub fn from_result<T>(result: &Result<T>) -> Self {
+        match result {
+            Ok(_) => BackendStatus::Ready,
+            Err(err) => BackendStatus::Error(format!("{err:#}")),
+        }
+    }
+
+    fn get_ui(&self, ui: &mut egui::Ui) {
+        match self {
+            BackendStatus::Loading => ui.add(Spinner::new()),
+            BackendStatus::Ready | BackendStatus::Running => {
+                ui.label(RichText::from("\u{2714}").color(Color32::GREEN))
+            }
+            BackendStatus::Unloaded => ui
+                .label(RichText::from("\u{23F8}").color(Color32::GRAY))
+                .on_hover_text("Unloaded, loaded again on the next OCR"),
+            BackendStatus::Error(err) => ui
+                .label(RichText::from("\u{2716}").color(Color32::RED))
+                .on_hover_text(err),
+        };
+    }
+}
+
+/// Component with its own status, a failed component does not stop the others.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, EnumIter)]
+pub enum Backend {
+    Detector,
+    MangaOcr,
+    Tesseract,
+    Ctc,
+    ImageModel,
+    Dictionary,
+    Translator,
+}
+
+impl From<&OcrBackend> for Backend {
+    fn from(backend: &OcrBackend) -> Self {
+        match backend {
+            OcrBackend::MangaOcr => Backend::MangaOcr,
+            OcrBackend::Tesseract(_) => Backend::Tesseract,
+            OcrBackend::Ctc(_) => Backend::Ctc,
+        }
+    }
+}
+
+impl Backend {
+    fn get_id(self: &Backend) -> Id {
+        match self {
+            Backend::Detector => Id::new("Detector_Status"),
+            Backend::MangaOcr => Id::new("MangaOcr_Status"),
+            Backend::Tesseract => Id::new("Tesseract_Status"),
+            Backend::Ctc => Id::new("Ctc_Status"),
+            Backend::ImageModel => Id::new("ImageModel_Status"),
+            Backend::Dictionary => Id::new("Dictionary_Status"),
+            Backend::Translator => Id::new("Translator_Status"),
+        }
+    }
+
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            Backend::Detector => "Detector",
+            Backend::MangaOcr => "MangaOCR",
+            Backend::Tesseract => "Tesseract",
+            Backend::Ctc => "CTC",
+            Backend::ImageModel => "Image Model",
+            Backend::Dictionary => "Dictionary",
+            Backend::Translator => "Translator",
+        }
+    }
+
+    fn uses_onnx(&self) -> bool {
+        matches!(
+            self,
+            Backend::Detector | Backend::MangaOcr | Backend::Ctc | Backend::ImageModel
+        )
+    }
+
+    /// Status of the components which were loaded or used, with a retry button for the failed
+    /// ones.
+    pub fn show_statuses(ui: &mut egui::Ui) {
+        let statuses: Vec<(Backend, BackendStatus)> = Backend::iter()
+            .filter_map(|x| {
+                let status = x.get_status(ui.ctx())?;
+                Some((x, status))
+            })
+            .collect();
+        if statuses.is_empty() {
+            return;
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            for (backend, status) in statuses {
+                ui.label(backend.get_label());
+                status.get_ui(ui);
+                backend.show_provider(ui, &status);
+
+                if let BackendStatus::Error(err) = &status
+                    && ui
+                        .small_button("\u{27F3}")
+                        .on_hover_text(format!("Retry\n{err}"))
+                        .clicked()
+                {
+                    backend.retry();
+                }
+                ui.add_space(8.0);
+            }
+        });
+    }
+
+    /// Execution provider of the ONNX backends once they are loaded.
+    fn show_provider(&self, ui: &mut egui::Ui, status: &BackendStatus) {
+        if !self.uses_onnx() || !matches!(status, BackendStatus::Ready | BackendStatus::Running) {
+            return;
+        }
+        if let Some(provider) = get_active_provider(self) {
+            ui.label(RichText::new(provider.get_label()).weak())
+                .on_hover_text("Active Execution Provider");
+        }
+        if *self == Backend::MangaOcr {
+            let (label, hover) = if is_split_model_loaded() {
+                (
+                    "split",
+                    "The image is encoded once and the decoder reuses its cache",
+                )
+            } else {
+                (
+                    "full",
+                    "The split encoder and decoder are not in the models directory, the full model is used",
+                )
+            };
+            ui.label(RichText::new(label).weak()).on_hover_text(hover);
+        }
+    }
+
+    /// Status of the component, None if it was not loaded or used yet.
+    pub fn get_status(&self, ctx: &egui::Context) -> Option<BackendStatus> {
+        ctx.data(|data| data.get_temp(self.get_id()))
+    }
+
+    pub fn set_status(&self, ctx: &egui::Context, status: BackendStatus) {
+        ctx.data_mut(|data| data.insert_temp(self.get_id(), status));
+    }
+
+    /// Loads or checks the component again and updates its status.
+    pub fn retry(self) {
+        TASK_TRACKER.spawn(async move {
+            update_backend_status(self.clone(), BackendStatus::Loading);
+            let status = self.check().await;
+            update_backend_status(self, status);
+        });
+    }
+
+    async fn check(&self) -> BackendStatus {
+        match self {
+            Backend::Detector => BackendStatus::from_result(&DETECT_STATE.model.reload()),
+            Backend::MangaOcr => BackendStatus::from_result(&MANGA_OCR.reload()),
+            Backend::Tesseract => BackendStatus::from_result(&get_tesseract_version()),
+            Backend::Ctc => {
+                // The model of the step is loaded with its config on the next run
+                unload_ctc_models();
+                BackendStatus::Unloaded
+            }
+            Backend::ImageModel => {
+                unload_image_models();
+                BackendStatus::Unloaded
+            }
+            Backend::Dictionary => BackendStatus::from_result(&load_dictionary().await),
+            Backend::Translator => BackendStatus::from_result(&translate("テスト").await),
+        }
+    }
+}
+
+/// Builds the dictionary index, a panic while building it is reported as an error.
+pub async fn load_dictionary() -> Result<()> {
+    TASK_TRACKER
+        .spawn(async { dict::init_dictionary() })
+        .await
+        .map_err(|err| anyhow!("Failed to load the dictionary: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ui::backend_status::{Backend, BackendStatus};
+    use egui::Context;
+
+    #[tokio::test]
+    async fn test_retry_after_error() {
+        let ctx = Context::default();
+        Backend::ImageModel.set_status(&ctx, BackendStatus::Error("Failed".to_string()));
+        assert_eq!(
+            Backend::ImageModel.get_status(&ctx),
+            Some(BackendStatus::Error("Failed".to_string()))
+        );
+        // The error of a component does not change the others
+        assert_eq!(Backend::Detector.get_status(&ctx), None);
+
+        // The retry replaces the error with the new status
+        let status = Backend::ImageModel.check().await;
+        Backend::ImageModel.set_status(&ctx, status);
+        assert_eq!(
+            Backend::ImageModel.get_status(&ctx),
+            Some(BackendStatus::Unloaded)
+        );
+
+        // A component can fail again and be retried again
+        Backend::ImageModel.set_status(&ctx, BackendStatus::Error("Failed".to_string()));
+        let status = Backend::ImageModel.check().await;
+        Backend::ImageModel.set_status(&ctx, status);
+        assert_eq!(
+            Backend::ImageModel.get_status(&ctx),
+            Some(BackendStatus::Unloaded)
+        );
+    }
+}
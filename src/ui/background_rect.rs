@@ -1,12 +1,17 @@
 use super::{mouse_hover::get_frame_rect, screenshot_result_ui::scale_rect, settings::AppSettings};
 use crate::action::{OcrPipeline, ScreenshotParameter, ScreenshotResult, run_ocr};
-use crate::event::event::{is_minimized, reset_ocr_start_time, update_mouse_passthrough};
+use crate::error::OcrError;
+use crate::event::event::{
+    is_minimized, reset_ocr_start_time, show_notification, update_mouse_passthrough,
+};
+use crate::jpn::difficulty::SessionDifficulty;
+use crate::ui::notification::NotificationLevel;
 use crate::ui::screenshot_result_ui::get_clicked_result_id;
 use crate::ui::shutdown::TASK_TRACKER;
 use eframe::epaint::StrokeKind;
-use egui::{Color32, Context, Id, Pos2, Rect, Sense, Vec2};
+use egui::{Align2, Color32, Context, FontId, Id, Pos2, Rect, Sense, Vec2};
 use image::DynamicImage;
-use log::warn;
+use log::error;
 use std::time::Duration;
 use tokio::time::Instant;
 
@@ -24,6 +29,8 @@ pub struct BackgroundRect {
     pub start_ocr_at: Option<Instant>,
     #[serde(skip)]
     last_ocr_rect_hover_at: Option<Instant>,
+    #[serde(skip)]
+    pub session_difficulty: SessionDifficulty,
 }
 
 pub fn start_ocr_id() -> Id {
@@ -43,12 +50,7 @@ impl BackgroundRect {
     pub fn show(&mut self, ctx: &Context, settings: &AppSettings) {
         self.check_start_ocr(ctx, settings);
 
-        let bg_response = self.draw_background(
-            ctx,
-            settings.mouse_passthrough,
-            settings.clear_color,
-            settings.quick_area_pick_mode,
-        );
+        let bg_response = self.draw_background(ctx, settings);
 
         if !settings.mouse_passthrough
             && self.update_drag(settings, &bg_response.response, ctx.zoom_factor())
@@ -163,9 +165,12 @@ impl BackgroundRect {
             pipeline: OcrPipeline(settings.get_current_pipeline().items.clone()),
         };
 
-        let Ok(image) = screenshot_parameter.get_screenshot() else {
-            warn!("screenshot_parameter get screenshot failed");
-            return;
+        let image = match screenshot_parameter.get_screenshot() {
+            Ok(image) => image,
+            Err(err) => {
+                report_ocr_error(&screenshot_parameter, &OcrError::Screenshot(err));
+                return;
+            }
         };
 
         if are_inputs_unchanged(&ctx, screenshot_parameter.clone(), image.clone()) {
@@ -176,7 +181,14 @@ impl BackgroundRect {
 
         let auto_restart = settings.auto_restart_ocr;
         TASK_TRACKER.spawn(async move {
-            run_ocr(image, screenshot_parameter.pipeline).await;
+            // A panic in the run is reported instead of ending the task silently
+            let result = TASK_TRACKER
+                .spawn(run_ocr(image, screenshot_parameter.pipeline.clone()))
+                .await
+                .unwrap_or_else(|err| Err(OcrError::Task(err.to_string())));
+            if let Err(err) = result {
+                report_ocr_error(&screenshot_parameter, &err);
+            }
 
             if auto_restart {
                 reset_ocr_start_time();
@@ -187,16 +199,20 @@ impl BackgroundRect {
     fn draw_background(
         &mut self,
         ctx: &Context,
-        mouse_passthrough: bool,
-        clear_color: Color32,
-        quick_area_pick_mode: bool,
+        settings: &AppSettings,
     ) -> egui::InnerResponse<()> {
+        let mouse_passthrough = settings.mouse_passthrough;
+        let clear_color = settings.clear_color;
+        let quick_area_pick_mode = settings.quick_area_pick_mode;
+        let show_difficulty = settings.show_difficulty;
         let frame_rect = get_frame_rect(ctx);
         let rect = self.get_unscaled_rect();
 
         let rect = scale_rect(rect, 1.0 / ctx.zoom_factor());
 
-        if !quick_area_pick_mode && !self.hide_ocr_rects && self.screenshot_result.show(ctx, &rect)
+        if !quick_area_pick_mode
+            && !self.hide_ocr_rects
+            && self.screenshot_result.show(ctx, &rect, settings)
         {
             self.last_ocr_rect_hover_at = Some(Instant::now());
         }
@@ -239,8 +255,34 @@ impl BackgroundRect {
                     (1.0, Color32::RED),
                     StrokeKind::Middle,
                 );
+
+                if show_difficulty && !self.hide_ocr_rects {
+                    self.draw_difficulty(ui, rect);
+                }
             })
     }
+
+    fn draw_difficulty(&self, ui: &egui::Ui, rect: Rect) {
+        let Some(difficulty) = self.screenshot_result.difficulty else {
+            return;
+        };
+
+        let mut text = format!("Difficulty: {difficulty:.0}");
+        if let Some(average) = self.session_difficulty.get_average() {
+            text += &format!(
+                " | Session: {average:.0} ({} pages)",
+                self.session_difficulty.page_count
+            );
+        }
+
+        ui.painter().text(
+            rect.left_bottom() + Vec2::new(0.0, 2.0),
+            Align2::LEFT_TOP,
+            text,
+            FontId::proportional(14.0),
+            Color32::RED,
+        );
+    }
 }
 
 fn are_inputs_unchanged(
@@ -272,3 +314,16 @@ fn are_inputs_unchanged(
 
     unchanged
 }
+
+/// Logs the error with the captured area and shows it in the overlay.
+fn report_ocr_error(parameter: &ScreenshotParameter, error: &OcrError) {
+    error!(
+        "OCR of {}x{} at ({}, {}) with {} pipeline steps failed: {error}",
+        parameter.width,
+        parameter.height,
+        parameter.x,
+        parameter.y,
+        parameter.pipeline.0.len()
+    );
+    show_notification(NotificationLevel::Error, error.to_string());
+}
@@ -0,0 +1,211 @@
+use std::collections::HashSet;
+
+use egui::{Color32, Grid, RichText, ScrollArea, Ui};
+
+use super::screenshot_result_ui::{get_info_text, show_jpn_data_info};
+use crate::action::{self, ResultData};
+use crate::event::event::update_breakdown_known_words;
+use crate::jpn::JpnData;
+use crate::ui::furigana::FuriganaConfig;
+use crate::ui::shutdown::TASK_TRACKER;
+use crate::ui::update_queue::enqueue_update;
+
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[serde(default)]
+pub struct BreakdownUi {
+    #[serde(skip)]
+    pub result: Option<ResultData>,
+    #[serde(skip)]
+    expanded: HashSet<usize>,
+    #[serde(skip)]
+    known_words: HashSet<String>,
+}
+
+impl BreakdownUi {
+    pub fn set_result(&mut self, result: ResultData) {
+        self.result = Some(result);
+        self.expanded.clear();
+
+        TASK_TRACKER.spawn(async move {
+            let known_words = action::load_known_words();
+            enqueue_update(move |_, app| update_breakdown_known_words(app, known_words));
+        });
+    }
+
+    pub fn set_known_words(&mut self, known_words: HashSet<String>) {
+        self.known_words = known_words;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool, furigana: &FuriganaConfig) {
+        egui::Window::new("Sentence Breakdown")
+            .open(open)
+            .show(ctx, |ui| {
+                let Some(result) = self.result.take() else {
+                    ui.label("No box selected");
+                    return;
+                };
+
+                ui.label(get_info_text(&result.ocr));
+                ui.separator();
+
+                ScrollArea::vertical().show(ui, |ui| {
+                    self.show_table(ui, &get_words(&result), furigana);
+                });
+
+                self.result = Some(result);
+            });
+    }
+
+    fn show_table(&mut self, ui: &mut Ui, words: &[&JpnData], furigana: &FuriganaConfig) {
+        Grid::new("Sentence Breakdown Table")
+            .striped(true)
+            .show(ui, |ui| {
+                for header in [
+                    "Word",
+                    "Dictionary Form",
+                    "Reading",
+                    "Part of Speech",
+                    "Meaning",
+                    "JLPT",
+                    "Known",
+                ] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+
+                for (index, jpn_data) in words.iter().enumerate() {
+                    self.show_row(ui, index, jpn_data, furigana);
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn show_row(
+        &mut self,
+        ui: &mut Ui,
+        index: usize,
+        jpn_data: &JpnData,
+        furigana: &FuriganaConfig,
+    ) {
+        let is_expanded = self.expanded.contains(&index);
+        let entry = jpn_data.get_best_entry();
+
+        let response = furigana.show_word(ui, jpn_data, |text| {
+            if is_expanded {
+                text.color(Color32::RED)
+            } else {
+                text.underline()
+            }
+        });
+        if response.clicked() {
+            if is_expanded {
+                self.expanded.remove(&index);
+            } else {
+                self.expanded.insert(index);
+            }
+        }
+
+        let dictionary_form = jpn_data.get_dictionary_form();
+        ui.label(&dictionary_form);
+        ui.label(get_reading(jpn_data));
+        ui.label(
+            entry
+                .map(|x| x.get_parts_of_speech().join(", "))
+                .unwrap_or_default(),
+        );
+
+        ui.vertical(|ui| {
+            if is_expanded {
+                show_jpn_data_info(ui, jpn_data);
+            } else {
+                ui.label(
+                    entry
+                        .and_then(|x| x.get_top_gloss())
+                        .cloned()
+                        .unwrap_or_default(),
+                );
+            }
+        });
+
+        ui.label(
+            jpn_data
+                .get_jlpt_level()
+                .map(|x| format!("N{x}"))
+                .unwrap_or_default(),
+        );
+
+        let mut known = self.known_words.contains(&dictionary_form);
+        let text = if known {
+            RichText::new("known").color(Color32::GREEN)
+        } else {
+            RichText::new("unknown").color(Color32::GRAY)
+        };
+        if ui.checkbox(&mut known, text).changed() {
+            if known {
+                self.known_words.insert(dictionary_form.clone());
+            } else {
+                self.known_words.remove(&dictionary_form);
+            }
+            TASK_TRACKER.spawn(async move { action::set_word_known(&dictionary_form, known) });
+        }
+    }
+}
+
+/// Words of the text with dictionary or kanji data, in the order of the text.
+fn get_words(result: &ResultData) -> Vec<&JpnData> {
+    result
+        .jpn
+        .iter()
+        .flatten()
+        .filter(|x| x.has_kanji_data())
+        .collect()
+}
+
+/// Reading of the word, kana words show the reading of their best entry.
+fn get_reading(jpn_data: &JpnData) -> String {
+    jpn_data
+        .get_reading()
+        .or_else(|| {
+            jpn_data
+                .get_best_entry()
+                .and_then(|x| x.readings.first().cloned())
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OcrApp;
+    use crate::event::event::open_breakdown;
+    use crate::jpn::get_jpn_data;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_breakdown() {
+        let ocr = "猫\n学生\n食べる";
+        let result = ResultData {
+            ocr: ocr.to_string(),
+            jpn: get_jpn_data(ocr).await,
+            ..Default::default()
+        };
+
+        let mut app = OcrApp::default();
+        open_breakdown(&mut app, result);
+        assert!(app.window_state.show_breakdown);
+
+        let result = app.breakdown.result.as_ref().unwrap();
+        let rows: Vec<(String, String, String)> = get_words(result)
+            .into_iter()
+            .map(|x| (x.get_kanji(), x.get_dictionary_form(), get_reading(x)))
+            .collect();
+        let expected = [
+            ("猫", "猫", "ねこ"),
+            ("学生", "学生", "がくせい"),
+            ("食べる", "食べる", "たべる"),
+        ];
+        assert_eq!(
+            rows,
+            expected.map(|(a, b, c)| (a.to_string(), b.to_string(), c.to_string()))
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use egui::{Grid, RichText, ScrollArea, Ui};
+
+use crate::action;
+use crate::database::{OcrCorrection, Substitution};
+use crate::event::event::update_corrections;
+use crate::ui::shutdown::TASK_TRACKER;
+use crate::ui::update_queue::enqueue_update;
+
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[serde(default)]
+pub struct CorrectionUi {
+    #[serde(skip)]
+    corrections: Vec<OcrCorrection>,
+    #[serde(skip)]
+    substitutions: Vec<Substitution>,
+    #[serde(skip)]
+    is_loaded: bool,
+}
+
+impl CorrectionUi {
+    pub fn set_corrections(
+        &mut self,
+        corrections: Vec<OcrCorrection>,
+        substitutions: Vec<Substitution>,
+    ) {
+        self.corrections = corrections;
+        self.substitutions = substitutions;
+        self.is_loaded = true;
+    }
+
+    fn load_corrections(&mut self) {
+        self.is_loaded = true;
+
+        TASK_TRACKER.spawn(async move {
+            let corrections = action::load_ocr_corrections();
+            let substitutions = action::load_substitutions();
+            enqueue_update(move |_, app| update_corrections(app, corrections, substitutions));
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        egui::Window::new("OCR Corrections")
+            .open(open)
+            .show(ctx, |ui| {
+                if !self.is_loaded {
+                    self.load_corrections();
+                }
+
+                if ui.button("Refresh").clicked() {
+                    self.load_corrections();
+                }
+
+                ScrollArea::vertical().show(ui, |ui| {
+                    ui.heading("Learned Substitutions");
+                    self.show_substitutions(ui);
+                    ui.separator();
+
+                    ui.heading("Corrections");
+                    self.show_corrections(ui);
+                });
+            });
+    }
+
+    fn show_substitutions(&mut self, ui: &mut Ui) {
+        if self.substitutions.is_empty() {
+            ui.label("No substitutions learned yet");
+            return;
+        }
+
+        let mut deleted = None;
+        Grid::new("OCR Substitutions")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(RichText::new("Substitution").strong());
+                ui.label(RichText::new("Count").strong());
+                ui.end_row();
+
+                for substitution in &self.substitutions {
+                    ui.label(
+                        RichText::new(format!("{} → {}", substitution.from, substitution.to))
+                            .size(18.0),
+                    );
+                    ui.label(substitution.count.to_string());
+                    if ui.button("Forget").clicked() {
+                        deleted = Some(substitution.clone());
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(substitution) = deleted {
+            self.substitutions.retain(|x| *x != substitution);
+            self.corrections.retain(|x| {
+                x.substitution_from.as_ref() != Some(&substitution.from)
+                    || x.substitution_to.as_ref() != Some(&substitution.to)
+            });
+            TASK_TRACKER.spawn(async move {
+                action::delete_substitution(&substitution.from, &substitution.to);
+            });
+        }
+    }
+
+    fn show_corrections(&mut self, ui: &mut Ui) {
+        if self.corrections.is_empty() {
+            ui.label("Click a candidate character in a pinned information box to correct the OCR");
+            return;
+        }
+
+        let mut deleted = None;
+        Grid::new("OCR Corrections")
+            .num_columns(4)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(RichText::new("Date").strong());
+                ui.label(RichText::new("Original").strong());
+                ui.label(RichText::new("Corrected").strong());
+                ui.end_row();
+
+                for correction in &self.corrections {
+                    ui.label(&correction.created_at);
+                    ui.label(&correction.original);
+                    ui.label(&correction.corrected);
+                    if ui.button("Delete").clicked() {
+                        deleted = Some(correction.id);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(id) = deleted {
+            self.corrections.retain(|x| x.id != id);
+            TASK_TRACKER.spawn(async move {
+                action::delete_ocr_correction(id);
+                let substitutions = action::load_substitutions();
+                let corrections = action::load_ocr_corrections();
+                enqueue_update(move |_, app| update_corrections(app, corrections, substitutions));
+            });
+        }
+    }
+}
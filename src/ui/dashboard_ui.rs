@@ -0,0 +1,334 @@
+use egui::{Color32, Grid, Rect, RichText, ScrollArea, Sense, Ui, Vec2};
+use strum::{EnumIter, IntoEnumIterator};
+
+use crate::action;
+use crate::database::{ActivityRange, DailyActivity};
+use crate::event::event::update_activity;
+use crate::ui::shutdown::TASK_TRACKER;
+use crate::ui::update_queue::enqueue_update;
+
+const CHART_HEIGHT: f32 = 120.0;
+const HEATMAP_CELL_SIZE: f32 = 12.0;
+const HEATMAP_CELL_SPACING: f32 = 2.0;
+
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[serde(default)]
+pub struct DashboardUi {
+    pub range: DashboardRange,
+    pub custom_from: String,
+    pub custom_to: String,
+    pub metric: ActivityMetric,
+    #[serde(skip)]
+    activity: Vec<DailyActivity>,
+    #[serde(skip)]
+    error: Option<String>,
+    #[serde(skip)]
+    is_loading: bool,
+    #[serde(skip)]
+    is_loaded: bool,
+}
+
+#[derive(
+    serde::Deserialize, serde::Serialize, Default, Clone, Copy, PartialEq, Debug, EnumIter,
+)]
+pub enum DashboardRange {
+    #[default]
+    Week,
+    Month,
+    Quarter,
+    Year,
+    All,
+    Custom,
+}
+
+impl DashboardRange {
+    fn get_label(&self) -> &'static str {
+        match self {
+            DashboardRange::Week => "7 Days",
+            DashboardRange::Month => "30 Days",
+            DashboardRange::Quarter => "90 Days",
+            DashboardRange::Year => "365 Days",
+            DashboardRange::All => "All",
+            DashboardRange::Custom => "Custom",
+        }
+    }
+}
+
+#[derive(
+    serde::Deserialize, serde::Serialize, Default, Clone, Copy, PartialEq, Debug, EnumIter,
+)]
+pub enum ActivityMetric {
+    #[default]
+    Characters,
+    Lines,
+    Lookups,
+    NewWords,
+}
+
+impl ActivityMetric {
+    fn get_label(&self) -> &'static str {
+        match self {
+            ActivityMetric::Characters => "Characters read",
+            ActivityMetric::Lines => "Lines captured",
+            ActivityMetric::Lookups => "Lookups",
+            ActivityMetric::NewWords => "New words",
+        }
+    }
+
+    fn get_value(&self, activity: &DailyActivity) -> i64 {
+        match self {
+            ActivityMetric::Characters => activity.characters,
+            ActivityMetric::Lines => activity.lines,
+            ActivityMetric::Lookups => activity.lookups,
+            ActivityMetric::NewWords => activity.new_words,
+        }
+    }
+}
+
+impl DashboardUi {
+    pub fn set_activity(&mut self, activity: anyhow::Result<Vec<DailyActivity>>) {
+        self.is_loading = false;
+        self.is_loaded = true;
+        match activity {
+            Ok(activity) => {
+                self.activity = activity;
+                self.error = None;
+            }
+            Err(err) => {
+                self.activity = vec![];
+                self.error = Some(err.to_string());
+            }
+        }
+    }
+
+    fn load_activity(&mut self) {
+        self.is_loading = true;
+
+        let range = self.range;
+        let custom_range = ActivityRange::Between(self.custom_from.clone(), self.custom_to.clone());
+
+        TASK_TRACKER.spawn(async move {
+            let range = match range {
+                DashboardRange::Week => ActivityRange::LastDays(7),
+                DashboardRange::Month => ActivityRange::LastDays(30),
+                DashboardRange::Quarter => ActivityRange::LastDays(90),
+                DashboardRange::Year => ActivityRange::LastDays(365),
+                DashboardRange::All => ActivityRange::All,
+                DashboardRange::Custom => custom_range,
+            };
+
+            let activity = action::load_daily_activity(&range);
+            enqueue_update(move |_, app| update_activity(app, activity));
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        egui::Window::new("Activity Dashboard")
+            .open(open)
+            .show(ctx, |ui| {
+                if !self.is_loaded && !self.is_loading {
+                    self.load_activity();
+                }
+
+                self.show_range_selection(ui);
+                ui.separator();
+
+                if let Some(error) = &self.error {
+                    ui.colored_label(Color32::RED, error);
+                    return;
+                }
+                if self.is_loading && self.activity.is_empty() {
+                    ui.spinner();
+                    return;
+                }
+
+                ScrollArea::vertical().show(ui, |ui| {
+                    self.show_totals(ui);
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
+                        for metric in ActivityMetric::iter() {
+                            ui.selectable_value(&mut self.metric, metric, metric.get_label());
+                        }
+                    });
+                    show_time_series(ui, &self.activity, self.metric);
+                    ui.separator();
+                    show_heatmap(ui, &self.activity, self.metric);
+                });
+            });
+    }
+
+    fn show_range_selection(&mut self, ui: &mut Ui) {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            for range in DashboardRange::iter() {
+                changed |= ui
+                    .selectable_value(&mut self.range, range, range.get_label())
+                    .clicked();
+            }
+            if ui.button("Refresh").clicked() {
+                changed = true;
+            }
+        });
+
+        if self.range == DashboardRange::Custom {
+            ui.horizontal(|ui| {
+                ui.label("From:");
+                let from = ui.add(
+                    egui::TextEdit::singleline(&mut self.custom_from)
+                        .hint_text("YYYY-MM-DD")
+                        .desired_width(90.0),
+                );
+                ui.label("To:");
+                let to = ui.add(
+                    egui::TextEdit::singleline(&mut self.custom_to)
+                        .hint_text("YYYY-MM-DD")
+                        .desired_width(90.0),
+                );
+                let enter_pressed = (from.lost_focus() || to.lost_focus())
+                    && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                changed |= enter_pressed || ui.button("Apply").clicked();
+            });
+        }
+
+        if changed {
+            self.load_activity();
+        }
+    }
+
+    fn show_totals(&self, ui: &mut Ui) {
+        let active_days = self.activity.iter().filter(|x| x.lines > 0).count();
+
+        Grid::new("Activity Dashboard Totals")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for metric in ActivityMetric::iter() {
+                    let total: i64 = self.activity.iter().map(|x| metric.get_value(x)).sum();
+                    ui.label(metric.get_label());
+                    ui.label(RichText::new(total.to_string()).strong());
+                    ui.end_row();
+                }
+                ui.label("Active days");
+                ui.label(
+                    RichText::new(format!("{active_days} / {}", self.activity.len())).strong(),
+                );
+                ui.end_row();
+            });
+    }
+}
+
+fn show_time_series(ui: &mut Ui, activity: &[DailyActivity], metric: ActivityMetric) {
+    let max_value = activity
+        .iter()
+        .map(|x| metric.get_value(x))
+        .max()
+        .unwrap_or(0);
+
+    let width = ui.available_width().max(200.0);
+    let (rect, response) = ui.allocate_exact_size(Vec2::new(width, CHART_HEIGHT), Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_stroke(
+        rect,
+        0.0,
+        ui.visuals().widgets.noninteractive.bg_stroke,
+        egui::StrokeKind::Inside,
+    );
+
+    if activity.is_empty() || max_value == 0 {
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            "No activity",
+            egui::FontId::proportional(14.0),
+            ui.visuals().weak_text_color(),
+        );
+        return;
+    }
+
+    let bar_width = rect.width() / activity.len() as f32;
+    let hovered_index = response
+        .hover_pos()
+        .map(|pos| ((pos.x - rect.left()) / bar_width) as usize);
+
+    for (index, day) in activity.iter().enumerate() {
+        let value = metric.get_value(day);
+        let height = rect.height() * value as f32 / max_value as f32;
+        let left = rect.left() + index as f32 * bar_width;
+        let bar = Rect::from_min_max(
+            egui::pos2(left + bar_width * 0.1, rect.bottom() - height),
+            egui::pos2(left + bar_width * 0.9, rect.bottom()),
+        );
+        let color = if hovered_index == Some(index) {
+            Color32::RED
+        } else {
+            Color32::from_rgb(64, 160, 96)
+        };
+        painter.rect_filled(bar, 0.0, color);
+    }
+
+    if let Some(day) = hovered_index.and_then(|index| activity.get(index)) {
+        response.on_hover_text(format!("{}: {}", day.day, metric.get_value(day)));
+    }
+}
+
+/// Calendar heatmap with one column per week and the weekdays from sunday to saturday as rows.
+fn show_heatmap(ui: &mut Ui, activity: &[DailyActivity], metric: ActivityMetric) {
+    let Some(first_day) = activity.first() else {
+        return;
+    };
+    let max_value = activity
+        .iter()
+        .map(|x| metric.get_value(x))
+        .max()
+        .unwrap_or(0);
+
+    let offset = first_day.weekday as usize;
+    let week_count = (activity.len() + offset).div_ceil(7);
+    let cell_step = HEATMAP_CELL_SIZE + HEATMAP_CELL_SPACING;
+
+    ScrollArea::horizontal().show(ui, |ui| {
+        let size = Vec2::new(week_count as f32 * cell_step, 7.0 * cell_step);
+        let (rect, response) = ui.allocate_exact_size(size, Sense::hover());
+        let painter = ui.painter_at(rect);
+
+        let mut hovered_day = None;
+        for (index, day) in activity.iter().enumerate() {
+            let position = index + offset;
+            let min = rect.min
+                + Vec2::new(
+                    (position / 7) as f32 * cell_step,
+                    (position % 7) as f32 * cell_step,
+                );
+            let cell = Rect::from_min_size(min, Vec2::splat(HEATMAP_CELL_SIZE));
+
+            let value = metric.get_value(day);
+            painter.rect_filled(cell, 2.0, get_heatmap_color(ui, value, max_value));
+
+            if response.hover_pos().is_some_and(|pos| cell.contains(pos)) {
+                hovered_day = Some(day);
+            }
+        }
+
+        if let Some(day) = hovered_day {
+            response.on_hover_text(format!(
+                "{}\n{}: {}",
+                day.day,
+                metric.get_label(),
+                metric.get_value(day)
+            ));
+        }
+    });
+}
+
+fn get_heatmap_color(ui: &Ui, value: i64, max_value: i64) -> Color32 {
+    if value <= 0 || max_value <= 0 {
+        return ui.visuals().faint_bg_color;
+    }
+    let intensity = (value as f32 / max_value as f32).clamp(0.15, 1.0);
+    Color32::from_rgb(
+        (40.0 * (1.0 - intensity)) as u8,
+        (80.0 + 140.0 * intensity) as u8,
+        (60.0 + 40.0 * intensity) as u8,
+    )
+}
@@ -0,0 +1,141 @@
+use egui::{CentralPanel, Color32, ScrollArea, SidePanel, TopBottomPanel, Vec2};
+use strum::{EnumIter, IntoEnumIterator};
+
+use super::screenshot_result_ui::{get_info_text, show_jpn_data_info};
+use crate::event::event::update_dictionary_results;
+use crate::jpn::{JpnData, get_jpn_data, search_jpn_data_by_gloss, search_jpn_data_by_reading};
+use crate::ui::shutdown::TASK_TRACKER;
+use crate::ui::update_queue::enqueue_update;
+
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[serde(default)]
+pub struct DictionaryUi {
+    pub input: String,
+    pub search_mode: DictionarySearchMode,
+    #[serde(skip)]
+    pub results: Vec<Vec<JpnData>>,
+    #[serde(skip)]
+    pub selected: Option<JpnData>,
+}
+
+#[derive(
+    serde::Deserialize, serde::Serialize, Default, Clone, Copy, PartialEq, Debug, EnumIter,
+)]
+pub enum DictionarySearchMode {
+    #[default]
+    Japanese,
+    English,
+    Reading,
+}
+
+impl DictionarySearchMode {
+    fn get_label(&self) -> &'static str {
+        match self {
+            DictionarySearchMode::Japanese => "Japanese Text",
+            DictionarySearchMode::English => "English Meaning",
+            DictionarySearchMode::Reading => "Reading",
+        }
+    }
+}
+
+impl DictionaryUi {
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        egui::Window::new("Dictionary").open(open).show(ctx, |ui| {
+            TopBottomPanel::top("Dictionary Search Panel").show_inside(ui, |ui| {
+                self.show_search(ui);
+            });
+            SidePanel::left("Dictionary Side Panel").show_inside(ui, |ui| {
+                ScrollArea::vertical().show(ui, |ui| self.show_results(ui));
+            });
+            TopBottomPanel::bottom("Dictionary invisible bottom panel")
+                .show_separator_line(false)
+                .show_inside(ui, |_| ());
+            CentralPanel::default().show_inside(ui, |ui| {
+                ScrollArea::vertical().show(ui, |ui| {
+                    ui.set_width(400.0);
+                    if let Some(selected) = &self.selected {
+                        show_jpn_data_info(ui, selected);
+                    }
+                });
+            });
+        });
+    }
+
+    fn show_search(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            for mode in DictionarySearchMode::iter() {
+                if ui
+                    .selectable_value(&mut self.search_mode, mode, mode.get_label())
+                    .clicked()
+                {
+                    self.search();
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let response = ui.text_edit_singleline(&mut self.input);
+            let enter_pressed =
+                response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+            let live_search = self.search_mode == DictionarySearchMode::Japanese;
+            if (live_search && response.changed()) || enter_pressed || ui.button("Search").clicked()
+            {
+                self.search();
+            }
+        });
+    }
+
+    fn show_results(&mut self, ui: &mut egui::Ui) {
+        if self.results.is_empty() {
+            ui.label("No Results");
+            return;
+        }
+
+        let old_item_spacing = ui.spacing().item_spacing;
+        ui.spacing_mut().item_spacing = Vec2::new(0.0, old_item_spacing.y);
+
+        for line in &self.results {
+            ui.horizontal_wrapped(|ui| {
+                for jpn_data in line {
+                    let mut text = get_info_text(jpn_data.get_kanji());
+                    if jpn_data.has_kanji_data() {
+                        text = text.underline();
+                    }
+                    if self.selected.as_ref() == Some(jpn_data) {
+                        text = text.color(Color32::RED);
+                    }
+
+                    if ui.label(text).clicked() && jpn_data.has_kanji_data() {
+                        self.selected = Some(jpn_data.clone());
+                    }
+                }
+            });
+        }
+
+        ui.spacing_mut().item_spacing = old_item_spacing;
+    }
+
+    fn search(&mut self) {
+        let query = self.input.clone();
+        let search_mode = self.search_mode;
+
+        TASK_TRACKER.spawn(async move {
+            let results = match search_mode {
+                DictionarySearchMode::Japanese => get_jpn_data(&query).await,
+                DictionarySearchMode::English => search_jpn_data_by_gloss(&query)
+                    .await
+                    .into_iter()
+                    .map(|x| vec![x])
+                    .collect(),
+                DictionarySearchMode::Reading => search_jpn_data_by_reading(&query)
+                    .await
+                    .into_iter()
+                    .map(|x| vec![x])
+                    .collect(),
+            };
+
+            enqueue_update(move |_, app| update_dictionary_results(app, query, results));
+        });
+    }
+}
@@ -0,0 +1,106 @@
+use crate::jpn::JpnData;
+use crate::jpn::kana::{split_furigana, to_romaji};
+use crate::ui::screenshot_result_ui::get_info_text;
+use egui::{CollapsingHeader, Response, RichText, Ui};
+use strum::{EnumIter, IntoEnumIterator};
+
+const RUBY_SIZE: f32 = 10.0;
+
+#[derive(serde::Deserialize, serde::Serialize, Default, Clone)]
+#[serde(default)]
+pub struct FuriganaConfig {
+    pub mode: FuriganaMode,
+    pub show_romaji: bool,
+}
+
+#[derive(
+    serde::Deserialize, serde::Serialize, Default, Clone, Copy, PartialEq, Debug, EnumIter,
+)]
+pub enum FuriganaMode {
+    #[default]
+    Off,
+    Furigana,
+    KanaOnly,
+}
+
+impl FuriganaMode {
+    fn get_label(&self) -> &'static str {
+        match self {
+            FuriganaMode::Off => "Off",
+            FuriganaMode::Furigana => "Furigana",
+            FuriganaMode::KanaOnly => "Kana only",
+        }
+    }
+}
+
+impl FuriganaConfig {
+    pub fn show_config(&mut self, ui: &mut Ui) {
+        CollapsingHeader::new("Furigana Config").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for mode in FuriganaMode::iter() {
+                    ui.selectable_value(&mut self.mode, mode, mode.get_label());
+                }
+            });
+            ui.checkbox(&mut self.show_romaji, "Show Romaji");
+        });
+    }
+
+    /// Shows the word with its reading depending on the config. `style` is applied to the word text.
+    pub fn show_word(
+        &self,
+        ui: &mut Ui,
+        jpn_data: &JpnData,
+        style: impl Fn(RichText) -> RichText,
+    ) -> Response {
+        let surface = jpn_data.get_kanji();
+        if self.mode == FuriganaMode::Off && !self.show_romaji {
+            return ui.label(style(get_info_text(surface)));
+        }
+
+        let reading = jpn_data.get_reading();
+
+        ui.vertical(|ui| {
+            let response = match (self.mode, &reading) {
+                (FuriganaMode::Furigana, Some(reading)) => {
+                    ui.horizontal(|ui| {
+                        split_furigana(&surface, reading)
+                            .into_iter()
+                            .map(|(text, ruby)| show_ruby(ui, text, ruby, &style))
+                            .reduce(|a, b| a.union(b))
+                    })
+                    .inner
+                }
+                (FuriganaMode::Furigana, None) => {
+                    Some(show_ruby(ui, surface.clone(), None, &style))
+                }
+                (FuriganaMode::KanaOnly, reading) => {
+                    let text = reading.clone().unwrap_or_else(|| surface.clone());
+                    Some(ui.label(style(get_info_text(text))))
+                }
+                (FuriganaMode::Off, _) => Some(ui.label(style(get_info_text(&surface)))),
+            };
+
+            if self.show_romaji {
+                let kana = reading.as_deref().unwrap_or(&surface);
+                ui.label(RichText::new(to_romaji(kana)).size(RUBY_SIZE));
+            }
+
+            response
+        })
+        .inner
+        .unwrap_or_else(|| ui.label(""))
+    }
+}
+
+fn show_ruby(
+    ui: &mut Ui,
+    text: String,
+    ruby: Option<String>,
+    style: impl Fn(RichText) -> RichText,
+) -> Response {
+    ui.vertical(|ui| {
+        ui.label(RichText::new(ruby.unwrap_or_default()).size(RUBY_SIZE));
+        ui.label(style(get_info_text(text)))
+    })
+    .inner
+}
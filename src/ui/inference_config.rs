@@ -0,0 +1,179 @@
+use crate::detect::session_builder::{
+    get_active_providers, is_provider_available, set_inference_config,
+};
+use crate::ocr::ctc::unload_ctc_models;
+use crate::ocr::image_model::unload_image_models;
+use crate::ui::app::OcrApp;
+use egui::{Button, CollapsingHeader, Color32, RichText, Ui};
+use strum::{EnumIter, IntoEnumIterator};
+
+#[derive(
+    serde::Deserialize, serde::Serialize, Clone, Copy, PartialEq, Eq, Hash, Debug, EnumIter,
+)]
+pub enum Provider {
+    Cpu,
+    Cuda,
+    TensorRt,
+    DirectMl,
+    CoreMl,
+    OpenVino,
+}
+
+impl Provider {
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            Provider::Cpu => "CPU",
+            Provider::Cuda => "CUDA",
+            Provider::TensorRt => "TensorRT",
+            Provider::DirectMl => "DirectML",
+            Provider::CoreMl => "CoreML",
+            Provider::OpenVino => "OpenVINO",
+        }
+    }
+}
+
+#[derive(
+    serde::Deserialize, serde::Serialize, Default, Clone, Copy, PartialEq, Debug, EnumIter,
+)]
+pub enum OptimizationLevel {
+    Disable,
+    Level1,
+    Level2,
+    #[default]
+    Level3,
+}
+
+impl OptimizationLevel {
+    fn get_label(&self) -> &'static str {
+        match self {
+            OptimizationLevel::Disable => "Disabled",
+            OptimizationLevel::Level1 => "Basic",
+            OptimizationLevel::Level2 => "Extended",
+            OptimizationLevel::Level3 => "All",
+        }
+    }
+}
+
+/// ONNX Runtime session options, applied when the models are loaded.
+#[derive(serde::Deserialize, serde::Serialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct InferenceConfig {
+    /// Execution providers in the order of preference, the CPU is the last fallback
+    pub providers: Vec<Provider>,
+    /// 0 lets ONNX Runtime choose the number of threads
+    pub intra_threads: usize,
+    pub inter_threads: usize,
+    pub parallel_execution: bool,
+    pub optimization_level: OptimizationLevel,
+    pub cpu_arena: bool,
+    pub memory_pattern: bool,
+    /// Limit of the GPU memory arena, 0 for no limit
+    pub gpu_memory_limit_mb: usize,
+}
+
+impl Default for InferenceConfig {
+    fn default() -> Self {
+        Self {
+            providers: vec![Provider::Cuda, Provider::Cpu],
+            intra_threads: 4,
+            inter_threads: 0,
+            parallel_execution: false,
+            optimization_level: OptimizationLevel::default(),
+            cpu_arena: true,
+            memory_pattern: true,
+            gpu_memory_limit_mb: 0,
+        }
+    }
+}
+
+impl InferenceConfig {
+    pub fn show_config(&mut self, ui: &mut Ui) {
+        CollapsingHeader::new("Inference Config").show(ui, |ui| {
+            ui.label("Execution Providers:");
+            self.show_providers(ui);
+
+            let max_threads = std::thread::available_parallelism().map_or(16, |x| x.get());
+            ui.add(
+                egui::Slider::new(&mut self.intra_threads, 0..=max_threads)
+                    .text("Intra Op Threads"),
+            )
+            .on_hover_text("0 lets ONNX Runtime choose");
+            ui.add(
+                egui::Slider::new(&mut self.inter_threads, 0..=max_threads)
+                    .text("Inter Op Threads"),
+            )
+            .on_hover_text("Only used with parallel execution, 0 lets ONNX Runtime choose");
+            ui.checkbox(&mut self.parallel_execution, "Parallel Execution");
+
+            ui.horizontal(|ui| {
+                ui.label("Graph Optimization:");
+                for level in OptimizationLevel::iter() {
+                    ui.selectable_value(&mut self.optimization_level, level, level.get_label());
+                }
+            });
+
+            ui.checkbox(&mut self.cpu_arena, "CPU Memory Arena");
+            ui.checkbox(&mut self.memory_pattern, "Memory Pattern Optimization");
+            ui.add(
+                egui::Slider::new(&mut self.gpu_memory_limit_mb, 0..=16384)
+                    .text("GPU Memory Limit (MB)"),
+            )
+            .on_hover_text("0 for no limit");
+
+            if ui.button("Apply and Reload Models").clicked() {
+                set_inference_config(self.clone());
+                unload_ctc_models();
+                unload_image_models();
+                OcrApp::reload_backends();
+            }
+        });
+    }
+
+    fn show_providers(&mut self, ui: &mut Ui) {
+        let active = get_active_providers();
+        let mut moved_up = None;
+        let mut removed = None;
+
+        for (index, provider) in self.providers.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.add_enabled(index > 0, Button::new("\u{2B06}")).clicked() {
+                    moved_up = Some(index);
+                }
+                if ui
+                    .button(RichText::new("\u{1F5D9}").color(Color32::RED))
+                    .clicked()
+                {
+                    removed = Some(index);
+                }
+
+                let mut text = RichText::new(provider.get_label());
+                if active.contains(provider) {
+                    text = text.color(Color32::GREEN);
+                }
+                let response = ui.label(text);
+                if !is_provider_available(*provider) {
+                    ui.label(RichText::new("not available").color(Color32::GRAY));
+                } else if active.contains(provider) {
+                    response.on_hover_text("Active");
+                }
+            });
+        }
+
+        if let Some(index) = moved_up {
+            self.providers.swap(index - 1, index);
+        }
+        if let Some(index) = removed {
+            self.providers.remove(index);
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Add:");
+            for provider in Provider::iter() {
+                if !self.providers.contains(&provider) && ui.button(provider.get_label()).clicked()
+                {
+                    self.providers.push(provider);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,253 @@
+use egui::{CollapsingHeader, Color32, Grid, RichText, ScrollArea, Ui};
+
+use super::screenshot_result_ui::{get_info_text, show_jpn_data_info};
+use crate::action;
+use crate::event::event::{open_kanji_detail, update_kanji_detail};
+use crate::jpn::kanji::{KanjiData, get_kanji_data, get_kanji_with_radical};
+use crate::jpn::{JpnData, get_kanji_words};
+use crate::ui::shutdown::TASK_TRACKER;
+use crate::ui::stroke_order::StrokeOrderConfig;
+use crate::ui::update_queue::enqueue_update;
+
+const KANJI_SIZE: f32 = 60.0;
+const STROKE_ORDER_SIZE: f32 = 150.0;
+
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[serde(default)]
+pub struct KanjiDetailUi {
+    pub kanji: Option<char>,
+    #[serde(skip)]
+    pub detail: Option<KanjiDetail>,
+    #[serde(skip)]
+    is_loading: bool,
+    #[serde(skip)]
+    selected_radical: Option<String>,
+    #[serde(skip)]
+    radical_kanji: Vec<char>,
+    #[serde(skip)]
+    selected_word: Option<JpnData>,
+}
+
+#[derive(Clone, Debug)]
+pub struct KanjiDetail {
+    pub kanji: char,
+    pub kanji_data: Option<KanjiData>,
+    pub words: Vec<KanjiWord>,
+}
+
+#[derive(Clone, Debug)]
+pub struct KanjiWord {
+    pub jpn_data: JpnData,
+    pub common: bool,
+    pub known: bool,
+}
+
+impl KanjiDetailUi {
+    pub fn set_kanji(&mut self, kanji: char) {
+        if self.kanji == Some(kanji) && self.detail.is_some() {
+            return;
+        }
+        self.kanji = Some(kanji);
+        self.detail = None;
+        self.selected_radical = None;
+        self.radical_kanji = vec![];
+        self.selected_word = None;
+        self.load_detail();
+    }
+
+    pub fn set_detail(&mut self, detail: KanjiDetail) {
+        self.is_loading = false;
+        if self.kanji == Some(detail.kanji) {
+            self.detail = Some(detail);
+        }
+    }
+
+    fn load_detail(&mut self) {
+        let Some(kanji) = self.kanji else {
+            return;
+        };
+        self.is_loading = true;
+
+        TASK_TRACKER.spawn(async move {
+            let known_words = action::load_known_words();
+            let words = get_kanji_words(kanji)
+                .await
+                .into_iter()
+                .map(|(jpn_data, common)| KanjiWord {
+                    known: known_words.contains(&jpn_data.get_kanji()),
+                    jpn_data,
+                    common,
+                })
+                .collect();
+
+            let detail = KanjiDetail {
+                kanji,
+                kanji_data: get_kanji_data(kanji),
+                words,
+            };
+            enqueue_update(move |_, app| update_kanji_detail(app, detail));
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool, stroke_order: &StrokeOrderConfig) {
+        egui::Window::new("Kanji Detail")
+            .open(open)
+            .show(ctx, |ui| {
+                if self.detail.is_none() && !self.is_loading {
+                    self.load_detail();
+                }
+
+                let Some(mut detail) = self.detail.take() else {
+                    if self.is_loading {
+                        ui.spinner();
+                    } else {
+                        ui.label("No Kanji selected");
+                    }
+                    return;
+                };
+
+                ScrollArea::vertical().show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new(detail.kanji.to_string()).size(KANJI_SIZE));
+                        stroke_order.show_stroke_order(ui, detail.kanji, STROKE_ORDER_SIZE);
+                    });
+
+                    if let Some(kanji_data) = &detail.kanji_data {
+                        show_kanji_data(ui, kanji_data);
+                        self.show_radicals(ui, kanji_data);
+                    }
+
+                    self.show_words(ui, &mut detail.words);
+                });
+
+                self.detail = Some(detail);
+            });
+    }
+
+    fn show_radicals(&mut self, ui: &mut Ui, kanji_data: &KanjiData) {
+        if kanji_data.wk_radicals.is_empty() {
+            return;
+        }
+
+        ui.separator();
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Radicals:");
+            for radical in &kanji_data.wk_radicals {
+                let is_selected = self.selected_radical.as_ref() == Some(radical);
+                if ui.selectable_label(is_selected, radical).clicked() {
+                    if is_selected {
+                        self.selected_radical = None;
+                        self.radical_kanji = vec![];
+                    } else {
+                        self.selected_radical = Some(radical.clone());
+                        self.radical_kanji = get_kanji_with_radical(radical);
+                    }
+                }
+            }
+        });
+
+        if let Some(radical) = &self.selected_radical {
+            CollapsingHeader::new(format!("Kanji with radical '{radical}'"))
+                .default_open(true)
+                .show(ui, |ui| {
+                    ui.horizontal_wrapped(|ui| {
+                        for kanji in &self.radical_kanji {
+                            show_kanji_link(ui, *kanji);
+                        }
+                    });
+                });
+        }
+    }
+
+    fn show_words(&mut self, ui: &mut Ui, words: &mut [KanjiWord]) {
+        ui.separator();
+        CollapsingHeader::new(format!("Words ({})", words.len()))
+            .default_open(true)
+            .show(ui, |ui| {
+                Grid::new("Kanji Detail Words")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for word in words.iter_mut() {
+                            self.show_word_row(ui, word);
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if let Some(selected_word) = &self.selected_word {
+            ui.separator();
+            show_jpn_data_info(ui, selected_word);
+        }
+    }
+
+    fn show_word_row(&mut self, ui: &mut Ui, word: &mut KanjiWord) {
+        let text = word.jpn_data.get_kanji();
+
+        let mut label = get_info_text(&text);
+        if self.selected_word.as_ref() == Some(&word.jpn_data) {
+            label = label.color(Color32::RED);
+        }
+        if ui.label(label).clicked() {
+            self.selected_word = Some(word.jpn_data.clone());
+        }
+
+        ui.label(if word.common { "common" } else { "" });
+
+        if word.known {
+            ui.label(RichText::new("known").color(Color32::GREEN));
+        } else {
+            ui.label(RichText::new("unknown").color(Color32::GRAY));
+        }
+
+        let button_text = if word.known {
+            "Mark Unknown"
+        } else {
+            "Mark Known"
+        };
+        if ui.button(button_text).clicked() {
+            word.known = !word.known;
+            let known = word.known;
+            TASK_TRACKER.spawn(async move { action::set_word_known(&text, known) });
+        }
+    }
+}
+
+fn show_kanji_data(ui: &mut Ui, kanji_data: &KanjiData) {
+    let optional = |value: Option<u8>| value.map(|x| x.to_string()).unwrap_or_default();
+
+    Grid::new("Kanji Detail Data")
+        .num_columns(2)
+        .striped(true)
+        .show(ui, |ui| {
+            let rows = [
+                ("Meanings", kanji_data.meanings.join(", ")),
+                ("On Readings", kanji_data.readings_on.join(", ")),
+                ("Kun Readings", kanji_data.readings_kun.join(", ")),
+                ("Strokes", kanji_data.strokes.to_string()),
+                ("Grade", optional(kanji_data.grade)),
+                (
+                    "Frequency",
+                    kanji_data.freq.map(|x| x.to_string()).unwrap_or_default(),
+                ),
+                ("JLPT (old)", optional(kanji_data.jlpt_old)),
+                ("JLPT (new)", optional(kanji_data.jlpt_new)),
+                ("WaniKani Level", optional(kanji_data.wk_level)),
+                ("WaniKani Meanings", kanji_data.wk_meanings.join(", ")),
+                ("WaniKani On", kanji_data.wk_readings_on.join(", ")),
+                ("WaniKani Kun", kanji_data.wk_readings_kun.join(", ")),
+            ];
+
+            for (label, value) in rows {
+                ui.label(label);
+                ui.label(value);
+                ui.end_row();
+            }
+        });
+}
+
+/// Clickable kanji which opens the kanji detail window.
+pub fn show_kanji_link(ui: &mut Ui, kanji: char) {
+    if ui.button(get_info_text(kanji.to_string())).clicked() {
+        enqueue_update(move |_, app| open_kanji_detail(app, kanji));
+    }
+}
@@ -5,6 +5,7 @@ use egui_extras::{Column, TableBuilder};
 use tokio::time::sleep;
 
 use crate::event::event::update_history_data;
+use crate::jpn::difficulty::SessionDifficulty;
 use crate::ui::shutdown::TASK_TRACKER;
 use crate::ui::update_queue::enqueue_update;
 use crate::{action, database::HistoryData};
@@ -26,8 +27,27 @@ pub fn init_history_updater() {
 }
 
 impl HistoryDataUi {
-    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        open: &mut bool,
+        session_difficulty: &mut SessionDifficulty,
+    ) {
         egui::Window::new("History").open(open).show(ctx, |ui| {
+            TopBottomPanel::top("HistoryDataUi session panel").show_inside(ui, |ui| {
+                ui.horizontal(|ui| {
+                    match session_difficulty.get_average() {
+                        Some(average) => ui.label(format!(
+                            "Session Difficulty: {average:.0} ({} pages)",
+                            session_difficulty.page_count
+                        )),
+                        None => ui.label("Session Difficulty: -"),
+                    };
+                    if ui.button("Reset Session").clicked() {
+                        session_difficulty.reset();
+                    }
+                });
+            });
             TopBottomPanel::bottom("HistoryDataUi invisible bottom panel")
                 .show_separator_line(false)
                 .show_inside(ui, |_| ());
@@ -37,6 +57,7 @@ impl HistoryDataUi {
 
     fn show_table(&mut self, ui: &mut egui::Ui) {
         TableBuilder::new(ui)
+            .column(Column::auto())
             .column(Column::auto())
             .column(Column::remainder())
             .column(Column::remainder())
@@ -44,6 +65,9 @@ impl HistoryDataUi {
                 header.col(|ui| {
                     ui.heading("Timestamp");
                 });
+                header.col(|ui| {
+                    ui.heading("Difficulty");
+                });
                 header.col(|ui| {
                     ui.heading("OCR");
                 });
@@ -57,6 +81,11 @@ impl HistoryDataUi {
                         row.col(|ui| {
                             ui.label(&value.created_at);
                         });
+                        row.col(|ui| {
+                            if let Some(difficulty) = value.difficulty {
+                                ui.label(format!("{difficulty:.0}"));
+                            }
+                        });
                         row.col(|ui| {
                             ui.label(&value.ocr);
                         });
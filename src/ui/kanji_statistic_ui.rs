@@ -1,14 +1,19 @@
 use std::time::Duration;
 
-use egui::{CentralPanel, Context, ScrollArea, Sense, SidePanel, TopBottomPanel};
+use egui::{
+    CentralPanel, CollapsingHeader, Context, Grid, ScrollArea, Sense, SidePanel, TopBottomPanel, Ui,
+};
 use egui_extras::{Column, TableBuilder};
 use tokio::time::sleep;
 
+use super::kanji_detail_ui::show_kanji_link;
 use super::screenshot_result_ui::show_jpn_data_info;
+use crate::database::{KanjiStatistic, WordStatistic};
 use crate::event::event::{update_kanji_statistic, update_selected_jpn_data};
 use crate::ui::shutdown::TASK_TRACKER;
+use crate::ui::stroke_order::StrokeOrderConfig;
 use crate::ui::update_queue::enqueue_update;
-use crate::{action, database::KanjiStatistic, jpn::JpnData};
+use crate::{action, jpn::JpnData};
 
 #[derive(serde::Deserialize, serde::Serialize, Default)]
 #[serde(default)]
@@ -16,6 +21,10 @@ pub struct KanjiStatisticUi {
     pub kanji_statistic: Vec<KanjiStatistic>,
     pub selected_kanji_index: Option<usize>,
     pub selected_jpn_data: JpnData,
+    #[serde(skip)]
+    pub word_statistic: Option<WordStatistic>,
+    #[serde(skip)]
+    pub example_sentences: Vec<String>,
 }
 
 pub fn init_kanji_statistic_updater() {
@@ -30,7 +39,7 @@ pub fn init_kanji_statistic_updater() {
 }
 
 impl KanjiStatisticUi {
-    pub fn show(&mut self, ctx: &Context, open: &mut bool) {
+    pub fn show(&mut self, ctx: &Context, open: &mut bool, stroke_order: &StrokeOrderConfig) {
         egui::Window::new("Kanji Statistic")
             .open(open)
             .show(ctx, |ui| {
@@ -43,7 +52,20 @@ impl KanjiStatisticUi {
                 CentralPanel::default().show_inside(ui, |ui| {
                     ScrollArea::vertical().show(ui, |ui| {
                         ui.set_width(600.0);
+                        ui.horizontal_wrapped(|ui| {
+                            for word in &self.selected_jpn_data.words {
+                                if word.kanji_data.is_some() {
+                                    show_kanji_link(ui, word.word);
+                                }
+                            }
+                        });
+                        for word in &self.selected_jpn_data.words {
+                            if word.kanji_data.is_some() {
+                                stroke_order.show_stroke_order(ui, word.word, 100.0);
+                            }
+                        }
                         show_jpn_data_info(ui, &self.selected_jpn_data);
+                        self.show_word_statistic(ui);
                     });
                 });
             });
@@ -89,9 +111,55 @@ impl KanjiStatisticUi {
             let kanji = kanji_statistic.kanji.clone();
             TASK_TRACKER.spawn(async move {
                 if let Some(jpn_data) = action::get_kanji_jpn_data(&kanji).await {
-                    enqueue_update(|_, app| update_selected_jpn_data(app, jpn_data));
+                    let word = jpn_data.get_dictionary_form();
+                    let word_statistic = action::load_word_statistic(&word);
+                    let example_sentences = action::load_example_sentences(&word);
+
+                    enqueue_update(|_, app| {
+                        update_selected_jpn_data(app, jpn_data, word_statistic, example_sentences)
+                    });
                 };
             });
         }
     }
+
+    fn show_word_statistic(&self, ui: &mut Ui) {
+        let Some(statistic) = &self.word_statistic else {
+            return;
+        };
+
+        ui.separator();
+        Grid::new("Word Statistic")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                let rows = [
+                    ("Word", statistic.word.clone()),
+                    ("Seen", statistic.seen_count.to_string()),
+                    ("Looked up", statistic.lookup_count.to_string()),
+                    (
+                        "First seen",
+                        statistic.first_seen.clone().unwrap_or_default(),
+                    ),
+                    ("Last seen", statistic.last_seen.clone().unwrap_or_default()),
+                ];
+
+                for (label, value) in rows {
+                    ui.label(label);
+                    ui.label(value);
+                    ui.end_row();
+                }
+            });
+
+        CollapsingHeader::new(format!(
+            "Example Sentences ({})",
+            self.example_sentences.len()
+        ))
+        .default_open(true)
+        .show(ui, |ui| {
+            for sentence in &self.example_sentences {
+                ui.label(sentence);
+            }
+        });
+    }
 }
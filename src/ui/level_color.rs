@@ -0,0 +1,106 @@
+use crate::jpn::JpnData;
+use crate::jpn::kanji::get_kanji_data;
+use egui::{CollapsingHeader, Color32, RichText, Ui};
+use strum::{EnumIter, IntoEnumIterator};
+
+/// Colours from the easiest to the hardest level.
+const LEVEL_COLORS: [Color32; 5] = [
+    Color32::from_rgb(80, 200, 120),
+    Color32::from_rgb(120, 190, 230),
+    Color32::from_rgb(230, 210, 90),
+    Color32::from_rgb(240, 150, 60),
+    Color32::from_rgb(230, 80, 200),
+];
+
+/// WaniKani groups 10 levels into one stage (Pleasant, Painful, Death, Hell, Paradise, Reality).
+const WK_LEVELS_PER_STAGE: u8 = 10;
+
+#[derive(
+    serde::Deserialize, serde::Serialize, Default, Clone, Copy, PartialEq, Debug, EnumIter,
+)]
+pub enum LevelColorMode {
+    #[default]
+    Off,
+    Jlpt,
+    WaniKani,
+    Grade,
+}
+
+impl LevelColorMode {
+    fn get_label(&self) -> &'static str {
+        match self {
+            LevelColorMode::Off => "Off",
+            LevelColorMode::Jlpt => "JLPT",
+            LevelColorMode::WaniKani => "WaniKani",
+            LevelColorMode::Grade => "School Grade",
+        }
+    }
+
+    fn get_legend(&self) -> [&'static str; 5] {
+        match self {
+            LevelColorMode::Off => [""; 5],
+            LevelColorMode::Jlpt => ["N5", "N4", "N3", "N2", "N1"],
+            LevelColorMode::WaniKani => ["1-10", "11-20", "21-30", "31-40", "41-60"],
+            LevelColorMode::Grade => ["1-2", "3-4", "5-6", "Secondary", "Jinmeiyo"],
+        }
+    }
+
+    pub fn show_config(&mut self, ui: &mut Ui) {
+        CollapsingHeader::new("Level Colour Config").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for mode in LevelColorMode::iter() {
+                    ui.selectable_value(self, mode, mode.get_label());
+                }
+            });
+
+            if *self != LevelColorMode::Off {
+                ui.horizontal(|ui| {
+                    for (label, color) in self.get_legend().iter().zip(LEVEL_COLORS) {
+                        ui.label(RichText::new(*label).color(color));
+                    }
+                });
+            }
+        });
+    }
+
+    /// Colour of the hardest kanji in the word, `None` for kana and kanji without level.
+    pub fn get_word_color(&self, jpn_data: &JpnData) -> Option<Color32> {
+        let level = match self {
+            LevelColorMode::Off => None,
+            LevelColorMode::Jlpt => jpn_data.get_jlpt_level().map(get_jlpt_index),
+            LevelColorMode::WaniKani => jpn_data.get_wk_level().map(get_wk_index),
+            LevelColorMode::Grade => jpn_data.get_grade().map(get_grade_index),
+        };
+        level.map(|x| LEVEL_COLORS[x])
+    }
+
+    pub fn get_kanji_color(&self, kanji: char) -> Option<Color32> {
+        let kanji_data = get_kanji_data(kanji)?;
+        let level = match self {
+            LevelColorMode::Off => None,
+            LevelColorMode::Jlpt => kanji_data.jlpt_new.map(get_jlpt_index),
+            LevelColorMode::WaniKani => kanji_data.wk_level.map(get_wk_index),
+            LevelColorMode::Grade => kanji_data.grade.map(get_grade_index),
+        };
+        level.map(|x| LEVEL_COLORS[x])
+    }
+}
+
+fn get_jlpt_index(jlpt: u8) -> usize {
+    5 - jlpt.clamp(1, 5) as usize
+}
+
+fn get_wk_index(wk_level: u8) -> usize {
+    (wk_level.saturating_sub(1) / WK_LEVELS_PER_STAGE).min(4) as usize
+}
+
+/// Grades 1-6 are elementary school, 8 secondary school and 9-10 jinmeiyo kanji.
+fn get_grade_index(grade: u8) -> usize {
+    match grade {
+        0..=2 => 0,
+        3..=4 => 1,
+        5..=6 => 2,
+        7..=8 => 3,
+        _ => 4,
+    }
+}
@@ -1,13 +1,25 @@
 pub mod app;
+pub mod backend_status;
 pub mod background_rect;
+pub mod breakdown_ui;
+pub mod correction_ui;
+pub mod dashboard_ui;
+pub mod dictionary_ui;
+pub mod furigana;
 pub mod id_item;
 pub mod image_display;
+pub mod inference_config;
+pub mod kanji_detail_ui;
 pub mod kanji_history_ui;
 pub mod kanji_statistic_ui;
+pub mod level_color;
+pub mod model_ui;
 pub mod mouse_hover;
+pub mod notification;
 pub mod pipeline_config;
 pub mod screenshot_result_ui;
 pub mod settings;
 pub mod shortcut;
 pub mod shutdown;
+pub mod stroke_order;
 pub mod update_queue;
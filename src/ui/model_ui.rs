@@ -0,0 +1,216 @@
+use egui::{Button, Color32, ComboBox, Grid, RichText, Ui};
+use std::path::PathBuf;
+use strum::IntoEnumIterator;
+
+use crate::OcrApp;
+use crate::event::event::update_model_infos;
+use crate::model::store::{self, MODELS, ModelInfo, ModelStatus, ModelStoreConfig, ModelVariant};
+use crate::ui::shutdown::TASK_TRACKER;
+use crate::ui::update_queue::enqueue_update;
+
+#[derive(serde::Deserialize, serde::Serialize, Default)]
+#[serde(default)]
+pub struct ModelUi {
+    pub config: ModelStoreConfig,
+    import_path: String,
+    #[serde(skip)]
+    infos: Vec<ModelInfo>,
+    #[serde(skip)]
+    message: Option<String>,
+    #[serde(skip)]
+    is_loaded: bool,
+    #[serde(skip)]
+    is_busy: bool,
+}
+
+impl ModelUi {
+    /// Applies the stored config before the models are loaded.
+    pub fn init(&self) {
+        store::set_config(self.config.clone());
+    }
+
+    pub fn set_model_infos(&mut self, infos: Vec<ModelInfo>, message: Option<String>) {
+        self.infos = infos;
+        if message.is_some() {
+            self.message = message;
+        }
+        self.is_busy = false;
+    }
+
+    fn load_model_infos(&mut self) {
+        self.is_loaded = true;
+        self.run_task(|| None);
+    }
+
+    /// Runs the action in the background and refreshes the model list afterwards.
+    fn run_task(&mut self, action: impl FnOnce() -> Option<String> + Send + 'static) {
+        self.is_busy = true;
+
+        TASK_TRACKER.spawn(async move {
+            let message = action();
+            let infos = store::get_model_infos();
+            enqueue_update(move |_, app| update_model_infos(app, infos, message));
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        egui::Window::new("Models").open(open).show(ctx, |ui| {
+            if !self.is_loaded {
+                self.load_model_infos();
+            }
+
+            self.show_config(ui);
+            ui.separator();
+
+            self.show_models(ui);
+            ui.separator();
+
+            self.show_import(ui);
+
+            if let Some(message) = &self.message {
+                ui.separator();
+                ui.label(message);
+            }
+        });
+    }
+
+    fn show_config(&mut self, ui: &mut Ui) {
+        let mut changed = false;
+        Grid::new("Model Store Config")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Models Directory:");
+                changed |= ui
+                    .text_edit_singleline(&mut self.config.models_dir)
+                    .lost_focus();
+                ui.end_row();
+
+                ui.label("Offline Mode:");
+                changed |= ui
+                    .checkbox(&mut self.config.offline, "Only use local files")
+                    .changed();
+                ui.end_row();
+            });
+
+        if changed && store::get_config() != self.config {
+            store::set_config(self.config.clone());
+            self.load_model_infos();
+        }
+    }
+
+    fn show_models(&mut self, ui: &mut Ui) {
+        let mut download = None;
+        let mut selected_variant = None;
+        Grid::new("Model List")
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label(RichText::new("Model").strong());
+                ui.label(RichText::new("Variant").strong());
+                ui.label(RichText::new("Version").strong());
+                ui.label(RichText::new("Status").strong());
+                ui.end_row();
+
+                for info in &self.infos {
+                    ui.label(info.name);
+                    let mut variant = info.variant;
+                    ComboBox::from_id_salt(info.id)
+                        .selected_text(variant.get_label())
+                        .show_ui(ui, |ui| {
+                            for x in ModelVariant::iter() {
+                                ui.selectable_value(&mut variant, x, x.get_label());
+                            }
+                        });
+                    if variant != info.variant {
+                        selected_variant = Some((info.id, variant));
+                    }
+                    ui.label(info.version.as_deref().unwrap_or("-"));
+                    show_status(ui, info);
+                    if info.can_download {
+                        let hover = if info.pinned {
+                            "Pinned to a commit and checked against the shipped checksums"
+                        } else {
+                            "Not pinned, the checksums of the download are recorded"
+                        };
+                        if ui
+                            .add_enabled(!self.is_busy, Button::new("Download"))
+                            .on_hover_text(hover)
+                            .clicked()
+                        {
+                            download = Some(info.id);
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some((id, variant)) = selected_variant {
+            self.config.variants.insert(id.to_string(), variant);
+            store::set_config(self.config.clone());
+            self.message =
+                Some("Click \"Reload Models\" to load the selected variants".to_string());
+            self.load_model_infos();
+        }
+
+        if let Some(id) = download
+            && let Some(spec) = MODELS.into_iter().find(|x| x.id == id)
+        {
+            self.run_task(move || match store::download_model(spec) {
+                Ok(()) => Some(format!("Downloaded {}", spec.name)),
+                Err(err) => Some(format!("{err:#}")),
+            });
+        }
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!self.is_busy, Button::new("Refresh"))
+                .clicked()
+            {
+                self.load_model_infos();
+            }
+            if ui.button("Reload Models").clicked() {
+                OcrApp::reload_backends();
+            }
+        });
+    }
+
+    fn show_import(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Import From:");
+            ui.text_edit_singleline(&mut self.import_path)
+                .on_hover_text("Directory with the model files or the models directory of another installation");
+
+            let enabled = !self.is_busy && !self.import_path.trim().is_empty();
+            if ui.add_enabled(enabled, Button::new("Import")).clicked() {
+                let source = PathBuf::from(self.import_path.trim());
+                self.run_task(move || match store::import_models(&source) {
+                    Ok(names) => Some(format!("Imported {}", names.join(", "))),
+                    Err(err) => Some(format!("{err:#}")),
+                });
+            }
+        });
+    }
+}
+
+fn show_status(ui: &mut Ui, info: &ModelInfo) {
+    match &info.status {
+        ModelStatus::Verified => {
+            ui.label(RichText::new("\u{2714} Verified").color(Color32::GREEN));
+        }
+        ModelStatus::Unverified => {
+            ui.label(RichText::new("Unverified").color(Color32::YELLOW))
+                .on_hover_text("The files are not in the manifest, import them to add checksums");
+        }
+        ModelStatus::Bundled => {
+            ui.label("Bundled");
+        }
+        ModelStatus::Missing => {
+            ui.label(RichText::new("\u{2716} Missing").color(Color32::RED))
+                .on_hover_text(info.missing_files.join("\n"));
+        }
+        ModelStatus::Error(err) => {
+            ui.label(RichText::new("\u{2716} Error").color(Color32::RED))
+                .on_hover_text(err);
+        }
+    }
+}
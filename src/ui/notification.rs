@@ -0,0 +1,129 @@
+use egui::{Align2, Color32, Context, Id, RichText};
+
+const MAX_NOTIFICATIONS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotificationLevel {
+    Warning,
+    Error,
+}
+
+impl NotificationLevel {
+    fn get_color(&self) -> Color32 {
+        match self {
+            NotificationLevel::Warning => Color32::ORANGE,
+            NotificationLevel::Error => Color32::RED,
+        }
+    }
+
+    fn get_icon(&self) -> &'static str {
+        match self {
+            NotificationLevel::Warning => "\u{26A0}",
+            NotificationLevel::Error => "\u{2716}",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Notification {
+    level: NotificationLevel,
+    message: String,
+    /// The same message is shown once with the number of times it occurred
+    count: usize,
+}
+
+/// Dismissible messages in the corner of the overlay.
+#[derive(Default)]
+pub struct Notifications {
+    items: Vec<Notification>,
+}
+
+impl Notifications {
+    pub fn add(&mut self, level: NotificationLevel, message: String) {
+        if let Some(index) = self
+            .items
+            .iter()
+            .position(|x| x.level == level && x.message == message)
+        {
+            let mut item = self.items.remove(index);
+            item.count += 1;
+            self.items.push(item);
+            return;
+        }
+
+        self.items.push(Notification {
+            level,
+            message,
+            count: 1,
+        });
+        if self.items.len() > MAX_NOTIFICATIONS {
+            self.items.remove(0);
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        if self.items.is_empty() {
+            return;
+        }
+
+        let mut dismissed = None;
+        let mut dismiss_all = false;
+        egui::Area::new(Id::new("Notifications"))
+            .anchor(Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_max_width(400.0);
+                    for (index, item) in self.items.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui.small_button("\u{1F5D9}").clicked() {
+                                dismissed = Some(index);
+                            }
+                            ui.label(
+                                RichText::new(item.level.get_icon()).color(item.level.get_color()),
+                            );
+                            let mut text = item.message.clone();
+                            if item.count > 1 {
+                                text = format!("{text} ({}x)", item.count);
+                            }
+                            ui.label(text);
+                        });
+                    }
+                    if self.items.len() > 1 && ui.button("Dismiss All").clicked() {
+                        dismiss_all = true;
+                    }
+                });
+            });
+
+        if dismiss_all {
+            self.items.clear();
+        } else if let Some(index) = dismissed {
+            self.items.remove(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add() {
+        let mut notifications = Notifications::default();
+        notifications.add(NotificationLevel::Error, "a".to_string());
+        notifications.add(NotificationLevel::Warning, "b".to_string());
+        notifications.add(NotificationLevel::Error, "a".to_string());
+
+        let messages: Vec<(&str, usize)> = notifications
+            .items
+            .iter()
+            .map(|x| (x.message.as_str(), x.count))
+            .collect();
+        assert_eq!(messages, vec![("b", 1), ("a", 2)]);
+
+        for i in 0..MAX_NOTIFICATIONS {
+            notifications.add(NotificationLevel::Warning, i.to_string());
+        }
+        assert_eq!(notifications.items.len(), MAX_NOTIFICATIONS);
+        assert_eq!(notifications.items[0].message, "0");
+    }
+}
@@ -1,11 +1,17 @@
 use crate::action::OcrPipelineStep;
 use crate::event::event::remove_pipeline_step;
+use crate::ocr::ctc::{CtcConfig, CtcDecoding, Normalization};
+use crate::ocr::image_model::ImageModelConfig;
+use crate::ocr::manga_ocr::Decoding;
+use crate::ocr::tesseract::{TesseractConfig, TesseractLanguage};
+use crate::ocr::{ConfidenceFilter, LowConfidenceAction, OcrBackend};
 use crate::ui::id_item::IdItem;
 use crate::ui::update_queue::enqueue_update;
 use eframe::epaint::Color32;
-use egui::{CollapsingHeader, RichText, Ui};
+use egui::{CollapsingHeader, Grid, Id, RichText, Ui};
 use egui_dnd::{DragDropItem, dnd};
 use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct OcrPipeline {
@@ -60,7 +66,7 @@ impl IdItem<OcrPipelineStep> {
         if self.item.has_parameters() {
             CollapsingHeader::new((&self.item).name())
                 .id_salt(self.id())
-                .show(ui, |ui| self.item.show(ui));
+                .show(ui, |ui| self.item.show(ui, self.id()));
         } else {
             ui.label((&self.item).name());
         }
@@ -68,9 +74,10 @@ impl IdItem<OcrPipelineStep> {
 }
 
 impl OcrPipelineStep {
-    fn show(&mut self, ui: &mut Ui) {
+    fn show(&mut self, ui: &mut Ui, id: Id) {
         match self {
             OcrPipelineStep::ImageProcessing(config) => config.show(ui),
+            OcrPipelineStep::ImageModel(config) => Self::show_image_model(ui, config, id),
             OcrPipelineStep::BoxDetection {
                 threshold,
                 max_box_count,
@@ -78,14 +85,226 @@ impl OcrPipelineStep {
             } => {
                 Self::show_box_detection(ui, threshold, max_box_count, use_capture_image_as_output)
             }
-            _ => {}
+            OcrPipelineStep::OcrStep {
+                backend,
+                decoding,
+                confidence_filter,
+                auto_correct,
+            } => {
+                Self::show_backend(ui, backend);
+                match backend {
+                    OcrBackend::MangaOcr => Self::show_decoding(ui, decoding),
+                    OcrBackend::Tesseract(config) => Self::show_tesseract(ui, config),
+                    OcrBackend::Ctc(config) => Self::show_ctc(ui, config, id),
+                }
+                Self::show_confidence_filter(ui, confidence_filter);
+                ui.checkbox(auto_correct, "Dictionary Correction");
+            }
+            OcrPipelineStep::EnsembleStep {
+                backends,
+                decoding,
+                confidence_filter,
+                auto_correct,
+            } => {
+                Self::show_ensemble_backends(ui, backends, id);
+                if backends.contains(&OcrBackend::MangaOcr) {
+                    Self::show_decoding(ui, decoding);
+                }
+                Self::show_confidence_filter(ui, confidence_filter);
+                ui.checkbox(auto_correct, "Dictionary Correction");
+            }
         }
     }
 
     fn has_parameters(&self) -> bool {
         match self {
-            OcrPipelineStep::ImageProcessing(_) | OcrPipelineStep::BoxDetection { .. } => true,
-            _ => false,
+            OcrPipelineStep::ImageProcessing(_)
+            | OcrPipelineStep::ImageModel(_)
+            | OcrPipelineStep::BoxDetection { .. }
+            | OcrPipelineStep::OcrStep { .. }
+            | OcrPipelineStep::EnsembleStep { .. } => true,
+        }
+    }
+
+    fn show_confidence_filter(ui: &mut Ui, confidence_filter: &mut ConfidenceFilter) {
+        ui.add(
+            egui::Slider::new(&mut confidence_filter.threshold, 0.0..=1.0)
+                .text("Confidence Threshold"),
+        );
+        ui.horizontal(|ui| {
+            ui.label("Low Confidence Boxes:");
+            for action in LowConfidenceAction::iter() {
+                let label = match action {
+                    LowConfidenceAction::Flag => "Flag",
+                    LowConfidenceAction::Drop => "Drop",
+                };
+                ui.selectable_value(&mut confidence_filter.action, action, label);
+            }
+        });
+    }
+
+    fn show_backend(ui: &mut Ui, backend: &mut OcrBackend) {
+        ui.horizontal(|ui| {
+            ui.label("Backend:");
+            for option in OcrBackend::iter() {
+                let selected = std::mem::discriminant(backend) == std::mem::discriminant(&option);
+                if ui.selectable_label(selected, option.get_label()).clicked() && !selected {
+                    *backend = option;
+                }
+            }
+        });
+    }
+
+    fn show_ensemble_backends(ui: &mut Ui, backends: &mut Vec<OcrBackend>, id: Id) {
+        let mut removed = None;
+        for (index, backend) in backends.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                if ui
+                    .button(RichText::new("\u{1F5D9}").color(Color32::RED))
+                    .clicked()
+                {
+                    removed = Some(index);
+                }
+                match backend {
+                    OcrBackend::MangaOcr => {
+                        ui.label(backend.get_label());
+                    }
+                    OcrBackend::Tesseract(config) => {
+                        CollapsingHeader::new("Tesseract")
+                            .id_salt(id.with(("Ensemble Backend", index)))
+                            .show(ui, |ui| Self::show_tesseract(ui, config));
+                    }
+                    OcrBackend::Ctc(config) => {
+                        CollapsingHeader::new("CTC")
+                            .id_salt(id.with(("Ensemble Backend", index)))
+                            .show(ui, |ui| Self::show_ctc(ui, config, id.with(index)));
+                    }
+                }
+            });
+        }
+        if let Some(index) = removed {
+            backends.remove(index);
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Add Backend:");
+            for backend in OcrBackend::iter() {
+                if ui.button(backend.get_label()).clicked() {
+                    backends.push(backend);
+                }
+            }
+        });
+    }
+
+    fn show_tesseract(ui: &mut Ui, config: &mut TesseractConfig) {
+        ui.horizontal(|ui| {
+            ui.label("Language:");
+            for language in TesseractLanguage::iter() {
+                ui.selectable_value(&mut config.language, language, language.get_code());
+            }
+        });
+        ui.add(egui::Slider::new(&mut config.psm, 0..=13).text("Page Segmentation Mode"));
+    }
+
+    fn show_ctc(ui: &mut Ui, config: &mut CtcConfig, id: Id) {
+        Grid::new(("CTC Config", id)).num_columns(2).show(ui, |ui| {
+            ui.label("Model (.onnx):");
+            ui.text_edit_singleline(&mut config.model_path);
+            ui.end_row();
+
+            ui.label("Dictionary:");
+            ui.text_edit_singleline(&mut config.dictionary_path);
+            ui.end_row();
+        });
+        ui.add(egui::Slider::new(&mut config.input_height, 16..=128).text("Input Height"));
+        ui.horizontal(|ui| {
+            ui.label("Normalization:");
+            for normalization in Normalization::iter() {
+                ui.selectable_value(
+                    &mut config.normalization,
+                    normalization,
+                    normalization.get_label(),
+                );
+            }
+        });
+        ui.checkbox(&mut config.bgr, "BGR Input");
+        ui.checkbox(&mut config.rotate_vertical, "Rotate Vertical Text");
+
+        ui.horizontal(|ui| {
+            ui.label("Decoding:");
+            ui.selectable_value(&mut config.decoding, CtcDecoding::Greedy, "Greedy");
+            if ui
+                .selectable_label(
+                    matches!(config.decoding, CtcDecoding::BeamSearch { .. }),
+                    "Beam Search",
+                )
+                .clicked()
+                && config.decoding == CtcDecoding::Greedy
+            {
+                config.decoding = CtcDecoding::BeamSearch { beam_width: 5 };
+            }
+        });
+        if let CtcDecoding::BeamSearch { beam_width } = &mut config.decoding {
+            ui.add(egui::Slider::new(beam_width, 1..=20).text("Beam Width"));
+        }
+    }
+
+    fn show_image_model(ui: &mut Ui, config: &mut ImageModelConfig, id: Id) {
+        Grid::new(("Image Model Config", id))
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Model (.onnx):");
+                ui.text_edit_singleline(&mut config.model_path);
+                ui.end_row();
+            });
+        ui.add(egui::Slider::new(&mut config.scale, 1..=4).text("Scale"))
+            .on_hover_text("Output size of the model relative to its input, 1 for denoising");
+        ui.add(egui::Slider::new(&mut config.tile_size, 0..=1024).text("Tile Size"))
+            .on_hover_text("0 runs the whole text box at once");
+        ui.add(egui::Slider::new(&mut config.tile_overlap, 0..=64).text("Tile Overlap"));
+        ui.horizontal(|ui| {
+            ui.label("Normalization:");
+            for normalization in Normalization::iter() {
+                ui.selectable_value(
+                    &mut config.normalization,
+                    normalization,
+                    normalization.get_label(),
+                );
+            }
+        });
+        ui.checkbox(&mut config.bgr, "BGR Input");
+    }
+
+    fn show_decoding(ui: &mut Ui, decoding: &mut Decoding) {
+        ui.horizontal(|ui| {
+            ui.label("Decoding:");
+            if ui
+                .selectable_label(*decoding == Decoding::Greedy, "Greedy")
+                .clicked()
+            {
+                *decoding = Decoding::Greedy;
+            }
+            if ui
+                .selectable_label(
+                    matches!(decoding, Decoding::BeamSearch { .. }),
+                    "Beam Search",
+                )
+                .clicked()
+                && *decoding == Decoding::Greedy
+            {
+                *decoding = Decoding::default_beam_search();
+            }
+        });
+
+        if let Decoding::BeamSearch {
+            beam_width,
+            length_penalty,
+            n_best,
+        } = decoding
+        {
+            ui.add(egui::Slider::new(beam_width, 1..=10).text("Beam Width"));
+            ui.add(egui::Slider::new(length_penalty, 0.0..=2.0).text("Length Penalty"));
+            ui.add(egui::Slider::new(n_best, 1..=*beam_width).text("N-Best"));
         }
     }
 
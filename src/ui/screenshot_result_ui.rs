@@ -1,16 +1,26 @@
 use super::mouse_hover::get_frame_mouse_position;
 use crate::action::{self, ResultData, ScreenshotResult, get_translation};
-use crate::event::event::update_ocr_result;
+use crate::event::event::{correct_ocr_result, open_breakdown, undo_correction};
+use crate::jpn::JpnData;
+use crate::jpn::kanji::get_kanji_data;
 use crate::ocr::BackendResult;
+use crate::ui::kanji_detail_ui::show_kanji_link;
+use crate::ui::settings::AppSettings;
 use crate::ui::shutdown::TASK_TRACKER;
 use crate::ui::update_queue::enqueue_update;
 use eframe::epaint::StrokeKind;
 use egui::{Align2, Color32, Context, Id, Pos2, Rect, RichText, Sense, Vec2, Window};
 use itertools::Itertools;
+use std::ops::Range;
 use std::time::{Duration, Instant};
 
 impl ScreenshotResult {
-    pub fn show(&mut self, ctx: &egui::Context, screenshot_rect: &Rect) -> bool {
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        screenshot_rect: &Rect,
+        settings: &AppSettings,
+    ) -> bool {
         self.update_translation(ctx);
 
         let frame_mouse_position = get_frame_mouse_position(ctx).unwrap_or_default();
@@ -30,13 +40,35 @@ impl ScreenshotResult {
                     ui.set_height(rect.height());
                     let contains = rect.contains(frame_mouse_position);
 
+                    let hovered_word = if settings.char_hover && contains && !rect_is_clicked {
+                        let position = (frame_mouse_position - rect.min) * ctx.zoom_factor();
+                        result
+                            .get_char_index_at(position.x, position.y)
+                            .and_then(|index| result.get_jpn_data_at_char(index))
+                    } else {
+                        None
+                    };
+                    if let Some((start, jpn_data)) = hovered_word {
+                        highlight_chars(ui, &rect, result, start..start + jpn_data.words.len());
+                    }
+
                     let is_active = contains || rect_is_clicked;
                     if is_active {
-                        show_ocr_info_window(ctx, &rect, result, i, rect_is_clicked);
+                        show_ocr_info_window(
+                            ctx,
+                            &rect,
+                            result,
+                            i,
+                            rect_is_clicked,
+                            hovered_word.map(|(_, x)| x),
+                            settings,
+                        );
                     }
 
                     let color = if is_active {
                         Color32::GREEN
+                    } else if result.is_low_confidence() {
+                        LOW_CONFIDENCE_COLOR
                     } else {
                         Color32::BLUE
                     };
@@ -162,6 +194,8 @@ fn show_ocr_info_window(
     result: &ResultData,
     index: usize,
     rect_is_clicked: bool,
+    hovered_word: Option<&JpnData>,
+    settings: &AppSettings,
 ) {
     let window = create_info_window(ctx, rect, result, index);
 
@@ -171,6 +205,30 @@ fn show_ocr_info_window(
             ui.separator();
         }
 
+        if settings.show_difficulty
+            && let Some(difficulty) = result.difficulty
+        {
+            ui.label(format!("Difficulty: {difficulty:.0}"));
+        }
+
+        if let Some(correction) = &result.correction {
+            ui.horizontal(|ui| {
+                ui.label(format!("Corrected: {}", correction.get_summary()));
+                if rect_is_clicked && ui.button("Undo").clicked() {
+                    enqueue_update(move |_, app| undo_correction(app, index));
+                }
+            });
+        }
+
+        if let Some(confidence) = result.confidence {
+            let text = RichText::new(format!("Confidence: {:.0}%", confidence * 100.0));
+            if result.is_low_confidence() {
+                ui.label(text.color(LOW_CONFIDENCE_COLOR));
+            } else {
+                ui.label(text);
+            }
+        }
+
         if rect_is_clicked {
             let selected_kanji_id = Id::new("selected_kanji_id");
             let selected_kanji_index: usize = ui
@@ -188,6 +246,12 @@ fn show_ocr_info_window(
                         .underline()
                         .size(TEXT_SIZE);
 
+                    if let Some(color) = settings.level_color.get_kanji_color(*ocr_char) {
+                        text = text.color(color);
+                    }
+                    if result.is_low_confidence_char(index) {
+                        text = text.background_color(LOW_CONFIDENCE_BACKGROUND);
+                    }
                     if index == selected_kanji_index {
                         text = text.color(Color32::RED);
                     }
@@ -201,60 +265,124 @@ fn show_ocr_info_window(
             });
             ui.spacing_mut().item_spacing = old_item_spacing;
 
-            match &result.backend_result {
-                BackendResult::MangaOcr(top) => {
-                    ui.separator();
-
-                    ui.spacing_mut().item_spacing = Vec2::new(0.0, 0.0);
-
-                    if let Some(kanji_result) = top.get(selected_kanji_index) {
-                        ui.horizontal_wrapped(|ui| {
-                            for kanji_conf in kanji_result.iter() {
-                                let kanji = kanji_conf.kanji.clone();
-                                let mut text = RichText::new(kanji.clone()).size(TEXT_SIZE);
-
-                                if let Some(selected_char) = ocr_chars.get(selected_kanji_index)
-                                    && kanji_conf.kanji.eq(&selected_char.to_string())
-                                {
-                                    text = text.color(Color32::RED);
-                                };
-
-                                let response1 = ui.label(text);
-
-                                if response1.clicked() {
-                                    let updated_ocr = replace_nth_char_safe(
-                                        &result.ocr,
-                                        selected_kanji_index,
-                                        kanji.chars().next().unwrap(),
-                                    );
-
-                                    enqueue_update(move |_, app| {
-                                        update_ocr_result(app, index, updated_ocr)
-                                    });
-                                }
+            ui.horizontal(|ui| {
+                if let Some(selected_char) = ocr_chars.get(selected_kanji_index)
+                    && get_kanji_data(*selected_char).is_some()
+                {
+                    ui.label("Kanji Details:");
+                    show_kanji_link(ui, *selected_char);
+                }
+
+                if ui.button("Breakdown").clicked() {
+                    let result = result.clone();
+                    enqueue_update(move |_, app| open_breakdown(app, result));
+                }
+            });
+
+            if let Some(top) = result.backend_result.get_top_results() {
+                ui.separator();
+
+                ui.spacing_mut().item_spacing = Vec2::new(0.0, 0.0);
+
+                if let Some(kanji_result) = top.get(selected_kanji_index) {
+                    ui.horizontal_wrapped(|ui| {
+                        for kanji_conf in kanji_result.iter() {
+                            let kanji = kanji_conf.kanji.clone();
+                            let mut text = RichText::new(kanji.clone()).size(TEXT_SIZE);
+
+                            if let Some(selected_char) = ocr_chars.get(selected_kanji_index)
+                                && kanji_conf.kanji.eq(&selected_char.to_string())
+                            {
+                                text = text.color(Color32::RED);
+                            };
+
+                            let response1 = ui
+                                .label(text)
+                                .on_hover_text(format!("{:.1}%", kanji_conf.confidence * 100.0));
+
+                            if response1.clicked() {
+                                let updated_ocr = replace_nth_char_safe(
+                                    &result.ocr,
+                                    selected_kanji_index,
+                                    kanji.chars().next().unwrap(),
+                                );
+
+                                enqueue_update(move |_, app| {
+                                    correct_ocr_result(app, index, updated_ocr)
+                                });
                             }
-                        });
+                        }
+                    });
+                }
+            }
+
+            if let BackendResult::MangaOcrBeam(beam) = &result.backend_result
+                && beam.hypotheses.len() > 1
+            {
+                ui.separator();
+                ui.spacing_mut().item_spacing = old_item_spacing;
+                ui.label("Hypotheses:");
+
+                for hypothesis in &beam.hypotheses {
+                    let mut text =
+                        RichText::new(format!("{} ({:.2})", hypothesis.text, hypothesis.score));
+                    if hypothesis.text == result.ocr {
+                        text = text.color(Color32::RED);
+                    }
+
+                    if ui.label(text).clicked() {
+                        let updated_ocr = hypothesis.text.clone();
+                        enqueue_update(move |_, app| correct_ocr_result(app, index, updated_ocr));
+                    }
+                }
+            }
+
+            if let BackendResult::Ensemble(ensemble) = &result.backend_result {
+                ui.separator();
+                ui.spacing_mut().item_spacing = old_item_spacing;
+                ui.label("Backends:");
+
+                for backend_text in &ensemble.texts {
+                    let confidence = backend_text
+                        .confidence
+                        .map(|x| format!(" ({:.0}%)", x * 100.0))
+                        .unwrap_or_default();
+                    let mut text = RichText::new(format!(
+                        "{}: {}{}",
+                        backend_text.backend, backend_text.text, confidence
+                    ));
+                    if backend_text.text == result.ocr {
+                        text = text.color(Color32::RED);
+                    }
+
+                    if ui.label(text).clicked() {
+                        let updated_ocr = backend_text.text.clone();
+                        enqueue_update(move |_, app| correct_ocr_result(app, index, updated_ocr));
                     }
                 }
-                BackendResult::Unknown => {}
             }
         } else {
             let id = Id::new("Scroll Y");
             let index = ui.data(|map| map.get_temp(id)).unwrap_or_default();
-            let selected_jpn_data = result.get_jpn_data_with_info_by_index(index);
+            let selected_jpn_data = hovered_word
+                .filter(|x| x.has_kanji_data())
+                .or_else(|| result.get_jpn_data_with_info_by_index(index));
             for jpn in &result.jpn {
                 ui.spacing_mut().item_spacing = Vec2::new(0.0, 0.0);
                 ui.horizontal_wrapped(|ui| {
                     for jpn_data in jpn {
-                        let kanji = jpn_data.get_kanji();
-                        let mut text = get_info_text(&kanji);
-                        if jpn_data.has_kanji_data() {
-                            text = text.underline();
-                        }
-                        if selected_jpn_data == Some(jpn_data) {
-                            text = text.color(Color32::RED);
-                        }
-                        ui.label(text);
+                        settings.furigana.show_word(ui, jpn_data, |mut text| {
+                            if jpn_data.has_kanji_data() {
+                                text = text.underline();
+                            }
+                            if let Some(color) = settings.level_color.get_word_color(jpn_data) {
+                                text = text.color(color);
+                            }
+                            if selected_jpn_data == Some(jpn_data) {
+                                text = text.color(Color32::RED);
+                            }
+                            text
+                        });
                     }
                 });
             }
@@ -262,12 +390,28 @@ fn show_ocr_info_window(
             if let Some(info) = selected_jpn_data {
                 ui.separator();
                 show_jpn_data_info(ui, info);
-                update_kanji_statistic(ui, info);
+                update_kanji_statistic(ui, info, &result.ocr);
             }
         }
     });
 }
 
+/// Marks the characters of the hovered word on the page.
+fn highlight_chars(ui: &egui::Ui, rect: &Rect, result: &ResultData, chars: Range<usize>) {
+    let zoom_factor = ui.ctx().zoom_factor();
+    for char_rect in result.char_rects.get(chars).unwrap_or_default() {
+        let char_rect = Rect::from_min_size(
+            rect.min + Vec2::new(char_rect.x as f32, char_rect.y as f32) / zoom_factor,
+            Vec2::new(char_rect.w as f32, char_rect.h as f32) / zoom_factor,
+        );
+        ui.painter().rect_filled(
+            char_rect,
+            0.0,
+            Color32::from_rgba_unmultiplied(255, 255, 0, 60),
+        );
+    }
+}
+
 fn replace_nth_char_safe(s: &str, idx: usize, newchar: char) -> String {
     s.chars()
         .enumerate()
@@ -317,7 +461,7 @@ pub fn show_jpn_data_info(ui: &mut egui::Ui, info: &crate::jpn::JpnData) {
     }
 }
 
-fn update_kanji_statistic(ui: &mut egui::Ui, info: &crate::jpn::JpnData) {
+fn update_kanji_statistic(ui: &mut egui::Ui, info: &crate::jpn::JpnData, ocr: &str) {
     let id = Id::new("show_kanji_timer");
     let kanji_timer = ui.data(|x| x.get_temp::<KanjiStatisticTimer>(id));
 
@@ -326,9 +470,12 @@ fn update_kanji_statistic(ui: &mut egui::Ui, info: &crate::jpn::JpnData) {
             timer.statistic_updated = true;
             ui.data_mut(|x| x.insert_temp(id, timer));
             let kanji = info.get_kanji();
+            let word = info.get_dictionary_form();
+            let ocr = ocr.to_string();
 
             TASK_TRACKER.spawn(async move {
                 let _ = action::increment_kanji_statistic(&kanji);
+                action::record_word_lookup(&word, &ocr);
             });
             return;
         }
@@ -358,8 +505,10 @@ impl KanjiStatisticTimer {
 }
 
 const TEXT_SIZE: f32 = 20.0;
+const LOW_CONFIDENCE_COLOR: Color32 = Color32::from_rgb(255, 140, 0);
+const LOW_CONFIDENCE_BACKGROUND: Color32 = Color32::from_rgba_premultiplied(120, 60, 0, 160);
 
-fn get_info_text(text: impl Into<String>) -> RichText {
+pub fn get_info_text(text: impl Into<String>) -> RichText {
     RichText::new(text).size(TEXT_SIZE)
 }
 
@@ -1,12 +1,26 @@
 use super::background_rect::start_ocr_id;
 use crate::action::OcrPipelineStep;
 use crate::event::event::{reset_ui, update_decorations};
+use crate::model::lifecycle::set_idle_timeout;
+use crate::ocr::ctc::CtcConfig;
+use crate::ocr::image_model::ImageModelConfig;
+use crate::ocr::manga_ocr::Decoding;
+use crate::ocr::preprocess::PreprocessConfig;
+use crate::ocr::tesseract::TesseractConfig;
+use crate::ocr::{ConfidenceFilter, OcrBackend};
+use crate::ui::app::OcrApp;
+use crate::ui::backend_status::Backend;
+use crate::ui::furigana::FuriganaConfig;
 use crate::ui::id_item::IdItemVec;
 use crate::ui::image_display::ImageDisplay;
+use crate::ui::inference_config::InferenceConfig;
+use crate::ui::level_color::LevelColorMode;
 use crate::ui::pipeline_config::OcrPipeline;
 use crate::ui::shortcut::ShortcutManager;
+use crate::ui::stroke_order::StrokeOrderConfig;
 use crate::ui::update_queue::enqueue_update;
-use egui::{Button, CollapsingHeader, Color32, Context, Id, RichText, Spinner, Ui};
+use egui::{CollapsingHeader, Color32, Context, RichText, Ui};
+use std::time::Duration;
 
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
@@ -20,6 +34,8 @@ pub struct AppSettings {
     pub auto_restart_ocr: bool,
     pub auto_restart_delay_ms: u64,
     pub hover_delay_ms: u64,
+    /// Minutes without OCR after which the models are unloaded, 0 to keep them loaded
+    pub unload_idle_minutes: u64,
 
     //OCR Settings
     pub show_debug_cursor: bool,
@@ -33,6 +49,14 @@ pub struct AppSettings {
     pub new_step_combobox: Vec<OcrPipelineStep>,
 
     pub quick_area_pick_mode: bool,
+
+    pub stroke_order: StrokeOrderConfig,
+    pub furigana: FuriganaConfig,
+    pub level_color: LevelColorMode,
+    pub show_difficulty: bool,
+    pub char_hover: bool,
+
+    pub inference: InferenceConfig,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Default)]
@@ -40,10 +64,20 @@ pub struct WindowState {
     pub show_pipeline_config: bool,
     pub show_statistics: bool,
     pub show_history: bool,
+    pub show_dictionary: bool,
+    pub show_kanji_detail: bool,
+    pub show_breakdown: bool,
+    pub show_dashboard: bool,
+    pub show_corrections: bool,
+    pub show_models: bool,
     pub show_debug_images: bool,
 }
 
 impl AppSettings {
+    pub fn init_idle_timeout(&self) {
+        set_idle_timeout(Duration::from_secs(self.unload_idle_minutes * 60));
+    }
+
     pub fn get_current_pipeline(&self) -> &OcrPipeline {
         self.pipeline_configs
             .get(self.selected_pipeline)
@@ -59,14 +93,45 @@ impl AppSettings {
 
 impl Default for AppSettings {
     fn default() -> Self {
-        let vec = vec![
-            OcrPipelineStep::ImageProcessing(PreprocessConfig::default()),
+        let mut vec: Vec<OcrPipelineStep> = PreprocessConfig::get_defaults()
+            .into_iter()
+            .map(OcrPipelineStep::ImageProcessing)
+            .collect();
+        vec.extend([
+            OcrPipelineStep::ImageModel(ImageModelConfig::default()),
             OcrPipelineStep::BoxDetection {
                 threshold: 0.5,
                 max_box_count: 10,
                 use_capture_image_as_output: true,
             },
-        ];
+            OcrPipelineStep::OcrStep {
+                backend: OcrBackend::MangaOcr,
+                decoding: Decoding::default_beam_search(),
+                confidence_filter: ConfidenceFilter::default(),
+                auto_correct: true,
+            },
+            OcrPipelineStep::OcrStep {
+                backend: OcrBackend::Tesseract(TesseractConfig::default()),
+                decoding: Decoding::default(),
+                confidence_filter: ConfidenceFilter::default(),
+                auto_correct: true,
+            },
+            OcrPipelineStep::OcrStep {
+                backend: OcrBackend::Ctc(CtcConfig::default()),
+                decoding: Decoding::default(),
+                confidence_filter: ConfidenceFilter::default(),
+                auto_correct: true,
+            },
+            OcrPipelineStep::EnsembleStep {
+                backends: vec![
+                    OcrBackend::MangaOcr,
+                    OcrBackend::Tesseract(TesseractConfig::default()),
+                ],
+                decoding: Decoding::default(),
+                confidence_filter: ConfidenceFilter::default(),
+                auto_correct: true,
+            },
+        ]);
 
         Self {
             shortcut: ShortcutManager::default(),
@@ -77,6 +142,7 @@ impl Default for AppSettings {
             auto_restart_ocr: true,
             auto_restart_delay_ms: 1000,
             hover_delay_ms: 1000,
+            unload_idle_minutes: 10,
             show_debug_cursor: false,
             debug_images: ImageDisplay::default(),
             pipeline_configs: vec![
@@ -90,6 +156,12 @@ impl Default for AppSettings {
             new_step_selected: OcrPipelineStep::ImageProcessing(PreprocessConfig::default()),
             new_step_combobox: vec,
             quick_area_pick_mode: false,
+            stroke_order: StrokeOrderConfig::default(),
+            furigana: FuriganaConfig::default(),
+            level_color: LevelColorMode::default(),
+            show_difficulty: false,
+            char_hover: true,
+            inference: InferenceConfig::default(),
         }
     }
 }
@@ -113,13 +185,13 @@ impl AppSettings {
             .resizable(false);
         window.show(ctx, |ui| {
             ui.horizontal(|ui| {
-                Backend::MangaOcr.get_status_ui(ui);
-                let enabled = Backend::MangaOcr.get_status(ui) == BackendStatus::Ready;
-                if ui.add_enabled(enabled, Button::new("Start OCR")).clicked() {
+                // Failed components are skipped, e.g. the whole region is used without detector
+                if ui.button("Start OCR").clicked() {
                     ui.data_mut(|map| map.insert_temp(start_ocr_id(), true));
                 }
                 ui.checkbox(&mut self.auto_restart_ocr, "Auto Restart OCR");
             });
+            Backend::show_statuses(ui);
 
             self.show_pipeline_selector(ui);
 
@@ -127,6 +199,10 @@ impl AppSettings {
 
             self.show_window_settings(ui, window_state);
             self.shortcut.show_config(ui);
+            self.inference.show_config(ui);
+            self.stroke_order.show_config(ui);
+            self.furigana.show_config(ui);
+            self.level_color.show_config(ui);
 
             self.show_debug_config(ui, window_state);
 
@@ -224,6 +300,10 @@ impl AppSettings {
 
             ui.checkbox(&mut window_state.show_history, "Show History");
             ui.checkbox(&mut window_state.show_statistics, "Show Statistics");
+            ui.checkbox(&mut window_state.show_dictionary, "Show Dictionary");
+            ui.checkbox(&mut window_state.show_dashboard, "Show Dashboard");
+            ui.checkbox(&mut window_state.show_corrections, "Show Corrections");
+            ui.checkbox(&mut self.show_difficulty, "Show Difficulty");
         });
     }
 
@@ -242,10 +322,36 @@ impl AppSettings {
                 );
             });
 
+            ui.checkbox(&mut self.char_hover, "Character Hover")
+                .on_hover_text(
+                    "Look up the word under the mouse instead of scrolling through the words",
+                );
+
+            let response = ui
+                .add(
+                    egui::Slider::new(&mut self.unload_idle_minutes, 0..=120)
+                        .text("Unload Models After (min)"),
+                )
+                .on_hover_text(
+                    "Frees the memory of the models when no OCR ran for this time, 0 to keep them loaded",
+                );
+            if response.changed() {
+                self.init_idle_timeout();
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Reload Models").clicked() {
+                    OcrApp::reload_backends();
+                }
+                if ui.button("Unload Models").clicked() {
+                    OcrApp::unload_backends();
+                }
+            });
+
             ui.checkbox(
                 &mut window_state.show_pipeline_config,
                 "Show OCR Pipeline Config",
             );
+            ui.checkbox(&mut window_state.show_models, "Show Models");
         });
     }
 
@@ -271,81 +377,3 @@ impl AppSettings {
         });
     }
 }
-
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub enum BackendStatus {
-    Loading,
-    Ready,
-    Running,
-    Error,
-}
-
-impl BackendStatus {
-    fn get_ui(&self, ui: &mut egui::Ui) {
-        match self {
-            BackendStatus::Loading => ui.add(Spinner::new()),
-            BackendStatus::Ready | BackendStatus::Running => {
-                ui.label(RichText::from("\u{2714}").color(Color32::GREEN))
-            }
-            BackendStatus::Error => ui.label(RichText::from("\u{2716}").color(Color32::RED)),
-        };
-    }
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum Backend {
-    MangaOcr,
-}
-
-impl Backend {
-    fn get_id(self: &Backend) -> Id {
-        match self {
-            Backend::MangaOcr => Id::new("MangaOcr_Status"),
-        }
-    }
-
-    fn get_status(&self, ui: &egui::Ui) -> BackendStatus {
-        ui.data(|data| {
-            data.get_temp(self.get_id())
-                .unwrap_or_else(|| BackendStatus::Loading)
-        })
-    }
-
-    fn get_status_ui(&self, ui: &mut egui::Ui) {
-        self.get_status(ui).get_ui(ui);
-    }
-
-    pub fn set_status(&self, ctx: &egui::Context, status: BackendStatus) {
-        ctx.data_mut(|data| data.insert_temp(self.get_id(), status));
-    }
-}
-
-#[derive(serde::Deserialize, serde::Serialize, PartialEq, Debug, Clone)]
-pub enum PreprocessConfig {
-    SharpenGaussian { sigma: f32, amount: f32 },
-}
-
-impl Default for PreprocessConfig {
-    fn default() -> Self {
-        PreprocessConfig::SharpenGaussian {
-            sigma: 5.0,
-            amount: 10.0,
-        }
-    }
-}
-
-impl PreprocessConfig {
-    pub fn show(&mut self, ui: &mut Ui) {
-        match self {
-            PreprocessConfig::SharpenGaussian { sigma, amount } => {
-                ui.horizontal(|ui| {
-                    ui.add(egui::Slider::new(sigma, 0.1..=100.0).text("Sigma"));
-                });
-
-                ui.horizontal(|ui| {
-                    ui.add(egui::Slider::new(amount, -100.0..=100.0).text("Amount"));
-                });
-            }
-        }
-    }
-}
@@ -0,0 +1,159 @@
+use crate::jpn::kanjivg::{Stroke, StrokeState, VIEW_BOX_SIZE, clear_stroke_cache, get_strokes};
+use egui::{CollapsingHeader, Color32, Pos2, Rect, Sense, Shape, Stroke as PenStroke, Ui, Vec2};
+use strum::{EnumIter, IntoEnumIterator};
+
+const STROKE_SECONDS: f64 = 0.6;
+const PAUSE_SECONDS: f64 = 1.0;
+
+#[derive(serde::Deserialize, serde::Serialize, Default, Clone)]
+#[serde(default)]
+pub struct StrokeOrderConfig {
+    /// Directory with KanjiVG svg files or a single file kanjivg-*.xml
+    pub kanjivg_path: String,
+    pub mode: StrokeOrderMode,
+}
+
+#[derive(
+    serde::Deserialize, serde::Serialize, Default, Clone, Copy, PartialEq, Debug, EnumIter,
+)]
+pub enum StrokeOrderMode {
+    #[default]
+    Animated,
+    StepByStep,
+}
+
+impl StrokeOrderMode {
+    fn get_label(&self) -> &'static str {
+        match self {
+            StrokeOrderMode::Animated => "Animated",
+            StrokeOrderMode::StepByStep => "Step by Step",
+        }
+    }
+}
+
+impl StrokeOrderConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.kanjivg_path.trim().is_empty()
+    }
+
+    pub fn show_config(&mut self, ui: &mut Ui) {
+        CollapsingHeader::new("Stroke Order Config").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("KanjiVG Path:");
+                let response = ui
+                    .text_edit_singleline(&mut self.kanjivg_path)
+                    .on_hover_text("Directory with KanjiVG svg files or the kanjivg-*.xml file");
+                if response.changed() {
+                    clear_stroke_cache();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                for mode in StrokeOrderMode::iter() {
+                    ui.selectable_value(&mut self.mode, mode, mode.get_label());
+                }
+            });
+        });
+    }
+
+    pub fn show_stroke_order(&self, ui: &mut Ui, kanji: char, size: f32) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        match get_strokes(self.kanjivg_path.trim(), kanji) {
+            StrokeState::Loading => {
+                ui.spinner();
+                ui.ctx().request_repaint();
+            }
+            StrokeState::Loaded(strokes) => match self.mode {
+                StrokeOrderMode::Animated => show_animated(ui, &strokes, size),
+                StrokeOrderMode::StepByStep => show_steps(ui, &strokes, size / 2.0),
+            },
+            StrokeState::Error(err) => {
+                ui.label(format!("No stroke order: {err}"));
+            }
+        }
+    }
+}
+
+fn show_animated(ui: &mut Ui, strokes: &[Stroke], size: f32) {
+    let stroke_count = strokes.len() as f64;
+    let duration = stroke_count * STROKE_SECONDS + PAUSE_SECONDS;
+    let elapsed = (ui.input(|i| i.time) % duration) / STROKE_SECONDS;
+
+    let (rect, _) = ui.allocate_exact_size(Vec2::splat(size), Sense::hover());
+    draw_background(ui, rect);
+
+    for (index, stroke) in strokes.iter().enumerate() {
+        let progress = (elapsed - index as f64).clamp(0.0, 1.0) as f32;
+        if progress <= 0.0 {
+            break;
+        }
+        let color = if progress < 1.0 {
+            Color32::RED
+        } else {
+            ui.visuals().strong_text_color()
+        };
+        draw_stroke(ui, rect, stroke, progress, color);
+        draw_stroke_number(ui, rect, stroke, index + 1);
+    }
+
+    ui.ctx().request_repaint();
+}
+
+fn show_steps(ui: &mut Ui, strokes: &[Stroke], size: f32) {
+    ui.horizontal_wrapped(|ui| {
+        for step in 0..strokes.len() {
+            let (rect, _) = ui.allocate_exact_size(Vec2::splat(size), Sense::hover());
+            draw_background(ui, rect);
+
+            for (index, stroke) in strokes.iter().enumerate().take(step + 1) {
+                let color = if index == step {
+                    Color32::RED
+                } else {
+                    ui.visuals().weak_text_color()
+                };
+                draw_stroke(ui, rect, stroke, 1.0, color);
+            }
+        }
+    });
+}
+
+fn draw_background(ui: &Ui, rect: Rect) {
+    let painter = ui.painter();
+    let guide = ui.visuals().widgets.noninteractive.bg_stroke;
+    painter.rect_stroke(rect, 0.0, guide, egui::StrokeKind::Inside);
+    painter.line_segment([rect.center_top(), rect.center_bottom()], guide);
+    painter.line_segment([rect.left_center(), rect.right_center()], guide);
+}
+
+fn draw_stroke(ui: &Ui, rect: Rect, stroke: &Stroke, progress: f32, color: Color32) {
+    let point_count = ((stroke.len() as f32 * progress).ceil() as usize).max(1);
+    let points: Vec<Pos2> = stroke
+        .iter()
+        .take(point_count)
+        .map(|p| to_screen(rect, *p))
+        .collect();
+
+    let width = rect.width() / VIEW_BOX_SIZE * 3.0;
+    ui.painter()
+        .add(Shape::line(points, PenStroke::new(width, color)));
+}
+
+fn draw_stroke_number(ui: &Ui, rect: Rect, stroke: &Stroke, number: usize) {
+    if let Some(start) = stroke.first() {
+        ui.painter().text(
+            to_screen(rect, *start),
+            egui::Align2::RIGHT_BOTTOM,
+            number.to_string(),
+            egui::FontId::proportional(rect.width() / 12.0),
+            ui.visuals().weak_text_color(),
+        );
+    }
+}
+
+fn to_screen(rect: Rect, [x, y]: [f32; 2]) -> Pos2 {
+    let scale = rect.width() / VIEW_BOX_SIZE;
+    rect.min + Vec2::new(x * scale, y * scale)
+}